@@ -0,0 +1,257 @@
+//! # `matroskin-derive`
+//!
+//! Derive macros for [matroskin](https://crates.io/crates/matroskin).
+//!
+//! Usually used through re-export: `matroskin::command::Command`.
+//!
+//! - Item: [macro@Command]
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Fields, Ident, LitStr, Path, Type, parse_macro_input, spanned::Spanned,
+};
+
+/// Derive `matroskin::command::Command`
+///
+/// Attributes (`#[command(...)]`):
+/// - `name = "set.miner.pools"`: command name, **required**
+/// - `secured`: include AuthData in request
+/// - `encrypted`: encrypt params data
+/// - `response = Type`: message type of `Response<Type>`, `String` by default
+/// - `min_api = "3.0.3"`: minimal api version which supports command
+/// - `idempotent`: safe to retry, always set for `get.*` commands
+/// - `params_with = path::to::fn`: custom `fn(&Self) -> Result<Option<String>>`
+/// - `snapshot = "{...}"`: expected request JSON without AuthData
+/// - `no_test`: don't generate serialization snapshot test
+///
+/// Params are taken from struct shape:
+/// - unit struct: no params
+/// - newtype struct: inner field
+/// - any other struct: struct itself (should be `Serialize`)
+///
+/// Generated snapshot test builds request from `Default::default()`,
+/// so struct should be `Default` unless `no_test` is set.
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::command::Command;
+///
+/// #[derive(Debug, Default, Command)]
+/// #[command(name = "set.system.led", secured, snapshot = r#"{"cmd":"set.system.led","param":"auto"}"#)]
+/// pub struct SetSystemLed(pub String);
+/// ```
+#[proc_macro_derive(Command, attributes(command))]
+pub fn derive_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Parsed `#[command(...)]` attributes
+#[derive(Default)]
+struct CommandAttrs {
+    name: Option<LitStr>,
+    secured: bool,
+    encrypted: bool,
+    response: Option<Type>,
+    min_api: Option<LitStr>,
+    idempotent: bool,
+    params_with: Option<Path>,
+    snapshot: Option<LitStr>,
+    no_test: bool,
+}
+
+impl CommandAttrs {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("command")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    out.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("secured") {
+                    out.secured = true;
+                } else if meta.path.is_ident("encrypted") {
+                    out.encrypted = true;
+                } else if meta.path.is_ident("response") {
+                    out.response = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("min_api") {
+                    out.min_api = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("idempotent") {
+                    out.idempotent = true;
+                } else if meta.path.is_ident("params_with") {
+                    out.params_with = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("snapshot") {
+                    out.snapshot = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("no_test") {
+                    out.no_test = true;
+                } else {
+                    return Err(meta.error("unknown `command` attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let attrs = CommandAttrs::parse(&input)?;
+    let ident = &input.ident;
+    let name = attrs.name.clone().ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "missing `#[command(name = \"...\")]` attribute",
+        )
+    })?;
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "`Command` can be derived only for structs",
+        ));
+    };
+
+    let (params_ty, params_body) = match &data.fields {
+        Fields::Unit => (quote!(()), quote!(::core::result::Result::Ok(None))),
+        Fields::Unnamed(f) if f.unnamed.len() == 1 => {
+            let ty = &f.unnamed[0].ty;
+            (
+                quote!(#ty),
+                quote!(::matroskin::command::params_to_string(&self.0)),
+            )
+        }
+        _ => (
+            quote!(Self),
+            quote!(::matroskin::command::params_to_string(self)),
+        ),
+    };
+    let params_body = match &attrs.params_with {
+        Some(path) => quote!(#path(self)),
+        None => params_body,
+    };
+    let response = match &attrs.response {
+        Some(ty) => quote!(#ty),
+        None => quote!(::std::string::String),
+    };
+    let min_api = match &attrs.min_api {
+        Some(lit) => {
+            let [major, minor, patch] = parse_version(lit)?;
+            quote!(::core::option::Option::Some(
+                ::matroskin::capabilities::ApiVersion::new(#major, #minor, #patch)
+            ))
+        }
+        None => quote!(::core::option::Option::None),
+    };
+    let secured = attrs.secured;
+    let encrypted = attrs.encrypted;
+    let idempotent = attrs.idempotent || name.value().starts_with("get.");
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let test = if attrs.no_test {
+        quote!()
+    } else {
+        snapshot_test(ident, &attrs)
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::matroskin::command::Command for #ident #ty_generics #where_clause {
+            type Params = #params_ty;
+            type Response = ::matroskin::response::Response<#response>;
+            const CMD_NAME: &'static str = #name;
+            const SECURED: bool = #secured;
+            const ENCRYPTED: bool = #encrypted;
+            const IDEMPOTENT: bool = #idempotent;
+            const MIN_API: ::core::option::Option<::matroskin::capabilities::ApiVersion> = #min_api;
+            fn params(&self) -> ::matroskin::error::Result<::core::option::Option<::std::string::String>> {
+                #params_body
+            }
+        }
+
+        #test
+    })
+}
+
+/// Generate serialization snapshot test for command
+fn snapshot_test(ident: &Ident, attrs: &CommandAttrs) -> TokenStream2 {
+    let module = format_ident!("__matroskin_command_{}", to_snake_case(&ident.to_string()));
+    let encrypted = attrs.encrypted;
+    let snapshot = match &attrs.snapshot {
+        Some(s) => quote! {
+            if !#encrypted {
+                let request = Command::to_request(&cmd, None).unwrap();
+                assert_eq!(serde_json::to_string(&request).unwrap(), #s);
+            }
+        },
+        None => quote!(),
+    };
+    quote! {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod #module {
+            use ::matroskin::{account::Account, auth_data::AuthData, command::Command};
+            use ::matroskin::serde_json;
+
+            use super::*;
+
+            #[test]
+            fn request_snapshot() {
+                let cmd = <#ident as ::core::default::Default>::default();
+                let name = <#ident as Command>::CMD_NAME;
+
+                let auth = AuthData::for_cmd(name, Account::Super, "super", "snapshot").unwrap();
+                let request = serde_json::to_value(Command::to_request(&cmd, Some(auth)).unwrap()).unwrap();
+                assert_eq!(request["cmd"], name);
+                assert_eq!(request["account"], "super");
+                assert!(request["token"].is_string());
+
+                let plain = Command::params(&cmd).unwrap();
+                match &plain {
+                    Some(plain) if <#ident as Command>::ENCRYPTED => assert_ne!(&request["param"], plain),
+                    Some(plain) => assert_eq!(&request["param"], plain),
+                    None => assert!(request.get("param").is_none()),
+                }
+                if <#ident as Command>::ENCRYPTED {
+                    assert!(Command::to_request(&cmd, None).is_err());
+                }
+
+                #snapshot
+            }
+        }
+    }
+}
+
+/// `"3.0.3"` -> `[3, 0, 3]`, missing parts are `0`
+fn parse_version(lit: &LitStr) -> syn::Result<[u16; 3]> {
+    let mut out = [0u16; 3];
+    let value = lit.value();
+    let parts: Vec<_> = value.split('.').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return Err(syn::Error::new(
+            lit.span(),
+            "expected version like \"3.0.3\"",
+        ));
+    }
+    for (slot, part) in out.iter_mut().zip(parts) {
+        *slot = part
+            .parse()
+            .map_err(|_| syn::Error::new(lit.span(), "expected version like \"3.0.3\""))?;
+    }
+    Ok(out)
+}
+
+/// `SetMinerPools` -> `set_miner_pools`
+fn to_snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i != 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
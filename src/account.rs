@@ -2,11 +2,13 @@
 //!
 //! - Item: [Account]
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Getting_Started-getting_start>
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+use crate::error::Error;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 /// Static, unchangeable users
 ///
@@ -39,3 +41,17 @@ impl AsRef<str> for Account {
         }
     }
 }
+
+impl FromStr for Account {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "super" => Ok(Account::Super),
+            "user1" => Ok(Account::User1),
+            "user2" => Ok(Account::User2),
+            "user3" => Ok(Account::User3),
+            _ => Err(Error::InvalidAccount(s.to_string())),
+        }
+    }
+}
@@ -9,34 +9,56 @@
 //!
 //! - Item: [Actor]
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-TCP_Translate_Protocol-tcp_protocol>
+pub mod config;
 pub mod message;
+#[cfg(test)]
+pub(crate) mod mock;
 pub mod process;
 pub mod read;
+pub mod retry;
 pub mod send;
+pub mod stream;
 
-use std::fmt::Display;
+use std::{fmt::Display, future::Future, time::Instant};
 
+use serde::Serialize;
 use tokio::{
     net::{TcpStream, ToSocketAddrs},
     select,
-    sync::mpsc::{self, Receiver},
+    sync::{
+        mpsc::{self, Receiver},
+        oneshot,
+    },
 };
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, error, info, instrument, trace, warn};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::{
     account::Account,
     actor::{
-        message::ActorMessage,
+        config::ActorConfig,
+        message::{ActorMessage, Reply},
         process::{process, process_unknown},
+        read::{read_chunks, read_len},
+        retry::is_connection_lost,
+        send::send,
+        stream::FrameStream,
     },
+    audit::AuditRecord,
     auth_data::AuthData,
+    capabilities::Capabilities,
+    clock::Skew,
     command::{
         Command,
         get_device_info::{GetDeviceInfo, GetDeviceInfoParam},
     },
+    credentials::{CredentialProvider, MinerId},
+    drift::{Drift, ExtraFields},
+    dyn_command::{DynCommand, DynResponse},
     error::{Error, Result},
     password::Password,
+    permission::Permission,
+    response::check_code,
 };
 
 #[derive(Debug, Zeroize, ZeroizeOnDrop)]
@@ -49,43 +71,225 @@ use crate::{
 /// - miner should be at actual version <3.0.0
 ///     - firmware <https://www.whatsminer.com/src/views/firmware-download.html#Firmware>
 pub struct Actor {
+    /// Miner address, attached to errors
+    #[zeroize(skip)]
+    pub addr: String,
     #[zeroize(skip)]
     pub username: Account,
+    /// Detected on connect
+    #[zeroize(skip)]
+    pub capabilities: Capabilities,
     pub password: Password,
     pub salt: String,
+    /// Miner clock skew, measured on connect
+    #[zeroize(skip)]
+    pub skew: Skew,
+    /// Rights of account, read on connect, `None` if unknown
+    #[zeroize(skip)]
+    pub permission: Option<Permission>,
+    /// Serial number, read on connect if [ActorConfig::audit] is set
+    #[zeroize(skip)]
+    pub serial: Option<String>,
+    #[zeroize(skip)]
+    pub config: ActorConfig,
     #[zeroize(skip)]
     pub tx: tokio::sync::mpsc::Sender<ActorMessage>,
 }
 
+/// Skew in seconds, which is worth a warning
+const MAX_SKEW: i64 = 30;
+
 // TODO: add command is_alive(). It should send heartbeat data [0x00,0x00,0x00,0x00]
 impl Actor {
+    /// Make connection to ASIC with default [ActorConfig]
+    pub async fn new(
+        addr: impl Display + ToSocketAddrs,
+        username: Account,
+        password: impl Into<Password>,
+    ) -> Result<Self> {
+        Self::with_config(addr, username, password, ActorConfig::default()).await
+    }
+
     #[instrument(level = "info", skip(addr, username, password), fields(addr = %addr))]
     /// Make connection to ASIC
-    pub async fn new(
+    pub async fn with_config(
         addr: impl Display + ToSocketAddrs,
         username: Account,
         password: impl Into<Password>,
+        config: ActorConfig,
     ) -> Result<Self> {
         info!("Creating new Actor.");
 
         let (tx, rx) = mpsc::channel(10);
         info!(%addr, "Connecting to TCP stream.");
-        let mut stream = TcpStream::connect(&addr).await?;
+        let mut stream = TcpStream::connect(&addr)
+            .await
+            .map_err(|source| Error::Connect {
+                addr: addr.to_string(),
+                source,
+            })?;
         debug!(%addr, "TCP stream connected. Setting no delay.");
         stream.nodelay()?;
-        info!(%addr, "Getting actor salt.");
-        let salt = get_actor_salt(&mut stream).await?;
+        info!(%addr, "Getting actor salt and capabilities.");
+        let (salt, capabilities, when) =
+            handshake(&mut stream, config.max_frame)
+                .await
+                .map_err(|e| Error::Handshake {
+                    addr: addr.to_string(),
+                    source: Box::new(e),
+                })?;
         debug!(%addr, "Salt received: {}", salt);
-        tokio::spawn(run_actor(rx, stream, addr.to_string()));
+        let skew = if config.sync_clock && when > 0 {
+            Skew::measure(config.clock.now()?, when)
+        } else {
+            Skew::default()
+        };
+        if skew.0.abs() > MAX_SKEW {
+            warn!(%addr, skew = skew.0, "Miner clock differs from local one. Tokens use miner time.");
+        }
+        info!(%addr, api = ?capabilities.api, firmware = ?capabilities.firmware, "Capabilities detected.");
+        tokio::spawn(run_actor(rx, stream, addr.to_string(), config.clone()));
         info!(%addr, "Actor created successfully.");
 
-        Ok(Self {
+        let mut actor = Self {
+            addr: addr.to_string(),
             tx,
             username,
+            capabilities,
             password: password.into(),
             salt,
-        })
+            skew,
+            permission: None,
+            serial: None,
+            config,
+        };
+        actor.refresh_permission().await;
+        if actor.config.audit.is_some() {
+            actor.refresh_serial().await;
+        }
+        Ok(actor)
+    }
+
+    #[instrument(level = "debug", skip(self), fields(addr = %self.addr))]
+    /// Read permission of account from [Miner::permission](crate::command::get_device_info::Miner::permission)
+    ///
+    /// Failures are logged, permission stays unknown and nothing is rejected
+    pub async fn refresh_permission(&mut self) {
+        self.permission = None;
+        if !self.config.check_permission {
+            return;
+        }
+        if self.username == Account::Super {
+            self.permission = Some(Permission::ALL);
+            return;
+        }
+        let info = self
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                power: false,
+                network: false,
+                system: false,
+                salt: false,
+                error_code: false,
+            }))
+            .await;
+        let raw = match info {
+            Ok(info) => info.msg.miner.and_then(|m| m.permission),
+            Err(e) => {
+                warn!(error = %e, "Can't read permission.");
+                return;
+            }
+        };
+        match raw.map(|raw| Permission::parse(&raw, self.username)) {
+            Some(Ok(permission)) => self.permission = permission,
+            Some(Err(e)) => warn!(error = %e, "Can't parse permission."),
+            None => debug!("Miner doesn't report permission."),
+        }
+        info!(permission = ?self.permission, "Permission detected.");
+    }
+
+    #[instrument(level = "debug", skip(self), fields(addr = %self.addr))]
+    /// Read serial number for [AuditRecord]s
+    ///
+    /// Failures are logged, serial stays unknown
+    pub async fn refresh_serial(&mut self) {
+        let info = self
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                power: false,
+                network: false,
+                system: false,
+                salt: false,
+                error_code: false,
+            }))
+            .await;
+        match info {
+            Ok(info) => self.serial = info.msg.miner.map(|m| m.miner_sn),
+            Err(e) => warn!(error = %e, "Can't read serial number."),
+        }
     }
+
+    /// Fail with [Error::PermissionDenied] if account can't run command
+    pub fn check_permission(&self, cmd: &str, secured: bool) -> Result<()> {
+        match self.permission {
+            Some(permission) => permission.check(self.username, cmd, secured),
+            None => Ok(()),
+        }
+    }
+
+    /// Known commands, which account may use, all of them if permission is unknown
+    pub fn allowed_commands(&self) -> Vec<&'static str> {
+        self.permission.unwrap_or(Permission::ALL).commands()
+    }
+    #[instrument(level = "info", skip(addr, provider, config), fields(addr = %addr))]
+    /// Make connection to ASIC with credentials from [CredentialProvider]
+    ///
+    /// Miner is looked up by address first. If provider doesn't know it,
+    /// MAC and serial number are read with unsecured [GetDeviceInfo] and looked up again.
+    /// [CredentialProvider::fallback] is used only if both lookups missed.
+    /// Fails with [Error::NoCredentials] if nothing matches
+    pub async fn with_provider(
+        addr: impl Display + ToSocketAddrs,
+        provider: &dyn CredentialProvider,
+        config: ActorConfig,
+    ) -> Result<Self> {
+        let addr = addr.to_string();
+        if let Some(c) = provider.credentials(&MinerId::addr(&addr))? {
+            return Self::with_config(&addr, c.account, c.password.clone(), config).await;
+        }
+        debug!(%addr, "Address is unknown to provider. Reading MAC and serial number.");
+        let mut actor = Self::with_config(&addr, Account::Super, Password::Super, config).await?;
+        let info = actor
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                network: true,
+                power: false,
+                system: false,
+                salt: false,
+                error_code: false,
+            }))
+            .await?
+            .msg;
+        let id = MinerId {
+            addr: &addr,
+            mac: info.network.as_ref().map(|n| n.mac.as_str()),
+            serial: info.miner.as_ref().map(|m| m.miner_sn.as_str()),
+        };
+        let c = match provider.credentials(&id)? {
+            Some(c) => c,
+            None => provider
+                .fallback()?
+                .ok_or_else(|| Error::NoCredentials(addr.clone()))?,
+        };
+        actor.username = c.account;
+        actor.password = c.password.clone();
+        if let Some(miner) = info.miner {
+            actor.serial = Some(miner.miner_sn);
+        }
+        actor.refresh_permission().await;
+        Ok(actor)
+    }
+
     #[instrument(level = "debug", skip(self))]
     /// Generate AuthData for Command
     pub fn auth_data<C: Command>(&self) -> Result<AuthData> {
@@ -93,19 +297,187 @@ impl Actor {
         AuthData::from_actor::<C>(self)
     }
 
+    #[instrument(level = "debug", skip(self))]
+    /// Generate AuthData for command name known only at runtime
+    pub fn auth_data_for(&self, cmd: &str) -> Result<AuthData> {
+        debug!(command = %cmd, "Generating authentication data.");
+        AuthData::with_ts(
+            cmd,
+            self.username,
+            self.password.as_ref(),
+            &self.salt,
+            self.now()?,
+        )
+    }
+
+    /// Current miner time: [ActorConfig::clock] corrected by [Actor::skew]
+    pub fn now(&self) -> Result<u64> {
+        Ok(self.skew.apply(self.config.clock.now()?))
+    }
+
     #[instrument(level = "info", skip_all, fields(command_name = %C::CMD_NAME))]
     /// Execute some Command with actor
     pub async fn send<C: Command + Send + Sync>(&self, cmd: &C) -> Result<C::Response> {
         info!("Sending command: {}.", C::CMD_NAME);
-        let response = cmd.execute(self).await?;
+        let response = cmd
+            .execute(self)
+            .await
+            .map_err(|e| e.with_context(&self.addr, C::CMD_NAME))?;
         debug!("Command {} executed. Response received.", C::CMD_NAME);
         Ok(response)
     }
+
+    #[instrument(level = "info", skip_all, fields(command_name = %C::CMD_NAME))]
+    /// Execute command in strict mode
+    ///
+    /// Fields, which don't match [Command::Response], are logged with `warn` level and returned
+    pub async fn send_strict<C>(&self, cmd: &C) -> Result<(C::Response, Drift)>
+    where
+        C: Command + Send + Sync,
+        C::Response: Serialize + ExtraFields,
+    {
+        info!("Sending strict command: {}.", C::CMD_NAME);
+        let out = self
+            .config
+            .retry
+            .run(C::CMD_NAME, C::IDEMPOTENT, || cmd.execute_raw(self))
+            .await?;
+        let (response, drift) = C::response_from_str_strict(&out).map_err(|e| {
+            e.decoding(C::CMD_NAME)
+                .with_context(&self.addr, C::CMD_NAME)
+        })?;
+        drift.warn(C::CMD_NAME);
+        Ok((response, drift))
+    }
+
+    #[instrument(level = "info", skip_all, fields(command_name = %cmd.name()))]
+    /// Execute some type-erased [DynCommand] with actor
+    ///
+    /// Response should be downcasted to the command's response type
+    pub async fn send_dyn(&self, cmd: &dyn DynCommand) -> Result<DynResponse> {
+        info!("Sending dyn command: {}.", cmd.name());
+        let out = self
+            .config
+            .retry
+            .run(cmd.name(), cmd.idempotent(), || self.execute_raw_dyn(cmd))
+            .await?;
+        debug!("Command {} executed. Response received.", cmd.name());
+        cmd.dyn_response_from_str(&out)
+            .map_err(|e| e.decoding(cmd.name()).with_context(&self.addr, cmd.name()))
+    }
+
+    /// Run [DynCommand] once, returns raw JSON answer
+    ///
+    /// Same as [Command::execute_raw]: checks, audit and [Error::Api] on non-zero code
+    pub(crate) async fn execute_raw_dyn(&self, cmd: &dyn DynCommand) -> Result<String> {
+        let work = async {
+            self.capabilities.check(cmd.name(), cmd.min_api())?;
+            self.check_permission(cmd.name(), cmd.secured())?;
+            let auth = if cmd.secured() {
+                Some(self.auth_data_for(cmd.name())?)
+            } else {
+                None
+            };
+            let message = serde_json::to_vec(&cmd.to_dyn_request(auth)?)?;
+            let out = self.dispatch(message).await?;
+            check_code(&out, cmd.name())?;
+            Ok(out)
+        };
+        self.audited(
+            cmd.name(),
+            cmd.secured(),
+            cmd.encrypted(),
+            || cmd.dyn_params(),
+            work,
+            |_| Some(0),
+        )
+        .await
+        .map_err(|e| e.with_context(&self.addr, cmd.name()))
+    }
+
+    /// Push raw request bytes into actor worker and wait for raw answer
+    pub(crate) async fn dispatch(&self, message: Vec<u8>) -> Result<String> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(ActorMessage {
+                message,
+                rev: Reply::Frame(tx),
+            })
+            .await?;
+        let out = rx.await??;
+        trace!(data=%out, "got data from rx");
+        Ok(out)
+    }
+
+    #[instrument(level = "info", skip_all, fields(command_name = %C::CMD_NAME))]
+    /// Execute command, which returns large payload (logs, history)
+    ///
+    /// Response is read by chunks and limited by [ActorConfig::max_stream_frame].
+    /// Actor doesn't process other commands until the stream is read or dropped
+    pub async fn send_stream<C: Command + Send + Sync>(&self, cmd: &C) -> Result<FrameStream> {
+        info!("Sending stream command: {}.", C::CMD_NAME);
+        let work = async {
+            let message = cmd.to_message(self)?;
+            let (tx, rx) = oneshot::channel();
+            self.tx
+                .send(ActorMessage {
+                    message: message.into_bytes(),
+                    rev: Reply::Stream(tx),
+                })
+                .await?;
+            rx.await?
+        };
+        // answer is streamed, code isn't known here
+        self.audited(
+            C::CMD_NAME,
+            C::SECURED,
+            C::ENCRYPTED,
+            || cmd.params(),
+            work,
+            |_| None,
+        )
+        .await
+        .map_err(|e| e.with_context(&self.addr, C::CMD_NAME))
+    }
+
+    /// Run `work`, recording it to [ActorConfig::audit] if command is secured
+    ///
+    /// `params` are built only if record is written,
+    /// `code` gives code of answer from successful result
+    pub(crate) async fn audited<T>(
+        &self,
+        cmd: &str,
+        secured: bool,
+        encrypted: bool,
+        params: impl FnOnce() -> Result<Option<String>>,
+        work: impl Future<Output = Result<T>>,
+        code: impl FnOnce(&T) -> Option<i64>,
+    ) -> Result<T> {
+        let Some(sink) = self.config.audit.as_ref().filter(|_| secured) else {
+            return work.await;
+        };
+        // time of sending, not of answer
+        let ts = self.config.clock.now().unwrap_or_default();
+        let started = Instant::now();
+        let out = work.await;
+        let params = params().unwrap_or_else(|e| Some(format!("<invalid: {e}>")));
+        let params = params.map(|p| if encrypted { "<hidden>".to_string() } else { p });
+        let record = AuditRecord::new(self, ts, cmd, params, &out, code, started.elapsed());
+        if let Err(e) = sink.record(&record) {
+            warn!(%cmd, error = %e, "Can't write audit record.");
+        }
+        out
+    }
 }
 
 #[instrument(level = "info", skip(stream))]
-/// Execute GetDeviceInfo with only salf parametr
-async fn get_actor_salt(stream: &mut TcpStream) -> Result<String> {
+/// Execute GetDeviceInfo with only salt and system parameters
+///
+/// Returns salt, [Capabilities] and current time of miner
+async fn handshake(
+    stream: &mut TcpStream,
+    max_frame: usize,
+) -> Result<(String, Capabilities, u64)> {
     info!("Attempting to retrieve actor salt.");
     debug!("Preparing GetDeviceInfo command for salt extraction.");
     let data = process::<GetDeviceInfo>(
@@ -114,42 +486,80 @@ async fn get_actor_salt(stream: &mut TcpStream) -> Result<String> {
             miner: false,
             power: false,
             network: false,
-            system: false,
             error_code: false,
             ..Default::default()
         })
         .to_request_to_bytes(None)?
         .as_ref(),
+        max_frame,
     )
     .await?;
     debug!("{:#?}", &data);
     debug!("GetDeviceInfo command processed. Extracting salt.");
-    data.msg.salt.ok_or(Error::SaltNotFound)
+    let capabilities = data
+        .msg
+        .system
+        .as_ref()
+        .map(Capabilities::from)
+        .unwrap_or_default();
+    let salt = data.msg.salt.ok_or(Error::SaltNotFound)?;
+    Ok((salt, capabilities, data.when))
 }
 
 #[instrument(level = "info", skip(rx, stream), fields(addr = %addr))]
 /// Run actor worker
-async fn run_actor(mut rx: Receiver<ActorMessage>, mut stream: TcpStream, addr: String) {
+async fn run_actor(
+    mut rx: Receiver<ActorMessage>,
+    stream: TcpStream,
+    addr: String,
+    config: ActorConfig,
+) {
     info!("Actor worker started for address: {}.", addr);
+    // `None` after connection is lost, reconnected on next message
+    let mut stream = Some(stream);
     '_worker: loop {
         select! {
-            // TODO: Add reconnection logic
             Some(msg) = rx.recv() => {
                 debug!(%addr, "Actor: received command from channel.");
+                let conn = match connection(&mut stream, &addr).await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        error!(%addr, error=%e, "Can't reconnect.");
+                        match msg.rev {
+                            Reply::Frame(rev) => { let _ = rev.send(Err(e)); }
+                            Reply::Stream(rev) => { let _ = rev.send(Err(e)); }
+                        }
+                        continue '_worker;
+                    }
+                };
+                let lost = match msg.rev {
+                    Reply::Frame(rev) => {
+                        // Process the command, resulting in a custom `crate::error::Result`.
+                        let processing_result: crate::error::Result<String> =
+                            process_unknown(conn, &msg.message, config.max_frame).await;
 
-                // Process the command, resulting in a custom `crate::error::Result`.
-                let processing_result: crate::error::Result<String> = process_unknown(&mut stream, &msg.message).await;
+                        // Log any error that occurred during the command processing.
+                        let lost = match processing_result {
+                            Err(ref e) => {
+                                error!(%addr, error=%e, "Got error when processing data.");
+                                is_connection_lost(e)
+                            }
+                            Ok(_) => false,
+                        };
 
-                // Log any error that occurred during the command processing.
-                if let Err(ref e) = processing_result {
-                    error!(%addr, error=%e, "Got error when processing data.");
-                }
-
-                if let Err(unsent_value) = msg.rev.send(processing_result) {
-                    match unsent_value {
-                        Ok(s) => warn!(%addr, "Failed to send successful result ('{}') back to requester: oneshot receiver dropped.", s),
-                        Err(e) => warn!(%addr, error=%e, "Failed to send error result back to requester: oneshot receiver dropped."),
-                    };
+                        if let Err(unsent_value) = rev.send(processing_result) {
+                            match unsent_value {
+                                Ok(s) => warn!(%addr, "Failed to send successful result ('{}') back to requester: oneshot receiver dropped.", s),
+                                Err(e) => warn!(%addr, error=%e, "Failed to send error result back to requester: oneshot receiver dropped."),
+                            };
+                        }
+                        lost
+                    }
+                    Reply::Stream(rev) => stream_frame(conn, &msg.message, &config, rev, &addr).await,
+                };
+                if lost {
+                    warn!(%addr, "Connection lost. It will be reopened on next command.");
+                    stream = None;
                 }
                 debug!(%addr, "Response sent (or attempted to send) to oneshot channel for command.");
             }
@@ -160,3 +570,398 @@ async fn run_actor(mut rx: Receiver<ActorMessage>, mut stream: TcpStream, addr:
         }
     }
 }
+
+/// Current connection, or a new one if it was lost
+async fn connection<'a>(
+    stream: &'a mut Option<TcpStream>,
+    addr: &str,
+) -> Result<&'a mut TcpStream> {
+    if stream.is_none() {
+        info!(%addr, "Reconnecting.");
+        let conn = TcpStream::connect(addr)
+            .await
+            .map_err(|source| Error::Connect {
+                addr: addr.to_string(),
+                source,
+            })?;
+        conn.set_nodelay(true)?;
+        *stream = Some(conn);
+    }
+    Ok(stream.as_mut().expect("connection is set above"))
+}
+
+/// Send request and pass response body by chunks to [FrameStream]
+///
+/// Returns `true` if connection is lost
+async fn stream_frame(
+    stream: &mut TcpStream,
+    message: &[u8],
+    config: &ActorConfig,
+    rev: oneshot::Sender<Result<FrameStream>>,
+    addr: &str,
+) -> bool {
+    let len = match send(stream, message).await {
+        Ok(()) => read_len(stream, config.max_stream_frame).await,
+        Err(e) => Err(e),
+    };
+    let len = match len {
+        Ok(len) => len,
+        Err(e) => {
+            error!(%addr, error=%e, "Got error when processing data.");
+            let lost = is_connection_lost(&e);
+            let _ = rev.send(Err(e));
+            return lost;
+        }
+    };
+    let (tx, rx) = mpsc::channel(4);
+    if rev.send(Ok(FrameStream { len, rx })).is_err() {
+        warn!(%addr, "Stream requester dropped. Dropping connection.");
+        return true;
+    }
+    match read_chunks(stream, len, config.chunk_size, &tx).await {
+        Ok(complete) => !complete,
+        Err(e) => {
+            error!(%addr, error=%e, "Got error when streaming data.");
+            let lost = is_connection_lost(&e);
+            let _ = tx.send(Err(e)).await;
+            lost
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        actor::retry::RetryPolicy,
+        capabilities::ApiVersion,
+        command::{
+            get_fan_setting::{GetFanSettings, GetFanSettingsResponse},
+            get_miner_setting::{GetMinerSettings, GetMinerSettingsResponse},
+            raw::RawCommand,
+            set_miner_power_limit::SetMinerPowerLimit,
+            set_miner_power_percent::SetMinerPowerPercent,
+            set_system_reboot::SetSystemReboot,
+        },
+        error::ErrorKind,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn capabilities() {
+        let addr = mock::spawn(|req| match req["cmd"].as_str() {
+            Some("get.device.info") => Some(mock::handshake_answer("3.0.1")),
+            Some(cmd) => Some(mock::ok_answer(
+                cmd,
+                json!({"fan-poweroff-cool": 1, "fan-zero-speed": 0, "fan-temp-offset": 0}),
+            )),
+            None => None,
+        })
+        .await;
+        let actor = Actor::new(addr, Account::Super, Password::Super)
+            .await
+            .unwrap();
+        assert_eq!(actor.capabilities.api, Some(ApiVersion::new(3, 0, 1)));
+        assert_eq!(
+            actor.capabilities.firmware.as_deref(),
+            Some("20250915.16.Rel2")
+        );
+        assert_eq!(actor.salt, mock::SALT);
+
+        assert!(actor.send(&GetFanSettings).await.is_ok());
+        let e = actor.send(&SetMinerPowerPercent(50)).await.unwrap_err();
+        assert!(matches!(e.root(), Error::Unsupported { .. }));
+        assert_eq!(e.addr(), Some(actor.addr.as_str()));
+        let e = actor.send_dyn(&SetMinerPowerLimit(3000)).await.unwrap_err();
+        assert!(matches!(e.root(), Error::Unsupported { .. }));
+        assert!(
+            RawCommand::new("get.fan.setting")
+                .execute(&actor)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn frame_limit() {
+        let addr = mock::spawn(|req| match req["cmd"].as_str() {
+            Some("get.device.info") => None,
+            Some("get.fan.setting") => Some(mock::ok_answer(
+                "get.fan.setting",
+                mock::msg::<GetFanSettingsResponse>(
+                    json!({"fan-poweroff-cool": 1, "log": "x".repeat(4000)}),
+                ),
+            )),
+            Some(cmd) => Some(mock::ok_answer(
+                cmd,
+                mock::msg::<GetMinerSettingsResponse>(json!({"fast-boot": "enable"})),
+            )),
+            None => None,
+        })
+        .await;
+        let config = ActorConfig {
+            max_frame: 1024,
+            chunk_size: 1000,
+            ..Default::default()
+        };
+        let actor = Actor::with_config(addr, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            actor.send(&GetFanSettings).await.unwrap_err().root(),
+            Error::FrameTooLarge { max: 1024, .. }
+        ));
+        // unread body isn't mistaken for the next answer
+        assert!(actor.send(&GetMinerSettings).await.is_ok());
+
+        let mut stream = actor.send_stream(&GetFanSettings).await.unwrap();
+        assert!(stream.len > 4000);
+        let first = stream.next_chunk().await.unwrap().unwrap();
+        assert_eq!(first.len(), 1000);
+        let rest = stream.read_to_end().await.unwrap();
+        let out = String::from_utf8([first, rest].concat()).unwrap();
+        let response = GetFanSettings::response_from_str(&out).unwrap();
+        assert_eq!(response.msg.extra["log"].as_str().unwrap().len(), 4000);
+
+        // dropped stream doesn't break next command
+        drop(actor.send_stream(&GetFanSettings).await.unwrap());
+        assert!(actor.send(&GetMinerSettings).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let addr = mock::spawn(|req| match req["cmd"].as_str() {
+            Some("get.device.info") => None,
+            Some("get.fan.setting") => Some(json!({
+                "code": -1, "when": 1, "msg": "invalid token", "desc": "get.fan.setting"
+            })),
+            Some(cmd) => Some(mock::ok_answer(cmd, json!({"power-limit": "oops"}))),
+            None => None,
+        })
+        .await;
+        let actor = Actor::new(addr.clone(), Account::Super, Password::Super)
+            .await
+            .unwrap();
+
+        let e = actor.send(&GetFanSettings).await.unwrap_err();
+        assert!(e.is_auth_failure());
+        assert_eq!(e.cmd(), Some("get.fan.setting"));
+        assert_eq!(e.addr(), Some(addr.as_str()));
+        assert!(
+            e.to_string()
+                .starts_with(&format!("{addr} (get.fan.setting): "))
+        );
+        // direct execute carries the same context
+        let e = GetFanSettings.execute(&actor).await.unwrap_err();
+        assert_eq!(e.addr(), Some(addr.as_str()));
+        assert_eq!(e.cmd(), Some("get.fan.setting"));
+
+        let e = actor.send(&GetMinerSettings).await.unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Decode);
+        assert!(!e.is_retryable());
+
+        let e = Actor::new("127.0.0.1:1", Account::Super, Password::Super)
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Connect);
+        assert!(e.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicU32, Ordering},
+        };
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        // every odd command closes connection
+        let addr = mock::spawn(move |req| match req["cmd"].as_str() {
+            Some("get.device.info") => None,
+            Some(cmd) => (counter.fetch_add(1, Ordering::SeqCst) % 2 == 1)
+                .then(|| mock::ok_answer(cmd, mock::msg::<GetFanSettingsResponse>(json!({})))),
+            None => None,
+        })
+        .await;
+        let config = ActorConfig {
+            retry: RetryPolicy {
+                base_delay: std::time::Duration::from_millis(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let actor = Actor::with_config(addr, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+
+        // reconnected and sent again with new token
+        assert!(actor.send(&GetFanSettings).await.is_ok());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+        assert!(actor.send_dyn(&GetFanSettings).await.is_ok());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+
+        // destructive command is never repeated
+        let e = actor.send(&SetSystemReboot).await.unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::Io);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn clock_skew() {
+        use std::sync::Arc;
+
+        use crate::clock::FixedClock;
+
+        let addr = mock::spawn(|_| None).await;
+        let config = ActorConfig {
+            clock: Arc::new(FixedClock(1_700_000_100)),
+            ..Default::default()
+        };
+        let actor = Actor::with_config(addr.clone(), Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+        assert_eq!(actor.skew, Skew(-100));
+        assert_eq!(
+            actor.auth_data::<GetFanSettings>().unwrap().ts,
+            1_700_000_000
+        );
+        assert_eq!(
+            actor.auth_data_for("get.fan.setting").unwrap().ts,
+            1_700_000_000
+        );
+
+        let config = ActorConfig {
+            clock: Arc::new(FixedClock(1_700_000_100)),
+            sync_clock: false,
+            ..Default::default()
+        };
+        let actor = Actor::with_config(addr, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+        assert_eq!(
+            actor.auth_data::<GetFanSettings>().unwrap().ts,
+            1_700_000_100
+        );
+    }
+
+    #[tokio::test]
+    async fn provider() {
+        use crate::credentials::{Credentials, Fallback, MemoryProvider};
+
+        let addr = mock::spawn(|req| match req["cmd"].as_str() {
+            Some("get.device.info")
+                if req["param"].as_str().is_some_and(|p| p.contains("network")) =>
+            {
+                Some(mock::ok_answer(
+                    "get.device.info",
+                    json!({
+                        "network": {"mac": "C4:11:04:00:00:01"},
+                        "miner": {"miner-sn": "HTM50SVK30240912"}
+                    }),
+                ))
+            }
+            _ => None,
+        })
+        .await;
+
+        let mut provider = MemoryProvider::new();
+        provider.insert("HTM50S*", Account::User1, "by-serial");
+        let actor = Actor::with_provider(&addr, &provider, ActorConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(actor.username, Account::User1);
+        assert_eq!(actor.password.as_ref(), "by-serial");
+
+        provider.insert(addr.clone(), Account::User2, "by-addr");
+        provider.entries.reverse();
+        let actor = Actor::with_provider(&addr, &provider, ActorConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(actor.password.as_ref(), "by-addr");
+
+        let e = Actor::with_provider(&addr, &MemoryProvider::new(), ActorConfig::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(e, Error::NoCredentials(_)));
+
+        // default applies only after address, MAC and serial missed
+        let fallback = Fallback(Credentials::new(Account::User3, "default"));
+        let mut by_serial = MemoryProvider::new();
+        by_serial.insert("HTM50S*", Account::User1, "by-serial");
+        let chain: Vec<Box<dyn CredentialProvider>> =
+            vec![Box::new(by_serial), Box::new(fallback.clone())];
+        let actor = Actor::with_provider(&addr, &chain, ActorConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(actor.password.as_ref(), "by-serial");
+        let actor = Actor::with_provider(&addr, &fallback, ActorConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(actor.username, Account::User3);
+    }
+
+    #[tokio::test]
+    async fn permission() {
+        use crate::command::set_miner_fastboot::SetMinerFastboot;
+
+        let addr = mock::spawn(|req| match req["cmd"].as_str() {
+            Some("get.device.info") if req.get("param").is_some_and(|p| p == "miner") => {
+                Some(mock::ok_answer(
+                    "get.device.info",
+                    json!({"miner": {"permission": "super=255,user1=8"}}),
+                ))
+            }
+            Some("get.device.info") => None,
+            Some("set.system.reboot") => panic!("rejected command is sent"),
+            Some(cmd) => Some(mock::ok_answer(cmd, json!("ok"))),
+            None => None,
+        })
+        .await;
+
+        let config = ActorConfig {
+            check_permission: true,
+            ..Default::default()
+        };
+        // off by default
+        let actor = Actor::new(&addr, Account::User1, Password::User1)
+            .await
+            .unwrap();
+        assert_eq!(actor.permission, None);
+
+        let actor = Actor::with_config(&addr, Account::User1, Password::User1, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(actor.permission, Some(Permission(8)));
+        assert!(actor.send(&SetMinerFastboot(true)).await.is_ok());
+        let e = actor.send(&SetSystemReboot).await.unwrap_err();
+        assert!(matches!(
+            e.root(),
+            Error::PermissionDenied {
+                account: Account::User1,
+                ..
+            }
+        ));
+        assert!(e.is_auth_failure());
+        assert!(matches!(
+            actor.send_dyn(&SetSystemReboot).await.unwrap_err().root(),
+            Error::PermissionDenied { .. }
+        ));
+        let mut raw = RawCommand::new("set.system.reboot");
+        raw.secured = true;
+        assert!(matches!(
+            raw.execute(&actor).await.unwrap_err().root(),
+            Error::PermissionDenied { .. }
+        ));
+        let allowed = actor.allowed_commands();
+        assert!(allowed.contains(&"set.miner.fastboot") && !allowed.contains(&"set.system.reboot"));
+
+        let actor = Actor::with_config(&addr, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+        assert_eq!(actor.permission, Some(Permission::ALL));
+    }
+}
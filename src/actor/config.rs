@@ -0,0 +1,71 @@
+//! Define actor config module
+//!
+//! - Item: [ActorConfig]
+use std::sync::Arc;
+
+#[cfg(doc)]
+use crate::{actor::Actor, error::Error};
+use crate::{
+    actor::retry::RetryPolicy,
+    audit::AuditSink,
+    clock::{Clock, SystemClock},
+};
+
+/// Default [ActorConfig::max_frame], 1 MiB
+pub const DEFAULT_MAX_FRAME: usize = 1024 * 1024;
+/// Default [ActorConfig::max_stream_frame], 256 MiB
+pub const DEFAULT_MAX_STREAM_FRAME: usize = 256 * 1024 * 1024;
+/// Default [ActorConfig::chunk_size], 64 KiB
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Settings of [Actor]
+///
+/// # Example
+/// ```
+/// use matroskin::actor::config::ActorConfig;
+///
+/// let config = ActorConfig {
+///     max_frame: 64 * 1024,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct ActorConfig {
+    /// Max length of buffered response, bigger frames fail with [Error::FrameTooLarge]
+    pub max_frame: usize,
+    /// Max length of streamed response ([Actor::send_stream])
+    pub max_stream_frame: usize,
+    /// Size of chunks of streamed response
+    pub chunk_size: usize,
+    /// Retries of idempotent commands
+    pub retry: RetryPolicy,
+    /// Local time source for [AuthData](crate::auth_data::AuthData) timestamps
+    pub clock: Arc<dyn Clock>,
+    /// Stamp tokens with miner time, measured on connect ([Actor::skew])
+    pub sync_clock: bool,
+    /// Read account's [Permission](crate::permission::Permission) on connect
+    /// and reject commands it can't run ([Actor::permission])
+    ///
+    /// Off by default: bit layout of the mask isn't documented,
+    /// see [GROUPS](crate::permission::GROUPS)
+    pub check_permission: bool,
+    /// Where secured commands are recorded, see [audit](crate::audit)
+    ///
+    /// Serial number of miner is read on connect if it is set ([Actor::serial])
+    pub audit: Option<Arc<dyn AuditSink>>,
+}
+
+impl Default for ActorConfig {
+    fn default() -> Self {
+        Self {
+            max_frame: DEFAULT_MAX_FRAME,
+            max_stream_frame: DEFAULT_MAX_STREAM_FRAME,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            retry: RetryPolicy::default(),
+            clock: Arc::new(SystemClock),
+            sync_clock: true,
+            check_permission: false,
+            audit: None,
+        }
+    }
+}
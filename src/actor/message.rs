@@ -1,11 +1,20 @@
 //! Define message module for communication between actor and another process
 use tokio::sync::oneshot;
 
-use crate::error::Result;
+use crate::{actor::stream::FrameStream, error::Result};
 
 #[derive(Debug)]
 /// Message for Actor
 pub struct ActorMessage {
     pub message: Vec<u8>,
-    pub rev: oneshot::Sender<Result<String>>,
+    pub rev: Reply,
+}
+
+#[derive(Debug)]
+/// How Actor should answer
+pub enum Reply {
+    /// Whole frame, limited by [ActorConfig::max_frame](crate::actor::config::ActorConfig::max_frame)
+    Frame(oneshot::Sender<Result<String>>),
+    /// Frame by chunks, limited by [ActorConfig::max_stream_frame](crate::actor::config::ActorConfig::max_stream_frame)
+    Stream(oneshot::Sender<Result<FrameStream>>),
 }
@@ -0,0 +1,103 @@
+//! Fake miner for offline tests
+//!
+//! Speaks the same length-prefixed TCP protocol as real miner.
+//!
+//! - Item: [spawn]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-TCP_Translate_Protocol-tcp_protocol>
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Salt of fake miner
+pub(crate) const SALT: &str = "MoCkSaLt";
+
+/// Handler of decoded request, `None` closes connection
+pub(crate) type Handler = Arc<dyn Fn(&Value) -> Option<Value> + Send + Sync>;
+
+/// Answer of `get.device.info` handshake
+pub(crate) fn handshake_answer(api: &str) -> Value {
+    json!({
+        "code": 0,
+        "when": 1_700_000_000u64,
+        "msg": {
+            "salt": SALT,
+            "system": {
+                "api": api,
+                "platform": "H616",
+                "fwversion": "20250915.16.Rel2",
+                "control-board-version": "CB6V10",
+                "apiswitch": "1",
+                "ledstatus": "auto"
+            }
+        },
+        "desc": "get.device.info"
+    })
+}
+
+/// Generic OK answer for command
+pub(crate) fn ok_answer(cmd: &str, msg: Value) -> Value {
+    json!({ "code": 0, "when": 1_700_000_000u64, "msg": msg, "desc": cmd })
+}
+
+/// Message of fixed-shape answer: default `T` with `fields` on top
+///
+/// Settings responses fail on missing keys, so answers have to be complete
+pub(crate) fn msg<T: Default + Serialize>(fields: Value) -> Value {
+    let mut out = serde_json::to_value(T::default()).unwrap();
+    if let (Some(out), Value::Object(fields)) = (out.as_object_mut(), fields) {
+        out.extend(fields);
+    }
+    out
+}
+
+/// Start fake miner, returns its address
+///
+/// Every request goes to `handler`.
+/// Handshake (`get.device.info` without token) falls back to api `3.0.2` answer,
+/// if handler returns `None` for it
+pub(crate) async fn spawn(
+    handler: impl Fn(&Value) -> Option<Value> + Send + Sync + 'static,
+) -> String {
+    let handler: Handler = Arc::new(handler);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                loop {
+                    let mut len = [0u8; 4];
+                    if stream.read_exact(&mut len).await.is_err() {
+                        return;
+                    }
+                    let mut buf = vec![0u8; u32::from_le_bytes(len) as usize];
+                    if stream.read_exact(&mut buf).await.is_err() {
+                        return;
+                    }
+                    let request: Value = serde_json::from_slice(&buf).unwrap();
+                    let answer = match request["cmd"].as_str() {
+                        Some("get.device.info") if request.get("token").is_none() => {
+                            handler(&request).or_else(|| Some(handshake_answer("3.0.2")))
+                        }
+                        _ => handler(&request),
+                    };
+                    let Some(answer) = answer else {
+                        return;
+                    };
+                    let out = serde_json::to_vec(&answer).unwrap();
+                    stream
+                        .write_all(&(out.len() as u32).to_le_bytes())
+                        .await
+                        .unwrap();
+                    stream.write_all(&out).await.unwrap();
+                }
+            });
+        }
+    });
+    addr
+}
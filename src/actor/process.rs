@@ -19,21 +19,29 @@ use crate::{
 /// Execute some data into Actor
 ///
 /// You are need specify the Command
-pub async fn process<C: Command>(stream: &mut TcpStream, data: &[u8]) -> Result<C::Response> {
+pub async fn process<C: Command>(
+    stream: &mut TcpStream,
+    data: &[u8],
+    max_frame: usize,
+) -> Result<C::Response> {
     debug!(cmd=%C::CMD_NAME, "Processing command.",);
     send(stream, data).await?;
     debug!(cmd=%C::CMD_NAME,"Data sent. Reading response for command.");
-    read::<C>(stream).await
+    read::<C>(stream, max_frame).await
 }
 
 #[instrument(level = "debug", skip(stream, data))]
 /// Execute some data into Actor
 ///
 /// It is return any positive result
-pub async fn process_unknown(stream: &mut TcpStream, data: &[u8]) -> Result<String> {
+pub async fn process_unknown(
+    stream: &mut TcpStream,
+    data: &[u8],
+    max_frame: usize,
+) -> Result<String> {
     debug!("Processing unknown command.");
     debug!("Sending data for unknown command.");
     send(stream, data).await?;
     debug!("Data sent. Reading unknown response.");
-    read_unknown(stream).await
+    read_unknown(stream, max_frame).await
 }
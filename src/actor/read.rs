@@ -1,24 +1,31 @@
 //! This module provides functions for reading data from a TCP stream
 //! - when the command type is known and unknown.
 //!
-//!- Item: [read], [read_unknown]
+//!- Item: [read], [read_unknown], [read_len], [read_chunks]
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-TCP_Translate_Protocol-tcp_protocol>
 use tokio::{
     io::{self, AsyncReadExt},
     net::TcpStream,
+    sync::mpsc::Sender,
 };
-use tracing::{debug, error, info, instrument, trace};
+use tracing::{debug, error, info, instrument, warn};
 
-use crate::{command::Command, error::Result};
+use crate::{
+    command::Command,
+    error::{Error, Result},
+};
 
 #[instrument(level = "debug", skip(stream))]
 /// Read the stream when we can know command type
 ///
 /// - ApiDoc:
 /// <https://apidoc.whatsminer.com/#api-TCP_Translate_Protocol-tcp_protocol>
-pub async fn read<C: Command>(stream: &mut TcpStream) -> Result<<C as Command>::Response> {
+pub async fn read<C: Command>(
+    stream: &mut TcpStream,
+    max_frame: usize,
+) -> Result<<C as Command>::Response> {
     debug!("Reading response for known command: {}.", C::CMD_NAME);
-    let raw_response = read_unknown(stream).await?;
+    let raw_response = read_unknown(stream, max_frame).await?;
     debug!(
         "Raw response received for {}. Attempting to parse.",
         C::CMD_NAME
@@ -29,39 +36,74 @@ pub async fn read<C: Command>(stream: &mut TcpStream) -> Result<<C as Command>::
 #[instrument(level = "debug", skip(stream))]
 /// Read the stream when we can't know command type at compile time
 ///
+/// Frames longer than `max_frame` fail with [Error::FrameTooLarge]
+///
 /// - ApiDoc:
 /// <https://apidoc.whatsminer.com/#api-TCP_Translate_Protocol-tcp_protocol>
-pub async fn read_unknown(stream: &mut TcpStream) -> Result<String> {
+pub async fn read_unknown(stream: &mut TcpStream, max_frame: usize) -> Result<String> {
     debug!("Reading unknown response from stream.");
-    const MAX_BUF: usize = 8192;
-    let mut buf = Vec::with_capacity(MAX_BUF);
-    '_READ_RES: {
-        let mut len_buf = [0u8; 4];
-        info!("Reading response length (4 bytes).");
-        stream.read_exact(&mut len_buf).await?;
-        let resp_len = u32::from_le_bytes(len_buf) as usize;
-        debug!("Response length header received: {} bytes.", resp_len);
-
-        if resp_len == 0 {
-            error!("Received a zero-length response, which is invalid.");
-            // TODO: Change to out type
-            return Err(io::Error::from(io::ErrorKind::InvalidData).into());
-        }
-        if resp_len > MAX_BUF {
-            trace!(
-                response = %resp_len,
-                "Response length exceeds the limit of {}. This response will be truncated if buf is not resized.",
-                MAX_BUF
-            );
-        }
-        buf.resize(resp_len, 0);
-        info!("Reading {} bytes of response data.", resp_len);
-        stream.read_exact(&mut buf).await?;
-        debug!("Response data read successfully.");
-    }
+    let resp_len = read_len(stream, max_frame).await?;
+    let mut buf = vec![0u8; resp_len];
+    info!("Reading {} bytes of response data.", resp_len);
+    stream.read_exact(&mut buf).await?;
+    debug!("Response data read successfully.");
     info!("Attempting to parse response bytes to UTF-8 string.");
     Ok(String::from_utf8(buf).map_err(|e| {
         error!("Failed to decode response as UTF-8: {}", e);
         io::Error::new(io::ErrorKind::InvalidData, e)
     })?)
 }
+
+#[instrument(level = "debug", skip(stream))]
+/// Read length header of frame
+///
+/// Too large frame fails with [Error::FrameTooLarge] right after the header.
+/// Its body is left unread, so the stream is out of sync and must be reopened
+pub async fn read_len(stream: &mut TcpStream, max_frame: usize) -> Result<usize> {
+    let mut len_buf = [0u8; 4];
+    info!("Reading response length (4 bytes).");
+    stream.read_exact(&mut len_buf).await?;
+    let resp_len = u32::from_le_bytes(len_buf) as usize;
+    debug!("Response length header received: {} bytes.", resp_len);
+
+    if resp_len == 0 {
+        error!("Received a zero-length response, which is invalid.");
+        return Err(Error::EmptyFrame);
+    }
+    if resp_len > max_frame {
+        warn!(
+            response = %resp_len,
+            "Response length exceeds the limit of {}. Dropping connection.",
+            max_frame
+        );
+        return Err(Error::FrameTooLarge {
+            len: resp_len,
+            max: max_frame,
+        });
+    }
+    Ok(resp_len)
+}
+
+#[instrument(level = "debug", skip(stream, tx))]
+/// Read `len` bytes of frame body by chunks of `chunk_size` into `tx`
+///
+/// If receiver is dropped, reading stops and `false` is returned:
+/// the rest of body is left unread, so the stream must be reopened
+pub async fn read_chunks(
+    stream: &mut TcpStream,
+    len: usize,
+    chunk_size: usize,
+    tx: &Sender<Result<Vec<u8>>>,
+) -> Result<bool> {
+    let mut left = len;
+    while left > 0 {
+        let mut chunk = vec![0u8; left.min(chunk_size.max(1))];
+        stream.read_exact(&mut chunk).await?;
+        left -= chunk.len();
+        if tx.send(Ok(chunk)).await.is_err() {
+            debug!(left, "Stream receiver dropped. Dropping connection.");
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,157 @@
+//! Define retry module
+//!
+//! Only idempotent commands ([Command::IDEMPOTENT]) are retried,
+//! and only on retryable errors ([Error::is_retryable]).
+//! Every attempt is a new request with fresh [AuthData](crate::auth_data::AuthData).
+//!
+//! - Item: [RetryPolicy]
+use std::{
+    future::Future,
+    hash::{BuildHasher, RandomState},
+    time::Duration,
+};
+
+use tracing::warn;
+
+#[cfg(doc)]
+use crate::command::Command;
+use crate::error::{Error, Result};
+
+/// Retry policy of [Actor](crate::actor::Actor)
+///
+/// Delay before attempt `n` (from `1`) is `base_delay * 2^(n-1)`, capped by `max_delay`.
+/// With `jitter` the delay is random between half and full value
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one, `1` disables retries
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy without retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay after failed attempt `attempt` (from `1`)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(exp).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        // random in [0.5, 1.0)
+        let random = RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+        delay.mul_f64(0.5 + random / 2.0)
+    }
+
+    /// Run `attempt` until success, non-retryable error or attempts limit
+    ///
+    /// Non-idempotent commands run once
+    pub async fn run<T, F, Fut>(&self, cmd: &str, idempotent: bool, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let max = if idempotent {
+            self.max_attempts.max(1)
+        } else {
+            1
+        };
+        let mut n = 1;
+        loop {
+            match attempt().await {
+                Err(e) if n < max && e.is_retryable() => {
+                    let delay = self.delay(n);
+                    warn!(cmd = %cmd, attempt = n, error = %e, ?delay, "Retrying command.");
+                    tokio::time::sleep(delay).await;
+                    n += 1;
+                }
+                out => return out,
+            }
+        }
+    }
+}
+
+/// Is connection broken or out of sync, so worker should reconnect?
+pub(crate) fn is_connection_lost(e: &Error) -> bool {
+    matches!(
+        e.root(),
+        Error::Io(_) | Error::EmptyFrame | Error::FrameTooLarge { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::command::{
+        Command, get_device_info::GetDeviceInfo, get_fan_setting::GetFanSettings,
+        set_miner_fastboot::SetMinerFastboot, set_system_factory_reset::SetSystemFactoryReset,
+        set_system_reboot::SetSystemReboot,
+    };
+
+    // destructive commands must never be retried
+    const _: () = assert!(!SetSystemReboot::IDEMPOTENT && !SetSystemFactoryReset::IDEMPOTENT);
+    const _: () = assert!(GetFanSettings::IDEMPOTENT && GetDeviceInfo::IDEMPOTENT);
+    const _: () = assert!(SetMinerFastboot::IDEMPOTENT);
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(30), Duration::from_secs(5));
+
+        let policy = RetryPolicy::default();
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn run() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let calls = AtomicU32::new(0);
+        let flaky = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::EmptyFrame)
+        };
+
+        assert!(policy.run("get.a", true, flaky).await.is_err());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
+
+        assert!(policy.run("set.system.reboot", false, flaky).await.is_err());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+        let fatal = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::SaltNotFound)
+        };
+        assert!(policy.run("get.a", true, fatal).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
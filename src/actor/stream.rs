@@ -0,0 +1,48 @@
+//! Define frame stream module
+//!
+//! Large responses (logs, history) are read by chunks,
+//! so they don't have to fit into [ActorConfig::max_frame].
+//!
+//! - Item: [FrameStream]
+use tokio::sync::mpsc::Receiver;
+
+#[cfg(doc)]
+use crate::actor::{Actor, config::ActorConfig};
+use crate::{
+    actor::config::DEFAULT_CHUNK_SIZE,
+    error::{Error, Result},
+};
+
+/// Response body read by chunks, see [Actor::send_stream]
+///
+/// Actor is busy until the stream is read or dropped
+#[derive(Debug)]
+pub struct FrameStream {
+    /// Length of whole frame
+    pub len: usize,
+    pub(crate) rx: Receiver<Result<Vec<u8>>>,
+}
+
+impl FrameStream {
+    /// Next chunk, `None` when frame is over
+    pub async fn next_chunk(&mut self) -> Option<Result<Vec<u8>>> {
+        self.rx.recv().await
+    }
+
+    /// Collect all chunks
+    pub async fn read_to_end(mut self) -> Result<Vec<u8>> {
+        // `len` comes from peer, buffer grows with data actually received
+        let mut out = Vec::with_capacity(self.len.min(DEFAULT_CHUNK_SIZE));
+        while let Some(chunk) = self.next_chunk().await {
+            out.extend_from_slice(&chunk?);
+        }
+        Ok(out)
+    }
+
+    /// Collect all chunks into UTF-8 string
+    pub async fn read_to_string(self) -> Result<String> {
+        let out = self.read_to_end().await?;
+        String::from_utf8(out)
+            .map_err(|e| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+    }
+}
@@ -0,0 +1,337 @@
+//! Define audit module
+//!
+//! Records who changed what: every secured command sent by [Actor]
+//! goes to [ActorConfig::audit] as [AuditRecord], successful or not.
+//! Commands run directly ([Command::execute](crate::command::Command::execute)) are recorded too,
+//! every retry is a record of its own.
+//! Encrypted params (pool passwords, account passwords) are recorded as `<hidden>`,
+//! like in [Display](std::fmt::Display) of [Request](crate::request::Request).
+//!
+//! - Item: [AuditRecord], [AuditSink]
+//! - Sinks: [JsonLinesSink], [MemorySink]
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(doc)]
+use crate::actor::config::ActorConfig;
+use crate::{
+    account::Account,
+    actor::Actor,
+    error::{Error, Result},
+};
+
+/// One secured command sent to miner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Unix time of sending, local clock
+    pub ts: u64,
+    pub addr: String,
+    /// `None` if miner didn't report it on connect
+    pub serial: Option<String>,
+    pub account: Account,
+    pub cmd: String,
+    /// Params as sent, `<hidden>` for encrypted commands
+    pub params: Option<String>,
+    /// Code of miner answer, `None` if there was no answer
+    pub code: Option<i64>,
+    /// Error, `None` on success
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl AuditRecord {
+    /// Record of finished command, sent at `ts`
+    ///
+    /// `params` are recorded as given, so encrypted ones should be hidden by caller.
+    /// `code` is code of answer if command succeeded, failed commands take it from [Error::Api]
+    pub fn new<T>(
+        actor: &Actor,
+        ts: u64,
+        cmd: &str,
+        params: Option<String>,
+        result: &Result<T>,
+        code: impl FnOnce(&T) -> Option<i64>,
+        duration: Duration,
+    ) -> Self {
+        let (code, error) = match result {
+            Ok(out) => (code(out), None),
+            Err(e) => (api_code(e), Some(e.to_string())),
+        };
+        Self {
+            ts,
+            addr: actor.addr.clone(),
+            serial: actor.serial.clone(),
+            account: actor.username,
+            cmd: cmd.to_string(),
+            params,
+            code,
+            error,
+            duration_ms: duration.as_millis().try_into().unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// Code of [Error::Api], also when wrapped with address
+fn api_code(e: &Error) -> Option<i64> {
+    match e {
+        Error::Api { code, .. } => Some(*code),
+        Error::Context { source, .. } => api_code(source),
+        _ => None,
+    }
+}
+
+/// Destination of [AuditRecord]s
+///
+/// Called after every secured command, failures are logged and don't fail the command
+pub trait AuditSink: Debug + Send + Sync {
+    fn record(&self, record: &AuditRecord) -> Result<()>;
+}
+
+/// Appends records to file, one JSON object per line
+///
+/// File is created if missing, existing records are kept.
+/// On unix the file is readable only by owner
+#[derive(Debug)]
+pub struct JsonLinesSink {
+    pub path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl JsonLinesSink {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut options = File::options();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(options.open(path)?),
+        })
+    }
+}
+
+impl AuditSink for JsonLinesSink {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // one write per line, so concurrent actors don't interleave records
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Keeps records in memory, e.g. for tests or own shipping
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    pub records: Mutex<Vec<AuditRecord>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of records so far
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl AuditSink for MemorySink {
+    fn record(&self, record: &AuditRecord) -> Result<()> {
+        self.records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    };
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        actor::{
+            config::ActorConfig,
+            mock::{self, ok_answer},
+        },
+        clock::Clock,
+        command::{
+            Command,
+            get_fan_setting::GetFanSettings,
+            raw::RawCommand,
+            set_fan_temp_offset::SetFanTempOffset,
+            set_miner_pools::{SetMinerPools, SetMinerPoolsParamItem},
+        },
+        password::Password,
+    };
+
+    #[tokio::test]
+    async fn records() {
+        let miner = mock::spawn(|req| {
+            let cmd = req["cmd"].as_str()?;
+            match cmd {
+                "get.device.info" if req["param"] == "miner" => {
+                    Some(ok_answer(cmd, json!({"miner": {"miner-sn": "HTM1"}})))
+                }
+                "get.device.info" => None,
+                "set.fan.temp_offset" => Some(json!({"code": -2, "msg": "out of range"})),
+                _ => Some(ok_answer(cmd, json!("ok"))),
+            }
+        })
+        .await;
+        let sink = Arc::new(MemorySink::new());
+        let config = ActorConfig {
+            audit: Some(sink.clone()),
+            ..Default::default()
+        };
+        let actor = Actor::with_config(&miner, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+        assert_eq!(actor.serial.as_deref(), Some("HTM1"));
+
+        actor.send(&GetFanSettings).await.ok();
+        actor
+            .send(&SetMinerPools(vec![SetMinerPoolsParamItem {
+                pool: "stratum+tcp://pool:3333".into(),
+                worker: "farm.1".into(),
+                password: "secret".into(),
+            }]))
+            .await
+            .ok();
+        assert!(actor.send(&SetFanTempOffset(-99)).await.is_err());
+        let mut raw = RawCommand::new("set.system.led");
+        raw.params = Some(json!("auto"));
+        raw.secured = true;
+        raw.execute(&actor).await.unwrap();
+        // bypassing actor doesn't bypass audit
+        SetFanTempOffset(1).execute(&actor).await.ok();
+        // dynamic path fails and records like typed one
+        let e = actor.send_dyn(&SetFanTempOffset(-99)).await.unwrap_err();
+        assert!(matches!(e.root(), Error::Api { code: -2, .. }));
+
+        let records = sink.records();
+        let cmds: Vec<_> = records.iter().map(|r| r.cmd.as_str()).collect();
+        // unsecured getters aren't recorded
+        assert_eq!(
+            cmds,
+            [
+                "set.miner.pools",
+                "set.fan.temp_offset",
+                "set.system.led",
+                "set.fan.temp_offset",
+                "set.fan.temp_offset"
+            ]
+        );
+        assert_eq!(records[0].params.as_deref(), Some("<hidden>"));
+        assert_eq!(records[0].serial.as_deref(), Some("HTM1"));
+        assert_eq!(records[1].params.as_deref(), Some("-99"));
+        assert_eq!(records[1].code, Some(-2));
+        assert!(records[1].error.as_ref().unwrap().contains("out of range"));
+        assert_eq!(records[2].code, Some(0));
+        assert_eq!(records[2].error, None);
+        assert_eq!(records[4].code, Some(-2));
+    }
+
+    /// Clock, which is moved by test
+    #[derive(Debug)]
+    struct ManualClock(Arc<AtomicU64>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Result<u64> {
+            Ok(self.0.load(Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn sending_time() {
+        let miner = mock::spawn(|req| {
+            let cmd = req["cmd"].as_str()?;
+            match cmd {
+                "get.device.info" if req["param"] == "miner" => {
+                    Some(ok_answer(cmd, json!({"miner": {"miner-sn": "HTM1"}})))
+                }
+                _ => None,
+            }
+        })
+        .await;
+        let sink = Arc::new(MemorySink::new());
+        let now = Arc::new(AtomicU64::new(1_700_000_000));
+        let config = ActorConfig {
+            audit: Some(sink.clone()),
+            clock: Arc::new(ManualClock(now.clone())),
+            ..Default::default()
+        };
+        let actor = Actor::with_config(&miner, Account::Super, Password::Super, config)
+            .await
+            .unwrap();
+
+        // slow command, answered a minute later
+        let work = async {
+            now.fetch_add(60, Ordering::SeqCst);
+            Ok(())
+        };
+        actor
+            .audited(
+                "set.system.led",
+                true,
+                false,
+                || Ok(None),
+                work,
+                |_| Some(0),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sink.records()[0].ts, 1_700_000_000);
+    }
+
+    #[test]
+    fn json_lines() {
+        let path =
+            std::env::temp_dir().join(format!("matroskin-audit-{}.jsonl", std::process::id()));
+        let record = AuditRecord {
+            ts: 1_700_000_000,
+            addr: "10.10.1.17:4433".into(),
+            serial: None,
+            account: Account::Super,
+            cmd: "set.system.reboot".into(),
+            params: None,
+            code: Some(0),
+            error: None,
+            duration_ms: 12,
+        };
+        let sink = JsonLinesSink::open(&path).unwrap();
+        sink.record(&record).unwrap();
+        sink.record(&record).unwrap();
+        drop(sink);
+        JsonLinesSink::open(&path).unwrap().record(&record).unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<AuditRecord> = raw
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines, [record.clone(), record.clone(), record]);
+    }
+}
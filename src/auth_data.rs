@@ -3,18 +3,18 @@
 //! - Item: [AuthData]
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Token-generate_token>
 use aes::Aes256;
-use aes::cipher::{BlockEncrypt, KeyInit};
-use base64_light::base64_encode_bytes;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use base64_light::{base64_decode, base64_encode_bytes};
 #[allow(deprecated)]
 use cipher::generic_array::GenericArray;
 use serde::Serialize;
 use sha256::digest;
 use std::fmt::{Debug, Display};
-use std::time::{SystemTime, UNIX_EPOCH};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::account::Account;
 use crate::actor::Actor;
+use crate::clock::{Clock, SystemClock};
 use crate::command::Command;
 use crate::error::{Error, Result};
 
@@ -58,17 +58,34 @@ impl<'a> AuthData {
         password: impl AsRef<str>,
         salt: &'a str,
     ) -> Result<Self> {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
-        let ts = since_the_epoch.as_secs();
+        Self::for_cmd(C::CMD_NAME, username, password, salt)
+    }
+
+    /// Generate auth data for command name known only at runtime
+    ///
+    /// Stamped with local [SystemClock]
+    pub fn for_cmd(
+        cmd: &str,
+        username: Account,
+        password: impl AsRef<str>,
+        salt: &'a str,
+    ) -> Result<Self> {
+        Self::with_ts(cmd, username, password, salt, SystemClock.now()?)
+    }
 
+    /// Generate auth data with explicit timestamp `ts` (unix seconds)
+    pub fn with_ts(
+        cmd: &str,
+        username: Account,
+        password: impl AsRef<str>,
+        salt: &'a str,
+        ts: u64,
+    ) -> Result<Self> {
         // generate sha256 hex from data and convert it to base64
         //
         // - ApiDoc:
         // https://apidoc.whatsminer.com/#api-Token-generate_token
-        let input_to_hash = format!("{}{}{}{}", C::CMD_NAME, password.as_ref(), salt, ts);
+        let input_to_hash = format!("{}{}{}{}", cmd, password.as_ref(), salt, ts);
         let sha256_hex_digest = digest(input_to_hash);
 
         let sha256_raw_bytes = hex::decode(&sha256_hex_digest)?;
@@ -83,8 +100,15 @@ impl<'a> AuthData {
         })
     }
 
+    /// Generate auth data stamped with miner time ([Actor::now])
     pub fn from_actor<T: Command>(actor: &Actor) -> Result<Self> {
-        Self::new::<T>(actor.username, actor.password.as_ref(), &actor.salt)
+        Self::with_ts(
+            T::CMD_NAME,
+            actor.username,
+            actor.password.as_ref(),
+            &actor.salt,
+            actor.now()?,
+        )
     }
 
     /// Encrypt data using AES-256-ECB
@@ -125,4 +149,119 @@ impl<'a> AuthData {
         // Encode to base64
         Ok(base64_encode_bytes(&buffer))
     }
+
+    /// Decrypt data encrypted by [AuthData::encrypt]
+    ///
+    /// Input is base64 of AES-256-ECB blocks, PKCS7 padding is checked and removed.
+    /// Used for captured traffic and encrypted answers
+    pub fn decrypt(&self, data: impl AsRef<str>) -> Result<Vec<u8>> {
+        let block_size = 16;
+        let mut buffer = base64_decode(data.as_ref().trim());
+        if buffer.is_empty() || !buffer.len().is_multiple_of(block_size) {
+            return Err(Error::DecryptionFailed);
+        }
+
+        let cipher = Aes256::new_from_slice(&self.aes_key).map_err(|_| Error::DecryptionFailed)?;
+
+        for chunk in buffer.chunks_mut(block_size) {
+            #[allow(deprecated)]
+            let mut block = *GenericArray::from_slice(chunk);
+            cipher.decrypt_block(&mut block);
+            chunk.copy_from_slice(&block);
+        }
+
+        // Check and strip PKCS7 padding
+        let padding_len = *buffer.last().ok_or(Error::DecryptionFailed)? as usize;
+        if padding_len == 0
+            || padding_len > block_size
+            || buffer[buffer.len() - padding_len..]
+                .iter()
+                .any(|&b| b as usize != padding_len)
+        {
+            return Err(Error::DecryptionFailed);
+        }
+        buffer.truncate(buffer.len() - padding_len);
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOLS: &str =
+        r#"{"pools":[{"pool":"stratum+tcp://pool.example.com:3333","worker":"w1","passwd":"x"}]}"#;
+
+    /// Known answer: token inputs, token, sha256 hex key and
+    /// ciphertexts of empty, one block and multi-block ([POOLS]) plaintexts
+    ///
+    /// Computed with python `hashlib` and `cryptography` (AES-256-ECB, PKCS7)
+    type Vector = (
+        (&'static str, &'static str, &'static str, u64),
+        &'static str,
+        &'static str,
+        [&'static str; 3],
+    );
+
+    const VECTORS: &[Vector] = &[
+        (
+            ("get.fan.setting", "super", "BQ5hoXV9", 1_700_000_000),
+            "xVvBQ+Uz",
+            "c55bc143e533f39b96e2d82ce14a4b77051f3b1a3edb0dc3b760b01dd92c571b",
+            [
+                "m7Ge7N9Q2zEGHo0misG4lA==",
+                "uqUkRlA4PtPsCzPG2wsRsZuxnuzfUNsxBh6NJorBuJQ=",
+                "MSYqqGNVzTrl9YtaIH1UAaiuqDLvA9LRMI8RpX1zo4KUirSvblskQLK70KYn2eZ6IQ/Jo39UHIIhDTxSYKUHbu8lRoDXEF6oI5Pjih5QnulL9GDcO0HlbXiNrBvPdR85",
+            ],
+        ),
+        (
+            ("set.miner.fastboot", "test123", "test123", 1_745_325_700),
+            "BE/3jqMb",
+            "044ff78ea31b5b3de68bbad22076f772e5f1f936a269c215309587c93c4f24a5",
+            [
+                "kFx2jKU/umKZ6GaygJp8IQ==",
+                "3wvK7pYa0PQ0UvAEHeg6qZBcdoylP7pimehmsoCafCE=",
+                "Ip7qR6AN6IlczEZBUVCSAzD467uafZWHuNdorCZHKysMn5XWV2EIP3acd0TZc6PTdw698Llp/iQaK3Mu777p7uRtWbr+ua+y7hVVZcenQIlwLzm8MYQ9DbqDYRsYgrTk",
+            ],
+        ),
+    ];
+
+    #[test]
+    fn token() {
+        for ((cmd, password, salt, ts), token, key, _) in VECTORS {
+            let auth = AuthData::with_ts(cmd, Account::Super, password, salt, *ts).unwrap();
+            assert_eq!(auth.ts, *ts);
+            assert_eq!(auth.token, *token, "{cmd}");
+            assert_eq!(hex::encode(&auth.aes_key), *key);
+        }
+    }
+
+    #[test]
+    fn encrypt() {
+        for ((cmd, password, salt, ts), _, _, ciphertexts) in VECTORS {
+            let auth = AuthData::with_ts(cmd, Account::Super, password, salt, *ts).unwrap();
+            for (plain, cipher) in ["", "0123456789abcdef", POOLS].iter().zip(ciphertexts) {
+                assert_eq!(auth.encrypt(plain).unwrap(), *cipher, "{cmd}: {plain:?}");
+                assert_eq!(auth.decrypt(cipher).unwrap(), plain.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn decrypt_invalid() {
+        let ((cmd, password, salt, ts), ..) = VECTORS[0];
+        let auth = AuthData::with_ts(cmd, Account::Super, password, salt, ts).unwrap();
+        let other = AuthData::with_ts(cmd, Account::Super, "admin", salt, ts).unwrap();
+        let cipher = auth.encrypt(POOLS).unwrap();
+
+        assert!(matches!(
+            other.decrypt(&cipher),
+            Err(Error::DecryptionFailed)
+        ));
+        assert!(matches!(auth.decrypt(""), Err(Error::DecryptionFailed)));
+        assert!(matches!(
+            auth.decrypt(&cipher[..cipher.len() - 8]),
+            Err(Error::DecryptionFailed)
+        ));
+    }
 }
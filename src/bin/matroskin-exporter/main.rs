@@ -0,0 +1,109 @@
+//! # `matroskin-exporter` Prometheus exporter
+//!
+//! Polls miners on interval and serves the last results on `/metrics`.
+//!
+//! ```sh
+//! matroskin-exporter --cidr 10.10.1.0/24 --credentials miners.toml --listen 0.0.0.0:9897
+//! ```
+#[path = "../matroskin/credentials.rs"]
+mod credentials;
+#[path = "../matroskin/target.rs"]
+mod target;
+
+use std::{net::SocketAddr, process::ExitCode, sync::Arc, time::Duration};
+
+use clap::Parser;
+use matroskin::exporter::{Exporter, ExporterConfig};
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+use crate::{credentials::CredentialArgs, target::TargetArgs};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "matroskin-exporter",
+    version,
+    about = "Prometheus exporter for WhatsMiner api v3"
+)]
+struct Cli {
+    #[command(flatten)]
+    targets: TargetArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    /// Address of `/metrics` endpoint
+    #[arg(long, env = "MATROSKIN_LISTEN", default_value = "0.0.0.0:9897")]
+    listen: SocketAddr,
+    /// Seconds between polls of the fleet
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+    /// Max seconds of poll of one miner
+    #[arg(long, default_value_t = 10)]
+    timeout: u64,
+    /// Miners polled at the same time
+    #[arg(long, default_value_t = 16)]
+    parallel: usize,
+    /// More logs (`-v` info, `-vv` debug), `RUST_LOG` overrides it
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
+
+fn init_logs(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        _ => "debug",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .compact()
+        .init();
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logs(cli.verbose);
+
+    let targets = match cli.targets.resolve() {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let provider = match cli.credentials.provider() {
+        Ok(provider) => Arc::new(provider),
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let listener = match TcpListener::bind(cli.listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("error: can't listen on {}: {e}", cli.listen);
+            return ExitCode::from(2);
+        }
+    };
+
+    let exporter = Arc::new(Exporter::new(
+        targets,
+        provider,
+        ExporterConfig {
+            interval: Duration::from_secs(cli.interval.max(1)),
+            timeout: Duration::from_secs(cli.timeout.max(1)),
+            parallel: cli.parallel,
+            ..Default::default()
+        },
+    ));
+    tokio::spawn(exporter.clone().run());
+    match exporter.serve(listener).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
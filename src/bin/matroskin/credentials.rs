@@ -0,0 +1,117 @@
+//! Define credential selection module
+//!
+//! Priority: credential file (by address, MAC or serial), then for miners it doesn't know
+//! `--password` (or `MATROSKIN_PASSWORD`) or default password of account.
+//!
+//! - Item: [CredentialArgs]
+use std::path::PathBuf;
+
+use clap::Args;
+use matroskin::{
+    account::Account,
+    credentials::{CredentialProvider, Credentials, Fallback, FileProvider},
+    error::Result,
+    password::Password,
+};
+
+/// How to authenticate
+#[derive(Debug, Clone, Args)]
+pub struct CredentialArgs {
+    /// Account for miners without credential file entry
+    #[arg(long, env = "MATROSKIN_ACCOUNT", default_value = "super")]
+    pub account: Account,
+    /// Password for miners without credential file entry
+    #[arg(long, env = "MATROSKIN_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+    /// TOML or JSON file with per-miner credentials
+    #[arg(long, env = "MATROSKIN_CREDENTIALS", value_name = "PATH")]
+    pub credentials: Option<PathBuf>,
+}
+
+impl CredentialArgs {
+    /// Providers in order of priority
+    ///
+    /// Password from flag or account default is a [Fallback],
+    /// so it never shadows MAC and serial entries of the file
+    pub fn provider(&self) -> Result<Vec<Box<dyn CredentialProvider>>> {
+        let mut out: Vec<Box<dyn CredentialProvider>> = Vec::new();
+        if let Some(path) = &self.credentials {
+            out.push(Box::new(FileProvider::load(path)?));
+        }
+        let password = match &self.password {
+            Some(password) => Password::from(password.as_str()),
+            None => Password::from(self.account),
+        };
+        out.push(Box::new(Fallback(Credentials::new(self.account, password))));
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matroskin::credentials::MinerId;
+
+    use super::*;
+
+    #[test]
+    fn priority() {
+        let mut args = CredentialArgs {
+            account: Account::User1,
+            password: None,
+            credentials: None,
+        };
+        let provider = args.provider().unwrap();
+        assert!(
+            provider
+                .credentials(&MinerId::addr("10.0.0.1"))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            provider.fallback().unwrap().unwrap().password.as_ref(),
+            "user1"
+        );
+
+        args.password = Some("secret".into());
+        let c = args.provider().unwrap().fallback().unwrap().unwrap();
+        assert_eq!(c.password.as_ref(), "secret");
+    }
+
+    #[test]
+    fn serial_entry() {
+        let path =
+            std::env::temp_dir().join(format!("matroskin-cli-creds-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[[miner]]\nmatch = \"HTM50S*\"\naccount = \"super\"\npassword = \"by-serial\"\n",
+        )
+        .unwrap();
+        let args = CredentialArgs {
+            account: Account::Super,
+            password: Some("flag".into()),
+            credentials: Some(path.clone()),
+        };
+        let provider = args.provider();
+        std::fs::remove_file(&path).unwrap();
+        let provider = provider.unwrap();
+
+        // address lookup misses, so the actor reads the serial and asks again
+        assert!(
+            provider
+                .credentials(&MinerId::addr("10.0.0.1:4433"))
+                .unwrap()
+                .is_none()
+        );
+        let id = MinerId {
+            addr: "10.0.0.1:4433",
+            mac: Some("C4:11:04:00:00:01"),
+            serial: Some("HTM50SVK30240912"),
+        };
+        let c = provider.credentials(&id).unwrap().unwrap();
+        assert_eq!(c.password.as_ref(), "by-serial");
+        assert_eq!(
+            provider.fallback().unwrap().unwrap().password.as_ref(),
+            "flag"
+        );
+    }
+}
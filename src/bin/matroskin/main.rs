@@ -0,0 +1,756 @@
+//! # `matroskin` command-line tool
+//!
+//! Runs one command on many miners and prints results as table, JSON or JSON Lines.
+//!
+//! ```sh
+//! matroskin -a 10.10.1.17 info
+//! matroskin --cidr 10.10.1.0/24 --credentials miners.toml -o jsonl fan get
+//! matroskin -f rack1.txt fastboot on
+//! matroskin -a 10.10.1.17 miner set --power-mode low --power-limit 3000
+//! ```
+mod credentials;
+mod output;
+mod target;
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use matroskin::{
+    actor::{Actor, config::ActorConfig},
+    audit::JsonLinesSink,
+    command::{
+        get_device_custom_data::GetDeviceCustomData,
+        get_device_info::GetDeviceInfo,
+        get_fan_setting::GetFanSettings,
+        get_miner_setting::GetMinerSettings,
+        get_miner_status::{GetMinerStatus, GetMinerStatusParam},
+        get_system_setting::GetSystemSetting,
+        raw::RawCommand,
+        set_device_custom_data::SetDeviceCustomData,
+        set_fan_poweroff_cool::SetFanPoweroffCool,
+        set_fan_temp_offset::SetFanTempOffset,
+        set_fan_zero_speed::SetFanZeroSpeed,
+        set_miner_fastboot::SetMinerFastboot,
+        set_miner_pools::{SetMinerPools, SetMinerPoolsParamItem},
+        set_miner_power_limit::SetMinerPowerLimit,
+        set_miner_power_mode::{PowerMode, SetMinerPowerMode},
+        set_miner_power_percent::SetMinerPowerPercent,
+        set_system_factory_reset::SetSystemFactoryReset,
+        set_system_hostname::SetSystemHostname,
+        set_system_ntp_server::SetSystemNtpServer,
+        set_system_reboot::SetSystemReboot,
+        set_system_timezone::SetSystemTimezone,
+        set_user_change_passwd::SetUserChangePasswd,
+    },
+    credentials::CredentialProvider,
+    error::Result,
+    reconcile::{Reconciler, Spec},
+    rotation::Rotation,
+    snapshot::{self, RestoreOptions, Snapshot},
+};
+use serde_json::{Value, json};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    credentials::CredentialArgs,
+    output::{Format, Outcome, render},
+    target::TargetArgs,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "matroskin",
+    version,
+    about = "WhatsMiner api v3 command-line tool"
+)]
+struct Cli {
+    #[command(flatten)]
+    targets: TargetArgs,
+    #[command(flatten)]
+    credentials: CredentialArgs,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Table)]
+    output: Format,
+    /// Miners processed at the same time
+    #[arg(long, default_value_t = 16)]
+    parallel: usize,
+    /// Append secured commands to JSON Lines file, see `matroskin::audit`
+    #[arg(long, env = "MATROSKIN_AUDIT")]
+    audit: Option<PathBuf>,
+    /// More logs (`-v` info, `-vv` debug), `RUST_LOG` overrides it
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    #[command(subcommand)]
+    command: Cmd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Switch {
+    On,
+    Off,
+}
+
+impl From<Switch> for u8 {
+    fn from(value: Switch) -> Self {
+        (value == Switch::On) as u8
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Cmd {
+    #[command(flatten)]
+    Job(Job),
+    /// Replace passwords with random ones, see `matroskin::rotation`
+    Rotate {
+        /// Journal with old and new passwords, keep it to resume
+        #[arg(long)]
+        journal: PathBuf,
+        /// Credential file, verified passwords are merged into it
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Configuration snapshots, see `matroskin::snapshot`
+    #[command(subcommand)]
+    Snapshot(SnapshotCmd),
+}
+
+/// Commands, which run on every miner at once
+#[derive(Debug, Clone, Subcommand)]
+enum Job {
+    /// Device information (`get.device.info`)
+    Info,
+    /// Hash rate, pools and boards (`get.miner.status`)
+    Status {
+        /// Parts of status
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "summary")]
+        parts: Vec<StatusPart>,
+    },
+    /// Custom data
+    #[command(subcommand)]
+    CustomData(CustomDataCmd),
+    /// Fan settings
+    #[command(subcommand)]
+    Fan(FanCmd),
+    /// Miner settings
+    #[command(subcommand)]
+    Miner(MinerCmd),
+    /// System settings
+    #[command(subcommand)]
+    System(SystemCmd),
+    /// Mining pools
+    #[command(subcommand)]
+    Pools(PoolsCmd),
+    /// Fast boot (`set.miner.fastboot`)
+    Fastboot { state: Switch },
+    /// Reboot (`set.system.reboot`)
+    Reboot {
+        /// Confirm action
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Reset to factory settings (`set.system.factory_reset`)
+    FactoryReset {
+        /// Confirm action
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Set the same password on every miner (`set.user.change_passwd`)
+    Passwd {
+        #[arg(long, env = "MATROSKIN_NEW_PASSWORD", hide_env_values = true)]
+        new: String,
+    },
+    /// Bring miners to desired state of spec file, see `matroskin::reconcile`
+    ///
+    /// Only prints planned changes unless `--apply` is given
+    Reconcile {
+        /// TOML or JSON spec with `[[group]]` entries
+        #[arg(long)]
+        spec: PathBuf,
+        /// Run setters
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Commands, which account may use
+    Permissions,
+    /// Any command by name
+    Raw {
+        cmd: String,
+        /// Params as JSON, plain string if it isn't JSON
+        #[arg(long)]
+        param: Option<String>,
+        #[arg(long)]
+        secured: bool,
+        #[arg(long)]
+        encrypted: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StatusPart {
+    Summary,
+    Pools,
+    Edevs,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum CustomDataCmd {
+    /// `get.device.custom_data`
+    Get,
+    /// `set.device.custom_data`, given keys replace current ones
+    Set {
+        /// JSON object, like `{"msg0": "rack 1"}`
+        #[arg(value_parser = parse_object)]
+        data: serde_json::Map<String, Value>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum MinerCmd {
+    /// `get.miner.setting`
+    Get,
+    /// `set.miner.power_*`, only given options are sent
+    Set {
+        #[arg(long)]
+        power_mode: Option<PowerMode>,
+        /// Watts
+        #[arg(long)]
+        power_limit: Option<u32>,
+        /// Share of power limit
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        power_percent: Option<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum SystemCmd {
+    /// `get.system.setting`
+    Get,
+    /// `set.system.*`, only given options are sent
+    Set {
+        #[arg(long)]
+        hostname: Option<String>,
+        /// Replaces all servers, repeat for more
+        #[arg(long = "ntp-server")]
+        ntp_servers: Vec<String>,
+        /// POSIX timezone, like `CST-8`
+        #[arg(long, requires = "zonename")]
+        timezone: Option<String>,
+        /// Zone name, like `Asia/Shanghai`
+        #[arg(long, requires = "timezone")]
+        zonename: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum FanCmd {
+    /// `get.fan.setting`
+    Get,
+    /// `set.fan.*`, only given options are sent
+    Set {
+        #[arg(long)]
+        poweroff_cool: Option<Switch>,
+        #[arg(long)]
+        zero_speed: Option<Switch>,
+        #[arg(long, allow_negative_numbers = true)]
+        temp_offset: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum SnapshotCmd {
+    #[command(flatten)]
+    Job(SnapshotJob),
+    /// Compare two snapshot files, no miners are contacted
+    Diff { left: PathBuf, right: PathBuf },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum SnapshotJob {
+    /// Save snapshot of every miner to directory
+    Take {
+        #[arg(long)]
+        dir: PathBuf,
+    },
+    /// Write settings of snapshot back, only prints planned changes unless `--apply` is given
+    Restore {
+        file: PathBuf,
+        /// Run setters
+        #[arg(long)]
+        apply: bool,
+        /// Restore on miner with other serial number than snapshot
+        #[arg(long)]
+        force: bool,
+        /// Pool password, once per pool of snapshot; pools aren't restored without them
+        #[arg(long = "pool-password")]
+        pool_passwords: Vec<String>,
+    },
+}
+
+/// Work done on every connected miner
+#[derive(Debug, Clone)]
+enum Task {
+    Job(Job),
+    Snapshot(SnapshotJob),
+}
+
+/// Work, which needs miners
+enum Work {
+    Rotate { journal: PathBuf, out: PathBuf },
+    Run(Task),
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum PoolsCmd {
+    /// `set.miner.pools`
+    Set {
+        /// `URL,WORKER[,PASSWORD]`, up to 3 in priority order
+        #[arg(long = "pool", required = true, value_parser = parse_pool)]
+        pools: Vec<(String, String, String)>,
+    },
+}
+
+fn parse_pool(raw: &str) -> std::result::Result<(String, String, String), String> {
+    let mut parts = raw.splitn(3, ',');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(pool), Some(worker), password) if !pool.is_empty() && !worker.is_empty() => Ok((
+            pool.to_string(),
+            worker.to_string(),
+            password.unwrap_or_default().to_string(),
+        )),
+        _ => Err("expected URL,WORKER[,PASSWORD]".into()),
+    }
+}
+
+fn parse_object(raw: &str) -> std::result::Result<serde_json::Map<String, Value>, String> {
+    match serde_json::from_str(raw) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err("expected JSON object".into()),
+    }
+}
+
+/// Response message as JSON
+fn msg<T: serde::Serialize>(response: matroskin::response::Response<T>) -> Result<Value> {
+    Ok(serde_json::to_value(response.msg)?)
+}
+
+/// Run task on connected miner
+async fn run(task: &Task, actor: &Actor) -> Result<Value> {
+    match task {
+        Task::Job(job) => run_job(job, actor).await,
+        Task::Snapshot(SnapshotJob::Take { dir }) => {
+            let snapshot = Snapshot::capture(actor).await?;
+            let path = dir.join(snapshot.file_name());
+            snapshot.save(&path)?;
+            Ok(json!(path))
+        }
+        Task::Snapshot(SnapshotJob::Restore {
+            file,
+            apply,
+            force,
+            pool_passwords,
+        }) => {
+            let options = RestoreOptions {
+                allow_other_miner: *force,
+                pool_passwords: (!pool_passwords.is_empty()).then(|| pool_passwords.clone()),
+            };
+            let report = Snapshot::load(file)?
+                .restore(actor, Reconciler::new(!apply), &options)
+                .await?;
+            Ok(serde_json::to_value(report)?)
+        }
+    }
+}
+
+/// Run command on connected miner
+async fn run_job(job: &Job, actor: &Actor) -> Result<Value> {
+    match job {
+        Job::Info => msg(actor.send(&GetDeviceInfo::default()).await?),
+        Job::Status { parts } => {
+            let cmd = GetMinerStatus(GetMinerStatusParam {
+                summary: parts.contains(&StatusPart::Summary),
+                pools: parts.contains(&StatusPart::Pools),
+                edevs: parts.contains(&StatusPart::Edevs),
+            });
+            msg(actor.send(&cmd).await?)
+        }
+        Job::CustomData(CustomDataCmd::Get) => msg(actor.send(&GetDeviceCustomData).await?),
+        Job::CustomData(CustomDataCmd::Set { data }) => {
+            let mut current = serde_json::to_value(actor.send(&GetDeviceCustomData).await?.msg)?;
+            if let Value::Object(current) = &mut current {
+                current.extend(data.clone());
+            }
+            let data = serde_json::from_value(current)?;
+            msg(actor.send(&SetDeviceCustomData(data)).await?)
+        }
+        Job::Fan(FanCmd::Get) => msg(actor.send(&GetFanSettings).await?),
+        Job::Fan(FanCmd::Set {
+            poweroff_cool,
+            zero_speed,
+            temp_offset,
+        }) => {
+            let mut out = serde_json::Map::new();
+            if let Some(v) = poweroff_cool {
+                let r = actor.send(&SetFanPoweroffCool((*v).into())).await?;
+                out.insert("poweroff-cool".into(), msg(r)?);
+            }
+            if let Some(v) = zero_speed {
+                let r = actor.send(&SetFanZeroSpeed((*v).into())).await?;
+                out.insert("zero-speed".into(), msg(r)?);
+            }
+            if let Some(v) = temp_offset {
+                let r = actor.send(&SetFanTempOffset(*v)).await?;
+                out.insert("temp-offset".into(), msg(r)?);
+            }
+            Ok(Value::Object(out))
+        }
+        Job::Miner(MinerCmd::Get) => msg(actor.send(&GetMinerSettings).await?),
+        Job::Miner(MinerCmd::Set {
+            power_mode,
+            power_limit,
+            power_percent,
+        }) => {
+            let mut out = serde_json::Map::new();
+            if let Some(v) = power_mode {
+                let r = actor.send(&SetMinerPowerMode(*v)).await?;
+                out.insert("power-mode".into(), msg(r)?);
+            }
+            if let Some(v) = power_limit {
+                let r = actor.send(&SetMinerPowerLimit(*v)).await?;
+                out.insert("power-limit".into(), msg(r)?);
+            }
+            if let Some(v) = power_percent {
+                let r = actor.send(&SetMinerPowerPercent(*v)).await?;
+                out.insert("power-percent".into(), msg(r)?);
+            }
+            Ok(Value::Object(out))
+        }
+        Job::System(SystemCmd::Get) => msg(actor.send(&GetSystemSetting).await?),
+        Job::System(SystemCmd::Set {
+            hostname,
+            ntp_servers,
+            timezone,
+            zonename,
+        }) => {
+            let mut out = serde_json::Map::new();
+            if let Some(v) = hostname {
+                let r = actor.send(&SetSystemHostname(v.clone())).await?;
+                out.insert("hostname".into(), msg(r)?);
+            }
+            if !ntp_servers.is_empty() {
+                let r = actor.send(&SetSystemNtpServer(ntp_servers.clone())).await?;
+                out.insert("ntp-server".into(), msg(r)?);
+            }
+            if let (Some(timezone), Some(zonename)) = (timezone, zonename) {
+                let cmd = SetSystemTimezone {
+                    timezone: timezone.clone(),
+                    zonename: zonename.clone(),
+                };
+                out.insert("timezone".into(), msg(actor.send(&cmd).await?)?);
+            }
+            Ok(Value::Object(out))
+        }
+        Job::Pools(PoolsCmd::Set { pools }) => {
+            let pools = pools
+                .iter()
+                .map(|(pool, worker, password)| SetMinerPoolsParamItem {
+                    pool: pool.clone(),
+                    worker: worker.clone(),
+                    password: password.clone(),
+                })
+                .collect();
+            msg(actor.send(&SetMinerPools(pools)).await?)
+        }
+        Job::Fastboot { state } => msg(actor.send(&SetMinerFastboot(*state == Switch::On)).await?),
+        Job::Reboot { .. } => msg(actor.send(&SetSystemReboot).await?),
+        Job::FactoryReset { .. } => msg(actor.send(&SetSystemFactoryReset).await?),
+        Job::Passwd { new } => {
+            let cmd =
+                SetUserChangePasswd::new(actor.username, actor.password.as_ref(), new.as_str());
+            msg(actor.send(&cmd).await?)
+        }
+        Job::Reconcile { spec, apply } => {
+            let spec = Spec::load(spec)?;
+            match Reconciler::new(!apply).reconcile_spec(actor, &spec).await? {
+                Some(report) => Ok(serde_json::to_value(report)?),
+                None => Ok(json!("no matching group")),
+            }
+        }
+        Job::Permissions => Ok(json!({
+            "account": actor.username,
+            "permission": actor.permission.map(|p| p.to_string()),
+            "commands": actor.allowed_commands(),
+        })),
+        Job::Raw {
+            cmd,
+            param,
+            secured,
+            encrypted,
+        } => {
+            let mut raw = RawCommand::new(cmd.as_str());
+            raw.params = param
+                .as_deref()
+                .map(|p| serde_json::from_str(p).unwrap_or_else(|_| Value::String(p.into())));
+            raw.secured |= *secured || *encrypted;
+            raw.encrypted = *encrypted;
+            Ok(serde_json::to_value(raw.execute(actor).await?)?)
+        }
+    }
+}
+
+/// Connect to every target and run command, results keep order of targets
+async fn run_all(
+    task: Task,
+    targets: Vec<String>,
+    provider: Arc<dyn CredentialProvider>,
+    config: ActorConfig,
+    parallel: usize,
+) -> Vec<Outcome> {
+    let limit = Arc::new(Semaphore::new(parallel.max(1)));
+    let mut tasks = JoinSet::new();
+    for (i, addr) in targets.into_iter().enumerate() {
+        let (task, provider, config, limit) = (
+            task.clone(),
+            provider.clone(),
+            config.clone(),
+            limit.clone(),
+        );
+        tasks.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            let work = async {
+                let actor = Actor::with_provider(&addr, provider.as_ref(), config).await?;
+                run(&task, &actor).await
+            };
+            let result = work.await.map_err(|e| e.with_addr(&addr));
+            (i, Outcome { addr, result })
+        });
+    }
+    let mut out: Vec<(usize, Outcome)> = tasks.join_all().await;
+    out.sort_by_key(|(i, _)| *i);
+    out.into_iter().map(|(_, o)| o).collect()
+}
+
+fn init_logs(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        _ => "debug",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .compact()
+        .init();
+}
+
+/// Compare two snapshot files, exit code follows diff(1)
+fn diff(left: &Path, right: &Path) -> ExitCode {
+    match (Snapshot::load(left), Snapshot::load(right)) {
+        (Ok(left), Ok(right)) => {
+            let differences = snapshot::diff(&left, &right);
+            for d in &differences {
+                println!("{d}");
+            }
+            // like diff(1): 1 if snapshots differ
+            ExitCode::from(u8::from(!differences.is_empty()))
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    init_logs(cli.verbose);
+
+    if let Cmd::Job(Job::Reboot { yes: false } | Job::FactoryReset { yes: false }) = cli.command {
+        eprintln!("error: destructive command, confirm with --yes");
+        return ExitCode::from(2);
+    }
+    let work = match cli.command.clone() {
+        Cmd::Snapshot(SnapshotCmd::Diff { left, right }) => return diff(&left, &right),
+        Cmd::Rotate { journal, out } => Work::Rotate { journal, out },
+        Cmd::Job(job) => Work::Run(Task::Job(job)),
+        Cmd::Snapshot(SnapshotCmd::Job(job)) => Work::Run(Task::Snapshot(job)),
+    };
+    if let Cmd::Snapshot(SnapshotCmd::Job(SnapshotJob::Take { dir })) = &cli.command
+        && let Err(e) = std::fs::create_dir_all(dir)
+    {
+        eprintln!("error: {}: {e}", dir.display());
+        return ExitCode::from(2);
+    }
+    if let Cmd::Snapshot(SnapshotCmd::Job(SnapshotJob::Restore { file: spec, .. })) = &cli.command
+        && let Err(e) = Snapshot::load(spec)
+    {
+        eprintln!("error: {}: {e}", spec.display());
+        return ExitCode::from(2);
+    }
+    if let Cmd::Job(Job::Reconcile { spec, .. }) = &cli.command
+        && let Err(e) = Spec::load(spec)
+    {
+        eprintln!("error: {}: {e}", spec.display());
+        return ExitCode::from(2);
+    }
+    let targets = match cli.targets.resolve() {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let provider = match cli.credentials.provider() {
+        Ok(provider) => Arc::new(provider),
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut config = ActorConfig {
+        // mask layout is assumed, it's read only when asked for
+        check_permission: matches!(cli.command, Cmd::Job(Job::Permissions)),
+        ..Default::default()
+    };
+    if let Some(path) = &cli.audit {
+        match JsonLinesSink::open(path) {
+            Ok(sink) => config.audit = Some(Arc::new(sink)),
+            Err(e) => {
+                eprintln!("error: {}: {e}", path.display());
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let outcomes = match work {
+        Work::Rotate { journal, out } => match Rotation::open(journal, out) {
+            Ok(mut rotation) => rotation
+                .rotate_all(&targets, provider.as_ref())
+                .await
+                .into_iter()
+                .map(|(addr, result)| Outcome {
+                    addr,
+                    result: result.map(|_| json!("rotated")),
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("error: {e}");
+                return ExitCode::from(2);
+            }
+        },
+        Work::Run(task) => run_all(task, targets, provider, config, cli.parallel).await,
+    };
+
+    println!("{}", render(cli.output, &outcomes));
+    if outcomes.iter().all(|o| o.result.is_ok()) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn args() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "matroskin",
+            "-a",
+            "10.0.0.1",
+            "-o",
+            "jsonl",
+            "fan",
+            "set",
+            "--temp-offset",
+            "-5",
+            "--zero-speed",
+            "on",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, Format::Jsonl);
+        assert!(matches!(
+            cli.command,
+            Cmd::Job(Job::Fan(FanCmd::Set {
+                temp_offset: Some(-5),
+                zero_speed: Some(Switch::On),
+                poweroff_cool: None
+            }))
+        ));
+
+        let cli = Cli::try_parse_from([
+            "matroskin",
+            "-a",
+            "10.0.0.1",
+            "pools",
+            "set",
+            "--pool",
+            "stratum+tcp://pool:3333,w.1",
+        ])
+        .unwrap();
+        let Cmd::Job(Job::Pools(PoolsCmd::Set { pools })) = cli.command else {
+            panic!("pools set expected");
+        };
+        assert_eq!(pools[0].1, "w.1");
+        assert!(parse_pool("stratum+tcp://pool:3333").is_err());
+    }
+
+    #[test]
+    fn setters() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["matroskin", "-a", "10.0.0.1"].iter().chain(args))
+                .map(|cli| cli.command)
+        };
+        assert!(matches!(
+            parse(&[
+                "miner",
+                "set",
+                "--power-mode",
+                "low",
+                "--power-percent",
+                "50"
+            ])
+            .unwrap(),
+            Cmd::Job(Job::Miner(MinerCmd::Set {
+                power_mode: Some(PowerMode::Low),
+                power_limit: None,
+                power_percent: Some(50)
+            }))
+        ));
+        assert!(parse(&["miner", "set", "--power-percent", "150"]).is_err());
+        // setter needs both
+        assert!(parse(&["system", "set", "--timezone", "CST-8"]).is_err());
+        let Cmd::Job(Job::System(SystemCmd::Set { ntp_servers, .. })) = parse(&[
+            "system",
+            "set",
+            "--ntp-server",
+            "a.pool",
+            "--ntp-server",
+            "b.pool",
+        ])
+        .unwrap() else {
+            panic!("system set expected");
+        };
+        assert_eq!(ntp_servers, ["a.pool", "b.pool"]);
+        let Cmd::Job(Job::Status { parts }) =
+            parse(&["status", "--parts", "summary,pools"]).unwrap()
+        else {
+            panic!("status expected");
+        };
+        assert_eq!(parts, [StatusPart::Summary, StatusPart::Pools]);
+        assert!(parse(&["custom-data", "set", r#"{"msg0": "rack 1"}"#]).is_ok());
+        assert!(parse(&["custom-data", "set", "rack 1"]).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Define output module
+//!
+//! - Table: one row per field, for humans
+//! - JSON: array of results
+//! - JSON Lines: one result per line, for scripts and log pipelines
+//!
+//! - Item: [Format], [Outcome]
+use clap::ValueEnum;
+use matroskin::error::Error;
+use serde_json::{Value, json};
+
+/// Output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Jsonl,
+}
+
+/// Result of command on one miner
+#[derive(Debug)]
+pub struct Outcome {
+    pub addr: String,
+    pub result: Result<Value, Error>,
+}
+
+impl Outcome {
+    pub fn to_json(&self) -> Value {
+        match &self.result {
+            Ok(result) => json!({ "addr": self.addr, "ok": true, "result": result }),
+            Err(e) => json!({
+                "addr": self.addr,
+                "ok": false,
+                "kind": format!("{:?}", e.kind()),
+                "error": e.to_string(),
+            }),
+        }
+    }
+}
+
+/// Render outcomes in format
+pub fn render(format: Format, outcomes: &[Outcome]) -> String {
+    match format {
+        Format::Json => {
+            let all: Vec<Value> = outcomes.iter().map(Outcome::to_json).collect();
+            serde_json::to_string_pretty(&all).unwrap_or_default()
+        }
+        Format::Jsonl => outcomes
+            .iter()
+            .map(|o| o.to_json().to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Format::Table => table(outcomes),
+    }
+}
+
+fn table(outcomes: &[Outcome]) -> String {
+    let mut rows = vec![("ADDR".to_string(), "FIELD".to_string(), "VALUE".to_string())];
+    for outcome in outcomes {
+        let mut fields = Vec::new();
+        match &outcome.result {
+            Ok(value) => flatten("", value, &mut fields),
+            Err(e) => fields.push(("error".to_string(), e.to_string())),
+        }
+        if fields.is_empty() {
+            fields.push(("result".to_string(), "ok".to_string()));
+        }
+        for (field, value) in fields {
+            rows.push((outcome.addr.clone(), field, value));
+        }
+    }
+    let addr_w = rows.iter().map(|r| r.0.len()).max().unwrap_or_default();
+    let field_w = rows.iter().map(|r| r.1.len()).max().unwrap_or_default();
+    rows.iter()
+        .map(|(addr, field, value)| format!("{addr:addr_w$}  {field:field_w$}  {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Leaf values with dotted paths
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+    let key = |k: &str| {
+        if prefix.is_empty() {
+            k.to_string()
+        } else {
+            format!("{prefix}.{k}")
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                flatten(&key(k), v, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                flatten(&key(&i.to_string()), v, out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => out.push((prefix.to_string(), s.clone())),
+        v => out.push((prefix.to_string(), v.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes() -> Vec<Outcome> {
+        vec![
+            Outcome {
+                addr: "10.0.0.1:4433".into(),
+                result: Ok(json!({"fan": {"zero-speed": 0}, "pools": ["a"]})),
+            },
+            Outcome {
+                addr: "10.0.0.2:4433".into(),
+                result: Err(Error::EmptyFrame),
+            },
+        ]
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            render(Format::Table, &outcomes()),
+            "ADDR           FIELD           VALUE\n\
+             10.0.0.1:4433  fan.zero-speed  0\n\
+             10.0.0.1:4433  pools.0         a\n\
+             10.0.0.2:4433  error           Received zero-length frame"
+        );
+        let lines = render(Format::Jsonl, &outcomes());
+        let second: Value = serde_json::from_str(lines.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(second["ok"], false);
+        assert_eq!(second["kind"], "Protocol");
+        let all: Value = serde_json::from_str(&render(Format::Json, &outcomes())).unwrap();
+        assert_eq!(all[0]["result"]["pools"][0], "a");
+    }
+}
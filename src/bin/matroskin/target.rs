@@ -0,0 +1,145 @@
+//! Define target selection module
+//!
+//! Miners are selected by addresses, files with addresses and CIDR ranges.
+//!
+//! - Item: [TargetArgs]
+use std::{collections::HashSet, net::Ipv4Addr, path::PathBuf};
+
+use clap::Args;
+
+/// Default port of api v3
+pub const DEFAULT_PORT: u16 = 4433;
+/// Biggest CIDR range, protects from scanning whole networks by typo
+pub const MAX_RANGE: u32 = 1 << 16;
+
+/// Which miners to talk to
+#[derive(Debug, Clone, Args)]
+pub struct TargetArgs {
+    /// Miner address, `host` or `host:port` (repeatable)
+    #[arg(short, long = "addr", value_name = "ADDR")]
+    pub addrs: Vec<String>,
+    /// File with addresses or CIDR ranges, one per line, `#` starts comment
+    #[arg(short, long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+    /// IPv4 range like `10.10.1.0/24` (repeatable)
+    #[arg(long, value_name = "CIDR")]
+    pub cidr: Vec<String>,
+    /// Port for addresses without it
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+}
+
+impl TargetArgs {
+    /// Addresses with ports in order of appearance, without duplicates
+    pub fn resolve(&self) -> Result<Vec<String>, String> {
+        let mut items: Vec<String> = self.addrs.clone();
+        items.extend(self.cidr.iter().cloned());
+        if let Some(path) = &self.file {
+            let raw = std::fs::read_to_string(path)
+                .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+            items.extend(parse_lines(&raw));
+        }
+
+        let mut out: Vec<String> = Vec::new();
+        let mut seen = HashSet::new();
+        for item in items {
+            let hosts = if item.contains('/') {
+                cidr(&item)?.into_iter().map(|ip| ip.to_string()).collect()
+            } else {
+                vec![item]
+            };
+            for host in hosts {
+                let addr = with_port(&host, self.port);
+                if seen.insert(addr.clone()) {
+                    out.push(addr);
+                }
+            }
+        }
+        if out.is_empty() {
+            return Err("no targets, use --addr, --file or --cidr".into());
+        }
+        Ok(out)
+    }
+}
+
+/// Non-empty lines without comments
+fn parse_lines(raw: &str) -> impl Iterator<Item = String> + '_ {
+    raw.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Append port if host has none
+fn with_port(host: &str, port: u16) -> String {
+    let host = host.trim();
+    if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+/// Hosts of IPv4 range, network and broadcast addresses are skipped for ranges bigger than `/31`
+fn cidr(raw: &str) -> Result<Vec<Ipv4Addr>, String> {
+    let invalid = || format!("invalid CIDR range: {raw}");
+    let (ip, bits) = raw.trim().split_once('/').ok_or_else(invalid)?;
+    let ip: Ipv4Addr = ip.parse().map_err(|_| invalid())?;
+    let bits: u32 = bits.parse().map_err(|_| invalid())?;
+    if bits > 32 {
+        return Err(invalid());
+    }
+    let size = 1u64 << (32 - bits);
+    if size > MAX_RANGE as u64 {
+        return Err(format!("{raw} is bigger than /16"));
+    }
+    let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+    let network = u32::from(ip) & mask;
+    let (first, last) = if size > 2 {
+        (1, size - 2)
+    } else {
+        (0, size - 1)
+    };
+    Ok((first..=last)
+        .map(|i| Ipv4Addr::from(network + i as u32))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        let hosts = cidr("10.10.1.77/30").unwrap();
+        assert_eq!(
+            hosts,
+            vec![Ipv4Addr::new(10, 10, 1, 77), Ipv4Addr::new(10, 10, 1, 78)]
+        );
+        assert_eq!(cidr("10.10.1.0/24").unwrap().len(), 254);
+        assert_eq!(
+            cidr("10.10.1.5/32").unwrap(),
+            vec![Ipv4Addr::new(10, 10, 1, 5)]
+        );
+        assert!(cidr("10.0.0.0/8").is_err());
+        assert!(cidr("10.0.0.0/33").is_err());
+        assert!(cidr("10.0.0/24").is_err());
+    }
+
+    #[test]
+    fn resolve() {
+        let args = TargetArgs {
+            addrs: vec!["10.0.0.1".into(), "10.0.0.2:4028".into()],
+            file: None,
+            cidr: vec!["10.0.0.0/30".into()],
+            port: DEFAULT_PORT,
+        };
+        assert_eq!(
+            args.resolve().unwrap(),
+            ["10.0.0.1:4433", "10.0.0.2:4028", "10.0.0.2:4433"]
+        );
+        let lines: Vec<String> =
+            parse_lines("# fleet\n10.0.0.1 # rack 1\n\n10.0.1.0/31\n").collect();
+        assert_eq!(lines, ["10.0.0.1", "10.0.1.0/31"]);
+    }
+}
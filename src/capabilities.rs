@@ -0,0 +1,149 @@
+//! Define capabilities module
+//!
+//! API version and firmware are detected once on connect ([Actor::new])
+//! and checked before every command ([Command::MIN_API]),
+//! so unsupported commands fail fast with [Error::Unsupported]
+//! instead of a cryptic `-2` from the miner.
+//!
+//! - Item: [Capabilities], [ApiVersion]
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(doc)]
+use crate::{actor::Actor, command::Command};
+use crate::{
+    command::get_device_info::System,
+    error::{Error, Result},
+};
+
+/// API version, like `3.0.2`
+///
+/// Parsing is lenient: `v3.0`, `3.0.2-beta` and `3` are accepted, missing parts are `0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ApiVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ApiVersion {
+    /// Creates a new [ApiVersion]
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().trim_start_matches(['v', 'V']);
+        let mut parts = s.split('.').map(|p| {
+            let digits: String = p.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u16>().ok()
+        });
+        let major = parts
+            .next()
+            .flatten()
+            .ok_or_else(|| Error::InvalidVersion(s.to_string()))?;
+        Ok(Self::new(
+            major,
+            parts.next().flatten().unwrap_or_default(),
+            parts.next().flatten().unwrap_or_default(),
+        ))
+    }
+}
+
+/// What connected miner can do
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Capabilities {
+    /// API version (`None` if miner didn't report it)
+    pub api: Option<ApiVersion>,
+    /// Firmware version, like `20250915.16.Rel2`
+    pub firmware: Option<String>,
+    /// Platform, like `H616`
+    pub platform: Option<String>,
+}
+
+impl Capabilities {
+    /// Check that api is at least `min`
+    ///
+    /// Unknown api is treated as supported
+    pub fn supports(&self, min: Option<ApiVersion>) -> bool {
+        match (self.api, min) {
+            (Some(api), Some(min)) => api >= min,
+            _ => true,
+        }
+    }
+
+    /// Fail with [Error::Unsupported] if api is lower than `min`
+    pub fn check(&self, cmd: &str, min: Option<ApiVersion>) -> Result<()> {
+        match self.api {
+            Some(api) if !self.supports(min) => Err(Error::Unsupported {
+                cmd: cmd.to_string(),
+                api,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<&System> for Capabilities {
+    fn from(value: &System) -> Self {
+        Self {
+            api: value.api_version(),
+            firmware: Some(value.fwversion.clone()),
+            platform: Some(value.platform.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "3.0.2".parse::<ApiVersion>().unwrap(),
+            ApiVersion::new(3, 0, 2)
+        );
+        assert_eq!(
+            "v3.1".parse::<ApiVersion>().unwrap(),
+            ApiVersion::new(3, 1, 0)
+        );
+        assert_eq!(
+            "3.0.3-beta".parse::<ApiVersion>().unwrap(),
+            ApiVersion::new(3, 0, 3)
+        );
+        assert!("".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn check() {
+        let caps = Capabilities {
+            api: Some(ApiVersion::new(3, 0, 2)),
+            ..Default::default()
+        };
+        assert!(caps.check("a", None).is_ok());
+        assert!(caps.check("a", Some(ApiVersion::new(3, 0, 2))).is_ok());
+        assert!(matches!(
+            caps.check("a", Some(ApiVersion::new(3, 0, 3))),
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(
+            Capabilities::default()
+                .check("a", Some(ApiVersion::new(9, 0, 0)))
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Define clock module
+//!
+//! Tokens ([AuthData](crate::auth_data::AuthData)) are stamped with unix time,
+//! and miner rejects them if its clock differs too much.
+//! [Actor](crate::actor::Actor) measures the skew from `when` of handshake answer
+//! and stamps tokens with the miner's notion of time.
+//!
+//! - Item: [Clock], [SystemClock], [FixedClock], [Skew]
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::{Error, Result};
+
+/// Source of unix time in seconds
+///
+/// Inject own implementation via [ActorConfig::clock](crate::actor::config::ActorConfig::clock)
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Result<u64>;
+}
+
+/// Local system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Result<u64> {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .map_err(|_| Error::ClockBeforeEpoch)
+    }
+}
+
+/// Clock, which always returns the same time, for deterministic tests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Result<u64> {
+        Ok(self.0)
+    }
+}
+
+/// Difference between miner and local clocks in seconds, positive if miner is ahead
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Skew(pub i64);
+
+impl Skew {
+    /// Skew of `remote` time relative to `local` time
+    pub fn measure(local: u64, remote: u64) -> Self {
+        Self(remote as i64 - local as i64)
+    }
+
+    /// Local time converted to miner time
+    pub fn apply(&self, local: u64) -> u64 {
+        local.saturating_add_signed(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew() {
+        let skew = Skew::measure(1_700_000_100, 1_700_000_000);
+        assert_eq!(skew, Skew(-100));
+        assert_eq!(
+            skew.apply(FixedClock(1_700_000_160).now().unwrap()),
+            1_700_000_060
+        );
+        assert_eq!(Skew::measure(10, 40).apply(5), 35);
+        assert_eq!(Skew(-10).apply(5), 0);
+        assert!(SystemClock.now().unwrap() > 1_700_000_000);
+    }
+}
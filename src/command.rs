@@ -22,6 +22,15 @@
 //!     }
 //! }
 //! ```
+//! ## Derive
+//! - same command with [`derive@Command`]
+//! ```rust,ignore
+//! use matroskin::command::Command;
+//!
+//! #[derive(Debug, Default, Command)]
+//! #[command(name = "set.system.led", secured, response = String)]
+//! pub struct SetSystemLed(pub String);
+//! ```
 //! ## Marks:
 //! - Ready to use: ✅
 //! - Unstable (do not use it in any ways, only at your own risk): ⚠️
@@ -30,66 +39,151 @@
 //! ## [list of commands](https://apidoc.whatsminer.com/):
 //! - [x] [get.device.custom_data](https://apidoc.whatsminer.com/#api-Device-device_get_custom_data)
 //! - [x] ✅ [get.device.info](https://apidoc.whatsminer.com/#api-Device-device_get_info)
-//! - [ ] [set.device.custom_data](https://apidoc.whatsminer.com/#api-Device-device_set_custom_data)
+//! - [x] [set.device.custom_data](https://apidoc.whatsminer.com/#api-Device-device_set_custom_data)
 //! - [x] ✅ [get.fan.setting](https://apidoc.whatsminer.com/#api-Fan-btminer_get_fansettings)
-//! - [ ] [set.fan.poweroff_cool](https://apidoc.whatsminer.com/#api-Fan-btminer_poweroff_cool)
-//! - [ ] [set.fan.temp_offset](https://apidoc.whatsminer.com/#api-Fan-fan_set_temp_offset)
-//! - [ ] [set.fan.zero_speed](https://apidoc.whatsminer.com/#api-Fan-btminer_zero_speed)
+//! - [x] [set.fan.poweroff_cool](https://apidoc.whatsminer.com/#api-Fan-btminer_poweroff_cool)
+//! - [x] [set.fan.temp_offset](https://apidoc.whatsminer.com/#api-Fan-fan_set_temp_offset)
+//! - [x] [set.fan.zero_speed](https://apidoc.whatsminer.com/#api-Fan-btminer_zero_speed)
 //! - [ ] [get.log.download](https://apidoc.whatsminer.com/#api-Log-syslog_download)
 //! - [ ] [set.log.upload](https://apidoc.whatsminer.com/#api-Log-syslog_upload)
 //! - [ ] [get.miner.history](https://apidoc.whatsminer.com/#api-Miner-btminer_get_history)
 //! - [x] [get.miner.setting](https://apidoc.whatsminer.com/#api-Miner-btminer_get_settings)
-//! - [ ] [get.miner.status](https://apidoc.whatsminer.com/#api-Miner-btminer_get_status)
+//! - [x] ✅ [get.miner.status](https://apidoc.whatsminer.com/#api-Miner-btminer_get_status)
 //! - [ ] [set.miner.cointype](https://apidoc.whatsminer.com/#api-Miner-btminer_set_cointype)
 //! - [ ] [set.miner.fast_hash](https://apidoc.whatsminer.com/#api-Miner-set_fast_mining)
 //! - [x] ✅ [set.miner.fastboot](https://apidoc.whatsminer.com/#api-Miner-btminer_set_fastboot)
 //! - [ ] [set.miner.heat_mode](https://apidoc.whatsminer.com/#api-Miner-btminer_set_heat_mode)
 //! - [x] ⚠️ [set.miner.pools](https://apidoc.whatsminer.com/#api-Miner-btminer_set_pools)
 //! - [ ] [set.miner.power](https://apidoc.whatsminer.com/#api-Miner-btminer_set_power)
-//! - [ ] [set.miner.power_limit](https://apidoc.whatsminer.com/#api-Miner-btminer_power_limit)
-//! - [ ] [set.miner.power_mode](https://apidoc.whatsminer.com/#api-Miner-btminer_power_mode)
-//! - [ ] [set.miner.power_percent](https://apidoc.whatsminer.com/#api-Miner-btminer_set_power_percent)
+//! - [x] [set.miner.power_limit](https://apidoc.whatsminer.com/#api-Miner-btminer_power_limit)
+//! - [x] [set.miner.power_mode](https://apidoc.whatsminer.com/#api-Miner-btminer_power_mode)
+//! - [x] [set.miner.power_percent](https://apidoc.whatsminer.com/#api-Miner-btminer_set_power_percent)
 //! - [ ] [set.miner.report](https://apidoc.whatsminer.com/#api-Miner-btminer_report)
 //! - [ ] [set.miner.restore_setting](https://apidoc.whatsminer.com/#api-Miner-btminer_restore)
 //! - [ ] [set.miner.service](https://apidoc.whatsminer.com/#api-Miner-btminer_service_set)
 //! - [ ] [set.miner.target_freq](https://apidoc.whatsminer.com/#api-Miner-btminer_set_targetfreq)
 //! - [ ] [set.miner.upfreq_speed](https://apidoc.whatsminer.com/#api-Miner-btminer_upfreq_speed)
 //! - [x] ✅ [get.system.setting](https://apidoc.whatsminer.com/#api-System-btminer_get_systemsettings)
-//! - [ ] [set.system.factory_reset](https://apidoc.whatsminer.com/#api-System-system_factory_reset)
-//! - [ ] [set.system.hostname](https://apidoc.whatsminer.com/#api-System-system_set_hostname)
+//! - [x] [set.system.factory_reset](https://apidoc.whatsminer.com/#api-System-system_factory_reset)
+//! - [x] [set.system.hostname](https://apidoc.whatsminer.com/#api-System-system_set_hostname)
 //! - [ ] [set.system.led](https://apidoc.whatsminer.com/#api-System-system_set_led)
 //! - [ ] [set.system.net_config](https://apidoc.whatsminer.com/#api-System-system_net_config)
-//! - [ ] [set.system.ntp_server](https://apidoc.whatsminer.com/#api-System-system_set_ntp)
-//! - [ ] [set.system.reboot](https://apidoc.whatsminer.com/#api-System-system_reboot)
+//! - [x] [set.system.ntp_server](https://apidoc.whatsminer.com/#api-System-system_set_ntp)
+//! - [x] [set.system.reboot](https://apidoc.whatsminer.com/#api-System-system_reboot)
 //! - [ ] [set.system.time_randomized](https://apidoc.whatsminer.com/#api-System-system_set_time_randomized)
-//! - [ ] [set.system.timezone](https://apidoc.whatsminer.com/#api-System-system_set_timezone)
+//! - [x] [set.system.timezone](https://apidoc.whatsminer.com/#api-System-system_set_timezone)
 //! - [ ] [set.system.update_firmware](https://apidoc.whatsminer.com/#api-System-system_update_firmware)
 //! - [ ] [set.system.webpools](https://apidoc.whatsminer.com/#api-System-system_set_webpools)
-//! - [ ] [set.user.change_passwd](https://apidoc.whatsminer.com/#api-User-user_set_passwd)
+//! - [x] ✅ [set.user.change_passwd](https://apidoc.whatsminer.com/#api-User-user_set_passwd)
 //! - [ ] [set.user.permission](https://apidoc.whatsminer.com/#api-User-user_set_permission)
 
 pub mod get_device_custom_data;
 pub mod get_device_info;
 pub mod get_fan_setting;
 pub mod get_miner_setting;
+pub mod get_miner_status;
 pub mod get_system_setting;
+pub mod raw;
+pub mod set_device_custom_data;
+pub mod set_fan_poweroff_cool;
+pub mod set_fan_temp_offset;
+pub mod set_fan_zero_speed;
 pub mod set_miner_fastboot;
 pub mod set_miner_pools;
+pub mod set_miner_power_limit;
+pub mod set_miner_power_mode;
+pub mod set_miner_power_percent;
+pub mod set_system_factory_reset;
+pub mod set_system_hostname;
+pub mod set_system_ntp_server;
+pub mod set_system_reboot;
+pub mod set_system_timezone;
+pub mod set_user_change_passwd;
 
 #[cfg(doc)]
 use crate::command::set_miner_fastboot::SetMinerFastboot;
-#[cfg(doc)]
-use crate::response::Response;
 use crate::{
-    actor::{Actor, message::ActorMessage},
+    actor::Actor,
     auth_data::AuthData,
+    capabilities::ApiVersion,
+    drift::{Drift, ExtraFields},
     error::{Error, Result},
     request::Request,
+    response::check_code,
 };
+#[cfg(doc)]
+use crate::{actor::retry::RetryPolicy, capabilities::Capabilities, response::Response};
 use core::str;
 use serde::{Deserialize, Serialize};
-use tokio::sync::oneshot;
-use tracing::{debug, trace};
+use serde_json::Value;
+use tracing::debug;
+
+/// Derive [Command] implementation
+///
+/// ```rust,ignore
+/// use matroskin::command::Command;
+///
+/// #[derive(Debug, Default, Command)]
+/// #[command(name = "set.miner.fastboot", secured, response = String)]
+/// pub struct SetMinerFastboot(pub bool);
+/// ```
+pub use matroskin_derive::Command;
+
+/// Names of all commands of api, see the list above
+pub const API_COMMANDS: &[&str] = &[
+    "get.device.custom_data",
+    "get.device.info",
+    "set.device.custom_data",
+    "get.fan.setting",
+    "set.fan.poweroff_cool",
+    "set.fan.temp_offset",
+    "set.fan.zero_speed",
+    "get.log.download",
+    "set.log.upload",
+    "get.miner.history",
+    "get.miner.setting",
+    "get.miner.status",
+    "set.miner.cointype",
+    "set.miner.fast_hash",
+    "set.miner.fastboot",
+    "set.miner.heat_mode",
+    "set.miner.pools",
+    "set.miner.power",
+    "set.miner.power_limit",
+    "set.miner.power_mode",
+    "set.miner.power_percent",
+    "set.miner.report",
+    "set.miner.restore_setting",
+    "set.miner.service",
+    "set.miner.target_freq",
+    "set.miner.upfreq_speed",
+    "get.system.setting",
+    "set.system.factory_reset",
+    "set.system.hostname",
+    "set.system.led",
+    "set.system.net_config",
+    "set.system.ntp_server",
+    "set.system.reboot",
+    "set.system.time_randomized",
+    "set.system.timezone",
+    "set.system.update_firmware",
+    "set.system.webpools",
+    "set.user.change_passwd",
+    "set.user.permission",
+];
+
+/// Serialize params to string for [Request]
+///
+/// - `null` -> no params
+/// - JSON string -> sent as is (`"enable"`)
+/// - anything else -> JSON text
+pub fn params_to_string(params: &impl Serialize) -> Result<Option<String>> {
+    Ok(match serde_json::to_value(params)? {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        v => Some(serde_json::to_string(&v)?),
+    })
+}
 
 /// Base trait for buildings commands
 ///
@@ -130,31 +224,23 @@ pub trait Command {
     /// - data: [AuthData]
     /// - where [AuthData::encrypt]
     const ENCRYPTED: bool = false;
+    /// Is it safe to repeat command?
+    ///
+    /// Only idempotent commands are retried by [RetryPolicy].
+    /// Destructive commands (reboot, factory reset) are never idempotent
+    const IDEMPOTENT: bool = false;
+    /// Minimal api version which supports command
+    ///
+    /// - `None`: any version
+    /// - where: [Command::execute], [Capabilities::check]
+    const MIN_API: Option<ApiVersion> = None;
 
     /// Return local params
     fn params(&self) -> Result<Option<String>>;
 
     /// Convert command to request
     fn to_request<'a, 'b: 'a>(&'a self, auth_data: Option<AuthData>) -> Result<Request> {
-        let parameter = self.params()?;
-
-        let parameter = if let Some(data) = &auth_data {
-            if let Some(x) = parameter {
-                if Self::ENCRYPTED {
-                    Some(data.encrypt(x.as_bytes())?)
-                } else {
-                    Some(x)
-                }
-            } else {
-                parameter
-            }
-        } else if Self::ENCRYPTED {
-            return Err(Error::CommandSholdHaveAuthData(Self::CMD_NAME));
-        } else {
-            parameter
-        };
-
-        Ok(Request::new(Self::CMD_NAME, auth_data, parameter))
+        Request::build(Self::CMD_NAME, auth_data, self.params()?, Self::ENCRYPTED)
     }
 
     fn is_secured(&self) -> bool {
@@ -171,6 +257,18 @@ pub trait Command {
     fn response_from_str(json: &str) -> Result<Self::Response> {
         Ok(serde_json::from_str(json)?)
     }
+    /// Deserialize response and report fields, which don't match [Command::Response]
+    ///
+    /// - where: [Drift::check]
+    fn response_from_str_strict(json: &str) -> Result<(Self::Response, Drift)>
+    where
+        Self::Response: Serialize + ExtraFields,
+    {
+        let raw: Value = serde_json::from_str(json)?;
+        let response = Self::response_from_str(json)?;
+        let drift = Drift::check(&raw, &response)?;
+        Ok((response, drift))
+    }
     /// Convert Command to String
     fn to_request_to_string(&self, auth_data: Option<AuthData>) -> Result<String> {
         let req = self.to_request(auth_data)?;
@@ -192,23 +290,61 @@ pub trait Command {
         Self: Sync + Send + Sized,
     {
         async {
-            let auth = if Self::SECURED {
-                Some(actor.auth_data::<Self>()?)
-            } else {
-                None
-            };
-            let message = self.to_request_to_string(auth)?;
-            debug!(cmd=%Self::CMD_NAME, "message for send {}", &message);
-            let (tx, rx) = oneshot::channel();
-            let message = ActorMessage {
-                message: message.as_bytes().to_vec(),
-                rev: tx,
+            let out = actor
+                .config
+                .retry
+                .run(Self::CMD_NAME, Self::IDEMPOTENT, || self.execute_raw(actor))
+                .await?;
+            Self::response_from_str(&out).map_err(|e| {
+                e.decoding(Self::CMD_NAME)
+                    .with_context(&actor.addr, Self::CMD_NAME)
+            })
+        }
+    }
+
+    /// Run command into actor once, returns raw JSON answer
+    ///
+    /// Non-zero code of answer fails with [Error::Api].
+    /// Secured commands are recorded to [ActorConfig::audit](crate::actor::config::ActorConfig::audit)
+    fn execute_raw(&self, actor: &Actor) -> impl std::future::Future<Output = Result<String>> + Send
+    where
+        Self: Sync + Send + Sized,
+    {
+        async {
+            let work = async {
+                let message = self.to_message(actor)?;
+                let out = actor.dispatch(message.into_bytes()).await?;
+                check_code(&out, Self::CMD_NAME)?;
+                Ok(out)
             };
-            actor.tx.send(message).await?;
-            let out = rx.await??;
-            trace!(data=%out,"got data from rx");
-            let out = Self::response_from_str(&out)?;
-            Ok(out)
+            actor
+                .audited(
+                    Self::CMD_NAME,
+                    Self::SECURED,
+                    Self::ENCRYPTED,
+                    || self.params(),
+                    work,
+                    |_| Some(0),
+                )
+                .await
+                .map_err(|e: Error| e.with_context(&actor.addr, Self::CMD_NAME))
         }
     }
+
+    /// Check capabilities of actor and build request message with its AuthData
+    fn to_message(&self, actor: &Actor) -> Result<String>
+    where
+        Self: Sized,
+    {
+        actor.capabilities.check(Self::CMD_NAME, Self::MIN_API)?;
+        actor.check_permission(Self::CMD_NAME, Self::SECURED)?;
+        let auth = if Self::SECURED {
+            Some(actor.auth_data::<Self>()?)
+        } else {
+            None
+        };
+        let message = self.to_request_to_string(auth)?;
+        debug!(cmd=%Self::CMD_NAME, "message for send {}", &message);
+        Ok(message)
+    }
 }
@@ -6,9 +6,10 @@
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Device-device_get_custom_data>
 use core::str;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{command::Command, error::Result, response::Response};
+use crate::{command::Command, drift::extra_fields};
 
 /// This command represents the `get.device.custom_data` operation.
 ///
@@ -38,11 +39,16 @@ use crate::{command::Command, error::Result, response::Response};
 ///     Ok(())
 /// }
 ///
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "get.device.custom_data",
+    response = GetDeviceCustomDataResponse,
+    snapshot = r#"{"cmd":"get.device.custom_data"}"#
+)]
 pub struct GetDeviceCustomData;
 
 /// [GetDeviceCustomData] Response
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetDeviceCustomDataResponse {
     pub custom_sn: String,
@@ -56,13 +62,9 @@ pub struct GetDeviceCustomDataResponse {
     pub msg7: String,
     pub msg8: String,
     pub msg9: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
-impl Command for GetDeviceCustomData {
-    type Params = ();
-    type Response = Response<GetDeviceCustomDataResponse>;
-    const CMD_NAME: &'static str = "get.device.custom_data";
-    fn params(&self) -> Result<Option<String>> {
-        Ok(None)
-    }
-}
+extra_fields!(GetDeviceCustomDataResponse);
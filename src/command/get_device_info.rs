@@ -8,8 +8,18 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{command::Command, error::Result, response::Response};
+use crate::{
+    capabilities::ApiVersion,
+    command::Command,
+    drift::extra_fields,
+    error::Result,
+    error_code::ErrorCode,
+    lenient::{non_empty, parse_bool, parse_num},
+    model::MinerModel,
+    response::Response,
+};
 
 /// This command represents the `get.device.info` operation.
 ///
@@ -93,6 +103,7 @@ impl Command for GetDeviceInfo {
     type Params = GetDeviceInfoParam;
     type Response = Response<DeviceInfo>;
     const CMD_NAME: &'static str = "get.device.info";
+    const IDEMPOTENT: bool = true;
 
     fn params(&self) -> Result<Option<String>> {
         Ok({
@@ -112,6 +123,9 @@ impl Command for GetDeviceInfo {
                 None
             } else {
                 let mut out = Vec::with_capacity(5);
+                if self.0.miner {
+                    out.push("miner");
+                }
                 if self.0.error_code {
                     out.push("error-code");
                 }
@@ -136,8 +150,8 @@ impl Command for GetDeviceInfo {
 /// Response structure containing various information about the ASIC device.
 ///
 /// - ApiDoc: <https://apidoc.whatsminer.com/#api-Device-device_get_info>
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct DeviceInfo {
     /// Network information
     pub network: Option<Network>,
@@ -149,18 +163,33 @@ pub struct DeviceInfo {
     pub power: Option<Power>,
     /// Salt value
     pub salt: Option<String>,
-    // TODO: make type for that
     /// Error codes
     ///
-    /// Expect reason and number of error
+    /// Expect reason and number of error, typed view: [DeviceInfo::errors]
     ///
     /// - List of errors: <https://www.whatsminer.com/src/views/firmware-download.html#Document>
+    #[serde(rename = "error-code", alias = "error-codes")]
     pub error_codes: Option<Vec<HashMap<String, String>>>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(DeviceInfo, "network" => network, "miner" => miner, "system" => system, "power" => power);
+
+impl DeviceInfo {
+    /// Parsed error codes, see [ErrorCode::info] for description
+    pub fn errors(&self) -> Vec<ErrorCode> {
+        self.error_codes
+            .as_deref()
+            .map(ErrorCode::parse_list)
+            .unwrap_or_default()
+    }
 }
 
 /// Network information
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Network {
     /// IP address
     pub ip: String,
@@ -176,11 +205,16 @@ pub struct Network {
     pub gateway: String,
     /// Hostname
     pub hostname: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
+extra_fields!(Network);
+
 /// Miner information
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Miner {
     /// Working status
     pub working: String,
@@ -229,11 +263,134 @@ pub struct Miner {
     pub upfreq_speed: Option<String>,
     /// Permission
     pub permission: Option<String>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(Miner);
+
+impl Miner {
+    /// Parsed [Miner::type] and [Miner::hash_board]
+    pub fn model(&self) -> Option<MinerModel> {
+        MinerModel::parse(&self.r#type, Some(&self.hash_board)).ok()
+    }
+
+    /// Is miner working?
+    pub fn is_working(&self) -> Option<bool> {
+        parse_bool(&self.working)
+    }
+
+    /// Is fast boot enabled?
+    pub fn fast_boot_enabled(&self) -> Option<bool> {
+        parse_bool(&self.fast_boot)
+    }
+
+    /// Number of hash boards
+    pub fn board_count(&self) -> Option<u8> {
+        parse_num(&self.board_num)
+    }
+
+    /// Detected hash rate per board, as reported by miner
+    ///
+    /// `"40123:41234:40***"` -> `[Some(40123), Some(41234), None]`
+    pub fn detected_hash_rates(&self) -> Vec<Option<u64>> {
+        match non_empty(&self.detect_hash_rate) {
+            Some(s) => s.split(':').map(parse_num).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Sum of detected hash rate, `None` if any board is unknown
+    pub fn detected_hash_rate_total(&self) -> Option<u64> {
+        let rates = self.detected_hash_rates();
+        if rates.is_empty() {
+            return None;
+        }
+        rates.into_iter().sum()
+    }
+
+    /// Pool strategy
+    pub fn pool_strategy(&self) -> PoolStrategy {
+        self.pool_strategy.as_str().into()
+    }
+
+    /// Heat mode, `None` if not set
+    pub fn heat_mode(&self) -> Option<&str> {
+        non_empty(&self.heatmode)
+    }
+
+    /// Hash percent, `None` if not set
+    pub fn hash_percent(&self) -> Option<u8> {
+        parse_num(&self.hash_percent)
+    }
+
+    /// Power limit (W), `None` if not set
+    pub fn power_limit(&self) -> Option<u32> {
+        parse_num(&self.power_limit_set)
+    }
+
+    /// Upfreq speed, `None` if not set
+    pub fn upfreq_speed(&self) -> Option<u8> {
+        self.upfreq_speed.as_deref().and_then(parse_num)
+    }
+
+    /// Is liquid cooling reported by EEPROM?
+    pub fn liquid_cooling(&self) -> Option<bool> {
+        self.eeprom_liquid_cooling.as_deref().and_then(parse_bool)
+    }
+
+    /// Is web pool enabled?
+    pub fn web_pool_enabled(&self) -> bool {
+        self.web_pool != 0
+    }
+
+    /// Non-empty PCB serial numbers, one per board
+    pub fn pcb_serials(&self) -> Vec<&str> {
+        [&self.pcbsn0, &self.pcbsn1, &self.pcbsn2]
+            .into_iter()
+            .filter_map(|s| non_empty(s))
+            .collect()
+    }
+
+    /// Non-empty chip data, one per board
+    pub fn chip_data(&self) -> Vec<&str> {
+        [&self.chipdata0, &self.chipdata1, &self.chipdata2]
+            .into_iter()
+            .filter_map(|s| non_empty(s))
+            .collect()
+    }
+}
+
+/// Pool strategy of [Miner]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolStrategy {
+    Failover,
+    RoundRobin,
+    LoadBalance,
+    /// Unknown strategy, as reported by miner
+    Other(String),
+}
+
+impl From<&str> for PoolStrategy {
+    fn from(value: &str) -> Self {
+        match value
+            .trim()
+            .to_ascii_uppercase()
+            .replace(['-', ' '], "_")
+            .as_str()
+        {
+            "FAILOVER" => Self::Failover,
+            "ROUND_ROBIN" | "ROUNDROBIN" => Self::RoundRobin,
+            "LOAD_BALANCE" | "LOADBALANCE" => Self::LoadBalance,
+            _ => Self::Other(value.to_string()),
+        }
+    }
 }
 
 /// System information
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct System {
     /// API version
     pub api: String,
@@ -252,11 +409,52 @@ pub struct System {
     pub apiswitch: String,
     /// LED status
     pub ledstatus: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(System);
+
+impl System {
+    /// API version, `None` if it can't be parsed
+    pub fn api_version(&self) -> Option<ApiVersion> {
+        self.api.parse().ok()
+    }
+
+    /// Is API switch enabled?
+    pub fn api_switch_enabled(&self) -> Option<bool> {
+        parse_bool(&self.apiswitch)
+    }
+
+    /// LED status
+    pub fn led_status(&self) -> LedStatus {
+        self.ledstatus.as_str().into()
+    }
+}
+
+/// LED status of [System]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedStatus {
+    Auto,
+    Manual,
+    /// Unknown status, as reported by miner
+    Other(String),
+}
+
+impl From<&str> for LedStatus {
+    fn from(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Self::Auto,
+            "manual" => Self::Manual,
+            _ => Self::Other(value.to_string()),
+        }
+    }
 }
 
 /// Power supply information
-#[derive(Debug, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
 pub struct Power {
     #[serde(rename = "type")]
     /// Power supply type
@@ -285,6 +483,23 @@ pub struct Power {
     pub sn: String,
     /// Vendor ID
     pub vendor: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(Power);
+
+impl Power {
+    /// Power supply mode, `None` if it can't be parsed
+    pub fn mode(&self) -> Option<u8> {
+        parse_num(&self.mode)
+    }
+
+    /// Output voltage (V)
+    pub fn vout_volts(&self) -> f32 {
+        self.vout as f32 / 100.0
+    }
 }
 
 #[cfg(test)]
@@ -293,6 +508,65 @@ mod get_device_info {
 
     use super::*;
 
+    #[test]
+    fn typed() {
+        let json = include_str!("../../.example-response/get.device.info.json");
+        let info = GetDeviceInfo::response_from_str(json).unwrap().msg;
+        assert_eq!(info.error_codes, Some(vec![]));
+        assert!(info.errors().is_empty());
+
+        let miner = info.miner.unwrap();
+        assert_eq!(miner.is_working(), Some(true));
+        assert_eq!(miner.fast_boot_enabled(), Some(false));
+        assert_eq!(miner.board_count(), Some(3));
+        assert_eq!(miner.detected_hash_rates(), [None, None, None]);
+        assert_eq!(miner.detected_hash_rate_total(), None);
+        assert_eq!(miner.pool_strategy(), PoolStrategy::Failover);
+        assert_eq!(miner.heat_mode(), None);
+        assert_eq!(miner.power_limit(), None);
+        assert_eq!(miner.upfreq_speed(), None);
+        assert!(miner.web_pool_enabled());
+        assert_eq!(miner.pcb_serials().len(), 3);
+
+        let system = info.system.unwrap();
+        assert_eq!(system.api_version(), Some(ApiVersion::new(3, 0, 2)));
+        assert_eq!(system.api_switch_enabled(), Some(true));
+        assert_eq!(system.led_status(), LedStatus::Auto);
+
+        let power = info.power.unwrap();
+        assert_eq!(power.mode(), Some(1));
+        assert_eq!(power.vout_volts(), 12.22);
+    }
+
+    #[test]
+    fn serialize_roundtrip() {
+        let json = include_str!("../../.example-response/get.device.info.json");
+        let response = GetDeviceInfo::response_from_str(json).unwrap();
+        let out = serde_json::to_value(&response).unwrap();
+        assert!(out["msg"]["system"].get("control-board-version").is_some());
+        assert!(out["msg"]["miner"].get("hash-board").is_some());
+        assert!(out["msg"]["miner"].get("type").is_some());
+
+        let back: Response<DeviceInfo> = serde_json::from_value(out).unwrap();
+        assert_eq!(back, response);
+    }
+
+    #[test]
+    fn hash_rates() {
+        let json = include_str!("../../.example-response/get.device.info.json")
+            .replace("40***:41***:40***", "40123:41234:40345");
+        let miner = GetDeviceInfo::response_from_str(&json)
+            .unwrap()
+            .msg
+            .miner
+            .unwrap();
+        assert_eq!(
+            miner.detected_hash_rates(),
+            [Some(40123), Some(41234), Some(40345)]
+        );
+        assert_eq!(miner.detected_hash_rate_total(), Some(121702));
+    }
+
     #[tokio::test]
     async fn request() -> Result<()> {
         let addr = "10.10.10.10:4433";
@@ -6,9 +6,10 @@
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-get_fan_settings>
 use core::str;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{command::Command, error::Result, response::Response};
+use crate::{command::Command, drift::extra_fields};
 
 /// This command represents the `get.fan.setting` operation.
 ///
@@ -38,26 +39,27 @@ use crate::{command::Command, error::Result, response::Response};
 ///     Ok(())
 /// }
 ///
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "get.fan.setting",
+    response = GetFanSettingsResponse,
+    snapshot = r#"{"cmd":"get.fan.setting"}"#
+)]
 pub struct GetFanSettings;
 
 /// [GetFanSettings] Response
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetFanSettingsResponse {
     pub fan_poweroff_cool: u8,
     pub fan_zero_speed: i64,
     pub fan_temp_offset: i64,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
-impl Command for GetFanSettings {
-    type Params = ();
-    type Response = Response<GetFanSettingsResponse>;
-    const CMD_NAME: &'static str = "get.fan.setting";
-    fn params(&self) -> Result<Option<String>> {
-        Ok(None)
-    }
-}
+extra_fields!(GetFanSettingsResponse);
 
 #[cfg(test)]
 mod get_fan_settings {
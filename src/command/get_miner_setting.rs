@@ -6,9 +6,10 @@
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_get_settings>
 use core::str;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{command::Command, error::Result, response::Response};
+use crate::{command::Command, drift::extra_fields, lenient::parse_bool};
 
 /// This command represents the `get.miner.setting` operation.
 ///
@@ -38,11 +39,16 @@ use crate::{command::Command, error::Result, response::Response};
 ///     Ok(())
 /// }
 ///
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "get.miner.setting",
+    response = GetMinerSettingsResponse,
+    snapshot = r#"{"cmd":"get.miner.setting"}"#
+)]
 pub struct GetMinerSettings;
 
 /// [GetMinerSettings] Response
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetMinerSettingsResponse {
     pub power_limit: i64,
@@ -62,14 +68,24 @@ pub struct GetMinerSettingsResponse {
     pub power: Option<i64>,
     // since 3.0.3v
     pub power_percent: Option<i64>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
-impl Command for GetMinerSettings {
-    type Params = ();
-    type Response = Response<GetMinerSettingsResponse>;
-    const CMD_NAME: &'static str = "get.miner.setting";
-    fn params(&self) -> Result<Option<String>> {
-        Ok(None)
+extra_fields!(GetMinerSettingsResponse);
+
+impl GetMinerSettingsResponse {
+    /// Is fast boot enabled?
+    pub fn fast_boot_enabled(&self) -> Option<bool> {
+        parse_bool(&self.fast_boot)
+    }
+
+    /// Is fast mining enabled?
+    ///
+    /// since 3.0.3v
+    pub fn fast_mining_enabled(&self) -> Option<bool> {
+        self.fast_mining.as_deref().and_then(parse_bool)
     }
 }
 
@@ -0,0 +1,212 @@
+//! Implement `get.miner.status` command
+//!
+//! This command is used to get hash rate, power and temperatures of the miner.
+//!
+//! - Command: [GetMinerStatus]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_get_status>
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    command::Command,
+    drift::extra_fields,
+    error::Result,
+    lenient::{number, parse_num},
+};
+
+/// This command represents the `get.miner.status` operation.
+///
+/// It is used to get hash rate, power and temperatures of the miner.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_get_status>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::get_miner_status::GetMinerStatus;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&GetMinerStatus::default()).await?;
+///     println!("Hash rate: {:?}", response.msg.summary.and_then(|s| s.hash_realtime));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "get.miner.status",
+    response = MinerStatus,
+    params_with = status_params,
+    snapshot = r#"{"cmd":"get.miner.status","param":"summary"}"#
+)]
+pub struct GetMinerStatus(pub GetMinerStatusParam);
+
+/// Parameters for the `get.miner.status` command.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_get_status>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GetMinerStatusParam {
+    /// Include hash rate, power and temperature summary
+    pub summary: bool,
+    /// Include pool states
+    pub pools: bool,
+    /// Include hash board states
+    pub edevs: bool,
+}
+
+impl Default for GetMinerStatusParam {
+    fn default() -> Self {
+        Self {
+            summary: true,
+            pools: false,
+            edevs: false,
+        }
+    }
+}
+
+/// `summary,pools,edevs`, only requested parts
+fn status_params(cmd: &GetMinerStatus) -> Result<Option<String>> {
+    let mut out = Vec::with_capacity(3);
+    if cmd.0.summary {
+        out.push("summary");
+    }
+    if cmd.0.pools {
+        out.push("pools");
+    }
+    if cmd.0.edevs {
+        out.push("edevs");
+    }
+    Ok(Some(out.join(",")))
+}
+
+/// [GetMinerStatus] Response
+///
+/// Parts, which weren't requested, are `None`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MinerStatus {
+    pub summary: Option<Summary>,
+    /// Pool states, as reported by miner
+    pub pools: Option<Vec<Map<String, Value>>>,
+    /// Hash board states, as reported by miner
+    pub edevs: Option<Vec<Map<String, Value>>>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(MinerStatus, "summary" => summary);
+
+/// Hash rate, power and temperature summary
+///
+/// Numbers are parsed leniently, values in miner's units:
+/// hash rate in TH/s, power in W, temperature in °C
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct Summary {
+    /// Seconds since mining started
+    #[serde(deserialize_with = "number")]
+    pub elapsed: Option<f64>,
+    /// Average hash rate since start
+    #[serde(deserialize_with = "number")]
+    pub hash_average: Option<f64>,
+    /// Hash rate for last minute
+    #[serde(rename = "hash-1min", deserialize_with = "number")]
+    pub hash_1min: Option<f64>,
+    /// Hash rate for last 15 minutes
+    #[serde(rename = "hash-15min", deserialize_with = "number")]
+    pub hash_15min: Option<f64>,
+    /// Current hash rate
+    #[serde(deserialize_with = "number")]
+    pub hash_realtime: Option<f64>,
+    /// Nominal hash rate
+    #[serde(deserialize_with = "number")]
+    pub factory_hash: Option<f64>,
+    /// Average chip frequency, MHz
+    #[serde(deserialize_with = "number")]
+    pub freq_avg: Option<f64>,
+    /// Target chip frequency, MHz
+    #[serde(deserialize_with = "number")]
+    pub target_freq: Option<f64>,
+    /// Current power
+    #[serde(deserialize_with = "number")]
+    pub power_realtime: Option<f64>,
+    /// Average power for last 5 minutes
+    #[serde(rename = "power-5min", deserialize_with = "number")]
+    pub power_5min: Option<f64>,
+    /// Power limit
+    #[serde(deserialize_with = "number")]
+    pub power_limit: Option<f64>,
+    /// Ambient temperature
+    #[serde(deserialize_with = "number")]
+    pub environment_temperature: Option<f64>,
+    /// Temperature of every hash board
+    pub board_temperature: Vec<Value>,
+    #[serde(deserialize_with = "number")]
+    pub chip_temp_min: Option<f64>,
+    #[serde(deserialize_with = "number")]
+    pub chip_temp_avg: Option<f64>,
+    #[serde(deserialize_with = "number")]
+    pub chip_temp_max: Option<f64>,
+    /// Inlet fan speed, RPM
+    #[serde(deserialize_with = "number")]
+    pub fan_speed_in: Option<f64>,
+    /// Outlet fan speed, RPM
+    #[serde(deserialize_with = "number")]
+    pub fan_speed_out: Option<f64>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(Summary);
+
+impl Summary {
+    /// Parsed [Summary::board_temperature], unparsable entries are `None`
+    pub fn board_temperatures(&self) -> Vec<Option<f64>> {
+        self.board_temperature
+            .iter()
+            .map(|v| match v {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => parse_num(s),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params() {
+        let cmd = GetMinerStatus(GetMinerStatusParam {
+            summary: true,
+            pools: false,
+            edevs: true,
+        });
+        assert_eq!(cmd.params().unwrap().as_deref(), Some("summary,edevs"));
+    }
+
+    #[test]
+    fn response() {
+        let raw = r#"{"code":0,"when":1700000000,"msg":{"summary":{
+            "elapsed":3600,"hash-realtime":"112.5","power-realtime":3250,
+            "environment-temperature":"25.0","board-temperature":[61.5,"62",""],
+            "fan-speed-in":4020,"fan-speed-out":"","firmware-flags":1}},"desc":"get.miner.status"}"#;
+        let status = GetMinerStatus::response_from_str(raw).unwrap().msg;
+        let summary = status.summary.unwrap();
+        assert_eq!(summary.hash_realtime, Some(112.5));
+        assert_eq!(summary.power_realtime, Some(3250.0));
+        assert_eq!(summary.fan_speed_out, None);
+        assert_eq!(summary.board_temperatures(), [Some(61.5), Some(62.0), None]);
+        assert!(summary.extra.contains_key("firmware-flags"));
+        assert_eq!(status.pools, None);
+    }
+}
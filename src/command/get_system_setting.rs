@@ -6,9 +6,10 @@
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-get_system_settings>
 use core::str;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-use crate::{command::Command, error::Result, response::Response};
+use crate::{command::Command, drift::extra_fields};
 
 /// This command represents the `get.system.setting` operation.
 ///
@@ -38,11 +39,16 @@ use crate::{command::Command, error::Result, response::Response};
 ///     Ok(())
 /// }
 ///
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "get.system.setting",
+    response = GetSystemSettingResponse,
+    snapshot = r#"{"cmd":"get.system.setting"}"#
+)]
 pub struct GetSystemSetting;
 
 /// [GetSystemSetting] Response
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetSystemSettingResponse {
     /// Is web panel on?
@@ -58,29 +64,39 @@ pub struct GetSystemSettingResponse {
     pub time_randomized: GetSystemSettingsResponseTimeRandomized,
     /// Time servers
     pub ntp_server: Vec<String>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
-#[derive(Debug, Default, Deserialize)]
+
+extra_fields!(
+    GetSystemSettingResponse,
+    "log-upload" => log_upload,
+    "time-randomized" => time_randomized,
+);
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetSystemSettingsResponseLogUpload {
     pub ip: String,
     pub port: String,
     pub proto: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
-#[derive(Debug, Default, Deserialize)]
+
+extra_fields!(GetSystemSettingsResponseLogUpload);
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct GetSystemSettingsResponseTimeRandomized {
     pub start: i64,
     pub stop: i64,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
 }
 
-impl Command for GetSystemSetting {
-    type Params = ();
-    type Response = Response<GetSystemSettingResponse>;
-    const CMD_NAME: &'static str = "get.system.setting";
-    fn params(&self) -> Result<Option<String>> {
-        Ok(None)
-    }
-}
+extra_fields!(GetSystemSettingsResponseTimeRandomized);
 
 #[cfg(test)]
 mod get_system_setting {
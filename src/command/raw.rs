@@ -0,0 +1,200 @@
+//! Implement raw passthrough command
+//!
+//! It is used to call any command, even if it has no typed implementation yet.
+//!
+//! - Command: [RawCommand]
+//! - ApiDoc: <https://apidoc.whatsminer.com>
+use serde_json::Value;
+use tracing::{debug, instrument};
+
+use crate::{
+    actor::Actor,
+    auth_data::AuthData,
+    command::params_to_string,
+    dyn_command::{DynCommand, DynResponse},
+    error::{Error, Result},
+    request::Request,
+    response::Response,
+};
+
+/// This command represents any operation by name.
+///
+/// It goes through the same [AuthData]/[Request] pipeline as typed commands,
+/// but response message is left as [Value].
+/// Raw commands are never retried, their effect is unknown.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::raw::RawCommand;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let cmd = RawCommand {
+///         cmd: "set.system.led".to_string(),
+///         params: Some(serde_json::json!("auto")),
+///         secured: true,
+///         encrypted: false,
+///     };
+///     let response = cmd.execute(&actor).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RawCommand {
+    /// Command name, like `get.miner.status`
+    pub cmd: String,
+    /// Command parameters
+    ///
+    /// - [Value::String] is sent as is (`"enable"`)
+    /// - anything else is sent as JSON text
+    pub params: Option<Value>,
+    /// Include AuthData in request?
+    pub secured: bool,
+    /// Encrypt params data?
+    pub encrypted: bool,
+}
+
+impl RawCommand {
+    /// Creates a new unsecured [RawCommand] without params
+    pub fn new(cmd: impl Into<String>) -> Self {
+        Self {
+            cmd: cmd.into(),
+            ..Default::default()
+        }
+    }
+
+    #[instrument(level = "info", skip_all, fields(command_name = %self.cmd))]
+    /// Run command into actor
+    ///
+    /// Non-zero code of answer fails with [Error::Api], like for typed commands
+    pub async fn execute(&self, actor: &Actor) -> Result<Response<Value>> {
+        let out = actor.execute_raw_dyn(self).await?;
+        debug!(cmd=%self.cmd, "raw answer received");
+        serde_json::from_str(&out).map_err(|e| {
+            Error::from(e)
+                .decoding(&self.cmd)
+                .with_context(&actor.addr, &self.cmd)
+        })
+    }
+}
+
+impl DynCommand for RawCommand {
+    fn name(&self) -> &str {
+        &self.cmd
+    }
+    fn secured(&self) -> bool {
+        self.secured
+    }
+    fn encrypted(&self) -> bool {
+        self.encrypted
+    }
+    fn dyn_params(&self) -> Result<Option<String>> {
+        params_to_string(&self.params)
+    }
+    fn to_dyn_request(&self, auth_data: Option<AuthData>) -> Result<Request> {
+        Request::build(
+            self.cmd.clone(),
+            auth_data,
+            self.dyn_params()?,
+            self.encrypted,
+        )
+    }
+    fn dyn_response_from_str(&self, json: &str) -> Result<DynResponse> {
+        Ok(Box::new(serde_json::from_str::<Response<Value>>(json)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::account::Account;
+
+    use super::*;
+
+    #[test]
+    fn view() {
+        let cmd = RawCommand::new("get.miner.status");
+        let c = serde_json::to_string(&cmd.to_dyn_request(None).unwrap()).unwrap();
+        assert_eq!(c, r#"{"cmd":"get.miner.status"}"#);
+
+        let cmd = RawCommand {
+            params: Some(json!("summary,pools")),
+            ..cmd
+        };
+        let c = serde_json::to_string(&cmd.to_dyn_request(None).unwrap()).unwrap();
+        assert_eq!(c, r#"{"cmd":"get.miner.status","param":"summary,pools"}"#);
+    }
+
+    #[test]
+    fn encrypted() {
+        let cmd = RawCommand {
+            cmd: "set.miner.pools".to_string(),
+            params: Some(json!([{"pool": "stratum+tcp://1.1.1.1:3333"}])),
+            secured: true,
+            encrypted: true,
+        };
+        assert!(cmd.to_dyn_request(None).is_err());
+
+        let auth = AuthData::for_cmd(&cmd.cmd, Account::Super, "super", "salt").unwrap();
+        let request = cmd.to_dyn_request(Some(auth.clone())).unwrap();
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value["param"],
+            auth.encrypt(cmd.dyn_params().unwrap().unwrap()).unwrap()
+        );
+        assert_eq!(value["account"], "super");
+    }
+
+    #[test]
+    fn response() {
+        let cmd: Box<dyn DynCommand> = Box::new(RawCommand::new("get.miner.status"));
+        let out = cmd
+            .dyn_response_from_str(
+                r#"{"code":0,"when":1,"msg":{"summary":{}},"desc":"get.miner.status"}"#,
+            )
+            .unwrap()
+            .downcast::<Response<Value>>()
+            .unwrap();
+        assert!(out.msg["summary"].is_object());
+    }
+
+    #[tokio::test]
+    async fn api_error() {
+        use crate::{actor::mock, password::Password};
+
+        let addr = mock::spawn(|req| {
+            let cmd = req["cmd"].as_str()?;
+            match cmd {
+                "get.device.info" => None,
+                "set.system.led" => Some(json!({"code": -2, "msg": "invalid param"})),
+                _ => Some(mock::ok_answer(cmd, json!("ok"))),
+            }
+        })
+        .await;
+        let actor = Actor::new(&addr, Account::Super, Password::Super)
+            .await
+            .unwrap();
+
+        let mut raw = RawCommand::new("set.system.led");
+        raw.secured = true;
+        let e = raw.execute(&actor).await.unwrap_err();
+        assert!(matches!(e.root(), Error::Api { code: -2, .. }));
+        assert_eq!(e.cmd(), Some("set.system.led"));
+        assert!(
+            RawCommand::new("get.fan.setting")
+                .execute(&actor)
+                .await
+                .is_ok()
+        );
+    }
+}
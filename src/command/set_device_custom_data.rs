@@ -0,0 +1,45 @@
+//! Implement `set.device.custom_data` command
+//!
+//! It is used to write miner device custom information.
+//!
+//! - Command: [SetDeviceCustomData]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Device-device_set_custom_data>
+use crate::command::{Command, get_device_custom_data::GetDeviceCustomDataResponse};
+
+/// This command represents the `set.device.custom_data` operation.
+///
+/// It is used to write miner device custom information.
+/// Param: the same fields as [GetDeviceCustomDataResponse], so read data can be written back.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Device-device_set_custom_data>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::get_device_custom_data::GetDeviceCustomDataResponse;
+/// use matroskin::command::set_device_custom_data::SetDeviceCustomData;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let data = GetDeviceCustomDataResponse {
+///         msg0: "rack 1, shelf 3".to_string(),
+///         ..Default::default()
+///     };
+///     let response = actor.send(&SetDeviceCustomData(data)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.device.custom_data",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.device.custom_data","param":"{\"custom-sn\":\"\",\"msg0\":\"\",\"msg1\":\"\",\"msg2\":\"\",\"msg3\":\"\",\"msg4\":\"\",\"msg5\":\"\",\"msg6\":\"\",\"msg7\":\"\",\"msg8\":\"\",\"msg9\":\"\"}"}"#
+)]
+pub struct SetDeviceCustomData(pub GetDeviceCustomDataResponse);
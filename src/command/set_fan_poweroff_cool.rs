@@ -0,0 +1,40 @@
+//! Implement `set.fan.poweroff_cool` command
+//!
+//! It is used to keep fans cooling after miner is powered off.
+//!
+//! - Command: [SetFanPoweroffCool]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-btminer_poweroff_cool>
+use crate::command::Command;
+
+/// This command represents the `set.fan.poweroff_cool` operation.
+///
+/// It is used to keep fans cooling after miner is powered off.
+/// Param: `1` enables, `0` disables.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-btminer_poweroff_cool>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_fan_poweroff_cool::SetFanPoweroffCool;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetFanPoweroffCool(1)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.fan.poweroff_cool",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.fan.poweroff_cool","param":"0"}"#
+)]
+pub struct SetFanPoweroffCool(pub u8);
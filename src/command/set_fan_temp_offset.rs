@@ -0,0 +1,40 @@
+//! Implement `set.fan.temp_offset` command
+//!
+//! It is used to shift temperature, which fans are regulated by.
+//!
+//! - Command: [SetFanTempOffset]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-fan_set_temp_offset>
+use crate::command::Command;
+
+/// This command represents the `set.fan.temp_offset` operation.
+///
+/// It is used to shift temperature, which fans are regulated by.
+/// Param: offset in °C, usually in `-30..=0`.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-fan_set_temp_offset>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_fan_temp_offset::SetFanTempOffset;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetFanTempOffset(-5)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.fan.temp_offset",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.fan.temp_offset","param":"0"}"#
+)]
+pub struct SetFanTempOffset(pub i64);
@@ -0,0 +1,40 @@
+//! Implement `set.fan.zero_speed` command
+//!
+//! It is used to allow fans to stop (immersion and hydro cooling).
+//!
+//! - Command: [SetFanZeroSpeed]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-btminer_zero_speed>
+use crate::command::Command;
+
+/// This command represents the `set.fan.zero_speed` operation.
+///
+/// It is used to allow fans to stop (immersion and hydro cooling).
+/// Param: `1` enables, `0` disables.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Fan-btminer_zero_speed>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_fan_zero_speed::SetFanZeroSpeed;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetFanZeroSpeed(1)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.fan.zero_speed",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.fan.zero_speed","param":"0"}"#
+)]
+pub struct SetFanZeroSpeed(pub u8);
@@ -47,6 +47,7 @@ impl Command for SetMinerFastboot {
     type Response = Response<String>;
     const CMD_NAME: &'static str = "set.miner.fastboot";
     const SECURED: bool = true;
+    const IDEMPOTENT: bool = true;
     fn params(&self) -> Result<Option<String>> {
         Ok(Some(if self.0 { "enable" } else { "disable" }.to_string()))
     }
@@ -6,7 +6,7 @@
 //! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_set_pools>
 use core::str;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{command::Command, error::Result, response::Response};
 
@@ -44,7 +44,7 @@ use crate::{command::Command, error::Result, response::Response};
 #[derive(Debug, Default)]
 pub struct SetMinerPools(pub SetMinerPoolsParam);
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 /// Pool configuration
 pub struct SetMinerPoolsParamItem {
@@ -56,7 +56,7 @@ pub struct SetMinerPoolsParamItem {
     ///
     /// like: 1-9A-z.1-9A-z
     pub worker: String,
-    #[serde(rename = "passwd")]
+    #[serde(rename = "passwd", default)]
     /// Password
     /// - not required
     pub password: String,
@@ -0,0 +1,43 @@
+//! Implement `set.miner.power_limit` command
+//!
+//! It is used to limit power consumption of the miner.
+//!
+//! - Command: [SetMinerPowerLimit]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_power_limit>
+use crate::command::Command;
+
+/// This command represents the `set.miner.power_limit` operation.
+///
+/// It is used to limit power consumption of the miner.
+/// Param: limit in watts.
+///
+/// since 3.0.3v
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_power_limit>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_miner_power_limit::SetMinerPowerLimit;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetMinerPowerLimit(3000)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.miner.power_limit",
+    secured,
+    idempotent,
+    min_api = "3.0.3",
+    snapshot = r#"{"cmd":"set.miner.power_limit","param":"0"}"#
+)]
+pub struct SetMinerPowerLimit(pub u32);
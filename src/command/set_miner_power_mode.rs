@@ -0,0 +1,92 @@
+//! Implement `set.miner.power_mode` command
+//!
+//! It is used to switch the miner between low, normal and high power modes.
+//!
+//! - Command: [SetMinerPowerMode]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_power_mode>
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command::Command,
+    error::{Error, Result},
+};
+
+/// This command represents the `set.miner.power_mode` operation.
+///
+/// It is used to switch the miner between low, normal and high power modes.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_power_mode>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_miner_power_mode::{PowerMode, SetMinerPowerMode};
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetMinerPowerMode(PowerMode::Low)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.miner.power_mode",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.miner.power_mode","param":"normal"}"#
+)]
+pub struct SetMinerPowerMode(pub PowerMode);
+
+/// Power mode of miner
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerMode {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl FromStr for PowerMode {
+    type Err = Error;
+
+    /// Case-insensitive, as reported by [GetMinerSettingsResponse::power_mode](crate::command::get_miner_setting::GetMinerSettingsResponse::power_mode)
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            _ => Err(Error::InvalidPowerMode(s.to_string())),
+        }
+    }
+}
+
+impl Display for PowerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("High".parse::<PowerMode>().unwrap(), PowerMode::High);
+        assert_eq!(PowerMode::Low.to_string(), "low");
+        assert!("turbo".parse::<PowerMode>().is_err());
+    }
+}
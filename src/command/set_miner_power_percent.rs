@@ -0,0 +1,43 @@
+//! Implement `set.miner.power_percent` command
+//!
+//! It is used to run the miner at a share of its power limit (heaters, demand response).
+//!
+//! - Command: [SetMinerPowerPercent]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_set_power_percent>
+use crate::command::Command;
+
+/// This command represents the `set.miner.power_percent` operation.
+///
+/// It is used to run the miner at a share of its power limit (heaters, demand response).
+/// Param: percent, `0..=100`.
+///
+/// since 3.0.3v
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-Miner-btminer_set_power_percent>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_miner_power_percent::SetMinerPowerPercent;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetMinerPowerPercent(50)).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.miner.power_percent",
+    secured,
+    idempotent,
+    min_api = "3.0.3",
+    snapshot = r#"{"cmd":"set.miner.power_percent","param":"0"}"#
+)]
+pub struct SetMinerPowerPercent(pub u8);
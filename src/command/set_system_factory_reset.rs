@@ -0,0 +1,39 @@
+//! Implement `set.system.factory_reset` command
+//!
+//! It is used to reset the miner to factory settings.
+//!
+//! - Command: [SetSystemFactoryReset]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_factory_reset>
+use crate::command::Command;
+
+/// This command represents the `set.system.factory_reset` operation.
+///
+/// It is used to reset the miner to factory settings.
+/// It is destructive, so it is never retried ([Command::IDEMPOTENT] is `false`).
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_factory_reset>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_system_factory_reset::SetSystemFactoryReset;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetSystemFactoryReset).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.system.factory_reset",
+    secured,
+    snapshot = r#"{"cmd":"set.system.factory_reset"}"#
+)]
+pub struct SetSystemFactoryReset;
@@ -0,0 +1,39 @@
+//! Implement `set.system.hostname` command
+//!
+//! It is used to change the network hostname of the miner.
+//!
+//! - Command: [SetSystemHostname]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_hostname>
+use crate::command::Command;
+
+/// This command represents the `set.system.hostname` operation.
+///
+/// It is used to change the network hostname of the miner.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_hostname>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_system_hostname::SetSystemHostname;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetSystemHostname("rack1-17".to_string())).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.system.hostname",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.system.hostname","param":""}"#
+)]
+pub struct SetSystemHostname(pub String);
@@ -0,0 +1,42 @@
+//! Implement `set.system.ntp_server` command
+//!
+//! It is used to set time servers of the miner.
+//!
+//! - Command: [SetSystemNtpServer]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_ntp>
+use crate::command::Command;
+
+/// This command represents the `set.system.ntp_server` operation.
+///
+/// It is used to set time servers of the miner.
+/// Param: list of servers, in the same shape as
+/// [GetSystemSettingResponse::ntp_server](crate::command::get_system_setting::GetSystemSettingResponse::ntp_server).
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_ntp>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_system_ntp_server::SetSystemNtpServer;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let cmd = SetSystemNtpServer(vec!["pool.ntp.org".to_string()]);
+///     let response = actor.send(&cmd).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.system.ntp_server",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.system.ntp_server","param":"[]"}"#
+)]
+pub struct SetSystemNtpServer(pub Vec<String>);
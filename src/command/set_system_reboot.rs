@@ -0,0 +1,39 @@
+//! Implement `set.system.reboot` command
+//!
+//! It is used to reboot the miner.
+//!
+//! - Command: [SetSystemReboot]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_reboot>
+use crate::command::Command;
+
+/// This command represents the `set.system.reboot` operation.
+///
+/// It is used to reboot the miner.
+/// It is destructive, so it is never retried ([Command::IDEMPOTENT] is `false`).
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_reboot>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_system_reboot::SetSystemReboot;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let response = actor.send(&SetSystemReboot).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.system.reboot",
+    secured,
+    snapshot = r#"{"cmd":"set.system.reboot"}"#
+)]
+pub struct SetSystemReboot;
@@ -0,0 +1,52 @@
+//! Implement `set.system.timezone` command
+//!
+//! It is used to set timezone of the miner.
+//!
+//! - Command: [SetSystemTimezone]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_timezone>
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+
+/// This command represents the `set.system.timezone` operation.
+///
+/// It is used to set timezone of the miner.
+/// Fields are the same as `timezone` and `zonename` of
+/// [GetSystemSettingResponse](crate::command::get_system_setting::GetSystemSettingResponse).
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-System-system_set_timezone>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_system_timezone::SetSystemTimezone;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let cmd = SetSystemTimezone {
+///         timezone: "CST-8".to_string(),
+///         zonename: "Asia/Shanghai".to_string(),
+///     };
+///     let response = actor.send(&cmd).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Command)]
+#[command(
+    name = "set.system.timezone",
+    secured,
+    idempotent,
+    snapshot = r#"{"cmd":"set.system.timezone","param":"{\"timezone\":\"\",\"zonename\":\"\"}"}"#
+)]
+pub struct SetSystemTimezone {
+    /// POSIX timezone, like `CST-8`
+    pub timezone: String,
+    /// Zone name, like `Asia/Shanghai`
+    pub zonename: String,
+}
@@ -0,0 +1,104 @@
+//! Implement `set.user.change_passwd` command
+//!
+//! It is used to change password of account [SetUserChangePasswd].
+//!
+//! - Command: [SetUserChangePasswd]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-User-user_set_passwd>
+use std::fmt::Debug;
+
+use serde::Serialize;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{account::Account, command::Command};
+
+/// This command represents the `set.user.change_passwd` operation.
+///
+/// It is used to change password of account.
+/// Params are encrypted with the key of current password.
+/// Token of the next request should be built from the new password,
+/// so [Actor](crate::actor::Actor) must be recreated after success.
+///
+/// - ApiDoc: <https://apidoc.whatsminer.com/#api-User-user_set_passwd>
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::command::set_user_change_passwd::SetUserChangePasswd;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let cmd = SetUserChangePasswd::new(Account::Super, "super", "n3w-S3cret");
+///     let response = actor.send(&cmd).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Serialize, Zeroize, ZeroizeOnDrop, Command)]
+#[command(name = "set.user.change_passwd", secured, encrypted)]
+pub struct SetUserChangePasswd {
+    #[zeroize(skip)]
+    pub account: Account,
+    pub old: String,
+    pub new: String,
+}
+
+impl SetUserChangePasswd {
+    pub fn new(account: Account, old: impl Into<String>, new: impl Into<String>) -> Self {
+        Self {
+            account,
+            old: old.into(),
+            new: new.into(),
+        }
+    }
+}
+
+impl Default for SetUserChangePasswd {
+    fn default() -> Self {
+        Self::new(Account::Super, "", "")
+    }
+}
+
+impl Debug for SetUserChangePasswd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetUserChangePasswd")
+            .field("account", &self.account)
+            .field("old", &"<hidden>")
+            .field("new", &"<hidden>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth_data::AuthData;
+
+    const _: () = assert!(!SetUserChangePasswd::IDEMPOTENT);
+
+    #[test]
+    fn request() {
+        let cmd = SetUserChangePasswd::new(Account::Super, "super", "n3w");
+        let auth = AuthData::with_ts(
+            SetUserChangePasswd::CMD_NAME,
+            Account::Super,
+            "super",
+            "salt",
+            1,
+        )
+        .unwrap();
+        let key = auth.clone();
+        let request = serde_json::to_value(cmd.to_request(Some(auth)).unwrap()).unwrap();
+        let param = key.decrypt(request["param"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&param).unwrap(),
+            serde_json::json!({"account": "super", "old": "super", "new": "n3w"})
+        );
+        assert!(cmd.to_request(None).is_err());
+        assert!(!format!("{cmd:?}").contains("n3w"));
+    }
+}
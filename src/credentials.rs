@@ -0,0 +1,223 @@
+//! Define credentials module
+//!
+//! Fleet tools shouldn't keep passwords in code.
+//! [CredentialProvider] picks [Credentials] for miner by its address, MAC or serial number.
+//!
+//! - Item: [CredentialProvider], [Credentials], [MinerId]
+//! - Providers: [EnvProvider], [FileProvider], [MemoryProvider], [Fallback]
+pub mod env;
+pub mod file;
+pub mod memory;
+
+use std::fmt::Debug;
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub use crate::credentials::{env::EnvProvider, file::FileProvider, memory::MemoryProvider};
+use crate::{account::Account, error::Result, password::Password};
+
+/// Account and password of miner
+///
+/// Password is zeroized on drop and hidden in [Debug]
+#[derive(Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
+pub struct Credentials {
+    #[zeroize(skip)]
+    pub account: Account,
+    pub password: Password,
+}
+
+impl Credentials {
+    pub fn new(account: Account, password: impl Into<Password>) -> Self {
+        Self {
+            account,
+            password: password.into(),
+        }
+    }
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("account", &self.account)
+            .field("password", &"<hidden>")
+            .finish()
+    }
+}
+
+/// What is known about miner before authentication
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MinerId<'a> {
+    /// `host` or `host:port`
+    pub addr: &'a str,
+    pub mac: Option<&'a str>,
+    pub serial: Option<&'a str>,
+}
+
+impl<'a> MinerId<'a> {
+    pub fn addr(addr: &'a str) -> Self {
+        Self {
+            addr,
+            ..Default::default()
+        }
+    }
+
+    /// Does pattern match address (with or without port), MAC or serial?
+    ///
+    /// Pattern is a case-insensitive glob: `*` is any sequence, `?` is any char.
+    /// MAC is compared with `-` treated as `:`
+    pub fn matches(&self, pattern: &str) -> bool {
+        let host = self
+            .addr
+            .rsplit_once(':')
+            .map_or(self.addr, |(host, _)| host);
+        glob(pattern, self.addr)
+            || glob(pattern, host)
+            || self
+                .mac
+                .is_some_and(|mac| glob(&pattern.replace('-', ":"), &mac.replace('-', ":")))
+            || self.serial.is_some_and(|serial| glob(pattern, serial))
+    }
+}
+
+/// Source of [Credentials]
+pub trait CredentialProvider: Send + Sync {
+    /// Credentials for miner, `None` if provider doesn't know it
+    fn credentials(&self, id: &MinerId) -> Result<Option<Credentials>>;
+
+    /// Credentials for miners, which match nothing by address, MAC or serial
+    ///
+    /// Used by [Actor::with_provider](crate::actor::Actor::with_provider) only after all lookups missed
+    fn fallback(&self) -> Result<Option<Credentials>> {
+        Ok(None)
+    }
+}
+
+/// Default credentials, which never shadow lookups of other providers
+///
+/// # Example
+/// ```
+/// use matroskin::account::Account;
+/// use matroskin::credentials::{CredentialProvider, Credentials, Fallback, MemoryProvider, MinerId};
+///
+/// let mut file = MemoryProvider::new();
+/// file.insert("HTM50S*", Account::Super, "by-serial");
+/// let providers: Vec<Box<dyn CredentialProvider>> = vec![
+///     Box::new(file),
+///     Box::new(Fallback(Credentials::new(Account::Super, "super"))),
+/// ];
+///
+/// assert!(providers.credentials(&MinerId::addr("10.10.1.17")).unwrap().is_none());
+/// assert_eq!(providers.fallback().unwrap().unwrap().password.as_ref(), "super");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fallback(pub Credentials);
+
+impl CredentialProvider for Fallback {
+    fn credentials(&self, _id: &MinerId) -> Result<Option<Credentials>> {
+        Ok(None)
+    }
+
+    fn fallback(&self) -> Result<Option<Credentials>> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+/// The first provider, which knows miner, wins
+impl CredentialProvider for Vec<Box<dyn CredentialProvider>> {
+    fn credentials(&self, id: &MinerId) -> Result<Option<Credentials>> {
+        for provider in self {
+            if let Some(credentials) = provider.credentials(id)? {
+                return Ok(Some(credentials));
+            }
+        }
+        Ok(None)
+    }
+
+    fn fallback(&self) -> Result<Option<Credentials>> {
+        for provider in self {
+            if let Some(credentials) = provider.fallback()? {
+                return Ok(Some(credentials));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Case-insensitive glob with `*` and `?`
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of last `*` and text position it was tried with
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let id = MinerId {
+            addr: "10.10.1.17:4433",
+            mac: Some("C4-11-04-AA-BB-CC"),
+            serial: Some("HTM50SVK30240912"),
+        };
+        assert!(id.matches("10.10.1.*"));
+        assert!(id.matches("10.10.1.17"));
+        assert!(id.matches("*:4433"));
+        assert!(id.matches("c4:11:04:*"));
+        assert!(id.matches("HTM50S??30*"));
+        assert!(!id.matches("10.10.2.*"));
+        assert!(!id.matches("10.10.1.1"));
+
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXXbYYc"));
+        assert!(!glob("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn chain() {
+        let mut first = MemoryProvider::new();
+        first.insert("10.0.0.*", Account::User1, "one");
+        let mut second = MemoryProvider::new();
+        second.insert("*", Account::Super, "fallback");
+        let chain: Vec<Box<dyn CredentialProvider>> = vec![Box::new(first), Box::new(second)];
+
+        let c = chain
+            .credentials(&MinerId::addr("10.0.0.5"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(c.account, Account::User1);
+        let c = chain
+            .credentials(&MinerId::addr("10.0.1.5"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(c.password.as_ref(), "fallback");
+        assert_eq!(
+            format!("{c:?}"),
+            "Credentials { account: Super, password: \"<hidden>\" }"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Define environment credential provider
+//!
+//! - Item: [EnvProvider]
+use crate::{
+    account::Account,
+    credentials::{CredentialProvider, Credentials, MinerId},
+    error::Result,
+};
+
+/// Default [EnvProvider::prefix]
+pub const DEFAULT_PREFIX: &str = "MATROSKIN";
+
+/// Credentials from environment variables, the same for every miner
+///
+/// - `{prefix}_ACCOUNT`: account name, `super` if not set
+/// - `{prefix}_PASSWORD`: password, provider knows nothing without it
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvProvider {
+    pub prefix: String,
+}
+
+impl Default for EnvProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_PREFIX)
+    }
+}
+
+impl EnvProvider {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    fn resolve(&self, var: impl Fn(&str) -> Option<String>) -> Result<Option<Credentials>> {
+        let Some(password) = var(&format!("{}_PASSWORD", self.prefix)) else {
+            return Ok(None);
+        };
+        let account = match var(&format!("{}_ACCOUNT", self.prefix)) {
+            Some(account) => account.parse()?,
+            None => Account::Super,
+        };
+        Ok(Some(Credentials::new(account, password)))
+    }
+}
+
+impl CredentialProvider for EnvProvider {
+    fn credentials(&self, _id: &MinerId) -> Result<Option<Credentials>> {
+        self.resolve(|name| std::env::var(name).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let provider = EnvProvider::default();
+        assert_eq!(provider.resolve(|_| None).unwrap(), None);
+
+        let c = provider
+            .resolve(|name| match name {
+                "MATROSKIN_PASSWORD" => Some("secret".into()),
+                "MATROSKIN_ACCOUNT" => Some("User1".into()),
+                _ => None,
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(c, Credentials::new(Account::User1, "secret"));
+
+        assert!(provider.resolve(|name| Some(name.to_string())).is_err());
+    }
+}
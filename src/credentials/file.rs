@@ -0,0 +1,300 @@
+//! Define file credential provider
+//!
+//! - Item: [FileProvider]
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    account::Account,
+    credentials::{CredentialProvider, Credentials, MemoryProvider, MinerId},
+    error::{Error, Result},
+};
+
+/// Credentials from TOML or JSON file, format is chosen by extension
+///
+/// Miners are matched by patterns in file order ([MinerId::matches]):
+/// ```toml
+/// [[miner]]
+/// match = "10.10.1.*"
+/// password = "secret"
+///
+/// [[miner]]
+/// match = "c4:11:04:*"
+/// account = "user1"
+/// password = "other"
+/// ```
+/// JSON has the same shape: `{"miner": [{"match": "...", "password": "..."}]}`.
+/// `account` is `super` if omitted
+#[derive(Debug, Clone)]
+pub struct FileProvider {
+    pub path: PathBuf,
+    pub inner: MemoryProvider,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CredentialsFile {
+    #[serde(default)]
+    miner: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+struct Entry {
+    #[serde(rename = "match")]
+    pattern: String,
+    #[serde(default = "default_account")]
+    #[zeroize(skip)]
+    account: Account,
+    password: String,
+}
+
+fn default_account() -> Account {
+    Account::Super
+}
+
+impl FileProvider {
+    /// Read credentials file, `.json` is parsed as JSON, anything else as TOML
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut raw = std::fs::read_to_string(path)?;
+        let json = path.extension().is_some_and(|e| e == "json");
+        let inner = if json {
+            Self::parse_json(&raw)
+        } else {
+            Self::parse_toml(&raw)
+        };
+        raw.zeroize();
+        Ok(Self {
+            path: path.to_path_buf(),
+            inner: inner?,
+        })
+    }
+
+    /// Read the file again, e.g. after rotation
+    pub fn reload(&mut self) -> Result<()> {
+        *self = Self::load(&self.path)?;
+        Ok(())
+    }
+
+    /// Write credentials atomically, format is chosen by extension like in [FileProvider::load]
+    pub fn save(path: impl AsRef<Path>, provider: &MemoryProvider) -> Result<()> {
+        let path = path.as_ref();
+        let file = CredentialsFile {
+            miner: provider
+                .entries
+                .iter()
+                .map(|(pattern, c)| Entry {
+                    pattern: pattern.clone(),
+                    account: c.account,
+                    password: c.password.as_ref().to_string(),
+                })
+                .collect(),
+        };
+        let mut raw = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::to_string_pretty(&file)?
+        } else {
+            toml::to_string(&file).map_err(|e| Error::Credentials(e.to_string()))?
+        };
+        let out = write_atomic(path, raw.as_bytes());
+        raw.zeroize();
+        out
+    }
+
+    pub fn parse_toml(raw: &str) -> Result<MemoryProvider> {
+        let file: CredentialsFile =
+            toml::from_str(raw).map_err(|e| Error::Credentials(e.message().to_string()))?;
+        Ok(file.into_provider())
+    }
+
+    pub fn parse_json(raw: &str) -> Result<MemoryProvider> {
+        let file: CredentialsFile =
+            serde_json::from_str(raw).map_err(|e| Error::Credentials(e.to_string()))?;
+        Ok(file.into_provider())
+    }
+}
+
+impl CredentialsFile {
+    fn into_provider(self) -> MemoryProvider {
+        let mut out = MemoryProvider::new();
+        for entry in &self.miner {
+            out.insert(
+                entry.pattern.clone(),
+                entry.account,
+                entry.password.as_str(),
+            );
+        }
+        out
+    }
+}
+
+/// Suffix of temporary files, unique within process
+static TMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Write file via temporary file in the same directory and rename
+///
+/// Readers see either old or new content, never a half-written file.
+/// Temporary name is unique per process and call, so concurrent writers don't collide,
+/// the last rename wins. Directory is synced after rename, so it survives power loss.
+/// On unix the file is readable only by owner
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TMP_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    sync_dir(path)
+}
+
+/// Flush rename in parent directory
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories can't be opened for sync on this platform, rename is flushed by the OS
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+impl CredentialProvider for FileProvider {
+    fn credentials(&self, id: &MinerId) -> Result<Option<Credentials>> {
+        self.inner.credentials(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let toml = r#"
+            [[miner]]
+            match = "10.10.1.*"
+            password = "secret"
+
+            [[miner]]
+            match = "c4:11:04:*"
+            account = "user1"
+            password = "other"
+        "#;
+        let json = r#"{"miner": [
+            {"match": "10.10.1.*", "password": "secret"},
+            {"match": "c4:11:04:*", "account": "user1", "password": "other"}
+        ]}"#;
+        for provider in [
+            FileProvider::parse_toml(toml).unwrap(),
+            FileProvider::parse_json(json).unwrap(),
+        ] {
+            let c = provider
+                .credentials(&MinerId::addr("10.10.1.2:4433"))
+                .unwrap()
+                .unwrap();
+            assert_eq!(c, Credentials::new(Account::Super, "secret"));
+            let id = MinerId {
+                addr: "10.10.9.9",
+                mac: Some("C4:11:04:00:00:01"),
+                serial: None,
+            };
+            let c = provider.credentials(&id).unwrap().unwrap();
+            assert_eq!(c, Credentials::new(Account::User1, "other"));
+            assert!(
+                provider
+                    .credentials(&MinerId::addr("10.10.9.9"))
+                    .unwrap()
+                    .is_none()
+            );
+        }
+
+        assert!(matches!(
+            FileProvider::parse_toml(
+                "[[miner]]\nmatch = \"*\"\naccount = \"root\"\npassword = \"x\""
+            ),
+            Err(Error::Credentials(_))
+        ));
+    }
+
+    #[test]
+    fn load() {
+        let path =
+            std::env::temp_dir().join(format!("matroskin-creds-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"miner": [{"match": "*", "password": "secret"}]}"#,
+        )
+        .unwrap();
+        let provider = FileProvider::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let c = provider.credentials(&MinerId::addr("1.1.1.1")).unwrap();
+        assert_eq!(c.unwrap().password.as_ref(), "secret");
+    }
+
+    #[test]
+    fn save() {
+        let mut provider = MemoryProvider::new();
+        provider.insert("10.0.0.1", Account::Super, "one").insert(
+            "10.0.0.*",
+            Account::User1,
+            "two",
+        );
+        for ext in ["json", "toml"] {
+            let path =
+                std::env::temp_dir().join(format!("matroskin-save-{}.{ext}", std::process::id()));
+            FileProvider::save(&path, &provider).unwrap();
+            let loaded = FileProvider::load(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let c = loaded.credentials(&MinerId::addr("10.0.0.2")).unwrap();
+            assert_eq!(c, Some(Credentials::new(Account::User1, "two")));
+            assert_eq!(loaded.inner.entries.len(), 2);
+        }
+    }
+
+    #[test]
+    fn concurrent_writes() {
+        let dir = std::env::temp_dir().join(format!("matroskin-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("journal.json");
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let path = &path;
+                s.spawn(move || write_atomic(path, format!("{i}").as_bytes()).unwrap());
+            }
+        });
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        let last: u32 = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // one of the writers won, no temporary files are left
+        assert!(last < 8);
+        assert_eq!(names, ["journal.json"]);
+    }
+}
@@ -0,0 +1,57 @@
+//! Define in-memory credential provider
+//!
+//! - Item: [MemoryProvider]
+use crate::{
+    account::Account,
+    credentials::{CredentialProvider, Credentials, MinerId},
+    error::Result,
+    password::Password,
+};
+
+/// Ordered list of patterns with credentials, the first matching pattern wins
+///
+/// Patterns are matched by [MinerId::matches]
+///
+/// # Example
+/// ```
+/// use matroskin::account::Account;
+/// use matroskin::credentials::{CredentialProvider, MemoryProvider, MinerId};
+///
+/// let mut provider = MemoryProvider::new();
+/// provider.insert("10.10.1.*", Account::Super, "secret");
+///
+/// let credentials = provider.credentials(&MinerId::addr("10.10.1.17:4433")).unwrap();
+/// assert_eq!(credentials.unwrap().password.as_ref(), "secret");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryProvider {
+    pub entries: Vec<(String, Credentials)>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add credentials for miners matching `pattern`
+    pub fn insert(
+        &mut self,
+        pattern: impl Into<String>,
+        account: Account,
+        password: impl Into<Password>,
+    ) -> &mut Self {
+        self.entries
+            .push((pattern.into(), Credentials::new(account, password)));
+        self
+    }
+}
+
+impl CredentialProvider for MemoryProvider {
+    fn credentials(&self, id: &MinerId) -> Result<Option<Credentials>> {
+        Ok(self
+            .entries
+            .iter()
+            .find(|(pattern, _)| id.matches(pattern))
+            .map(|(_, credentials)| credentials.clone()))
+    }
+}
@@ -0,0 +1,201 @@
+//! Define firmware drift module
+//!
+//! Every response struct, and the [Response] envelope, keeps unknown keys in flattened `extra` map,
+//! so firmware updates don't break parsing. Missing keys fail parsing of fixed-shape
+//! answers (settings), only sections, which depend on model and firmware
+//! ([DeviceInfo](crate::command::get_device_info::DeviceInfo) parts, status parts),
+//! fall back to defaults.
+//!
+//! Strict mode ([Actor::send_strict], [Command::response_from_str_strict]) reports
+//! which fields were unexpected or missing, so drift is noticed early.
+//!
+//! - Item: [Drift], [ExtraFields]
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+#[cfg(doc)]
+use crate::{actor::Actor, command::Command};
+use crate::{error::Result, response::Response};
+
+/// Access to unknown keys of response struct and its nested structs
+pub trait ExtraFields {
+    /// Unknown keys of this struct
+    fn extra(&self) -> Option<&Map<String, Value>> {
+        None
+    }
+
+    /// Nested structs with their keys
+    fn nested(&self) -> Vec<(&'static str, &dyn ExtraFields)> {
+        Vec::new()
+    }
+}
+
+impl ExtraFields for Value {}
+impl ExtraFields for String {}
+impl ExtraFields for () {}
+
+impl<T: ExtraFields> ExtraFields for Vec<T> {
+    fn nested(&self) -> Vec<(&'static str, &dyn ExtraFields)> {
+        // index isn't kept in path, entries of one list share their keys
+        self.iter().map(|v| ("[]", v as &dyn ExtraFields)).collect()
+    }
+}
+
+impl<T: ExtraFields> ExtraFields for Option<T> {
+    fn extra(&self) -> Option<&Map<String, Value>> {
+        self.as_ref()?.extra()
+    }
+
+    fn nested(&self) -> Vec<(&'static str, &dyn ExtraFields)> {
+        self.as_ref().map(T::nested).unwrap_or_default()
+    }
+}
+
+impl<T: ExtraFields> ExtraFields for Response<T> {
+    fn extra(&self) -> Option<&Map<String, Value>> {
+        Some(&self.extra)
+    }
+
+    fn nested(&self) -> Vec<(&'static str, &dyn ExtraFields)> {
+        vec![("msg", &self.msg)]
+    }
+}
+
+/// Implement [ExtraFields] for struct with `extra` field
+///
+/// `key => field` pairs list nested structs
+macro_rules! extra_fields {
+    ($ty:ty $(, $key:literal => $field:ident)* $(,)?) => {
+        impl $crate::drift::ExtraFields for $ty {
+            fn extra(&self) -> Option<&serde_json::Map<String, serde_json::Value>> {
+                Some(&self.extra)
+            }
+
+            fn nested(&self) -> Vec<(&'static str, &dyn $crate::drift::ExtraFields)> {
+                vec![$(($key, &self.$field)),*]
+            }
+        }
+    };
+}
+pub(crate) use extra_fields;
+
+/// Difference between response and its declared struct
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// Dotted paths of keys unknown to the struct, like `msg.miner.new-field`
+    pub unexpected: Vec<String>,
+    /// Dotted paths of keys filled with defaults
+    pub missing: Vec<String>,
+}
+
+impl Drift {
+    /// Compare raw JSON with parsed value
+    pub fn check<T: Serialize + ExtraFields>(raw: &Value, parsed: &T) -> Result<Self> {
+        let mut out = Self::default();
+        collect_unexpected(parsed, "", &mut out.unexpected);
+        collect_missing(raw, &serde_json::to_value(parsed)?, "", &mut out.missing);
+        Ok(out)
+    }
+
+    /// No drift?
+    pub fn is_empty(&self) -> bool {
+        self.unexpected.is_empty() && self.missing.is_empty()
+    }
+
+    /// Log drift with `warn` level
+    pub fn warn(&self, cmd: &str) {
+        if !self.unexpected.is_empty() {
+            warn!(cmd = %cmd, fields = ?self.unexpected, "Unexpected fields in response");
+        }
+        if !self.missing.is_empty() {
+            warn!(cmd = %cmd, fields = ?self.missing, "Missing fields in response");
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn collect_unexpected(value: &dyn ExtraFields, prefix: &str, out: &mut Vec<String>) {
+    if let Some(extra) = value.extra() {
+        out.extend(extra.keys().map(|k| join(prefix, k)));
+    }
+    for (key, nested) in value.nested() {
+        collect_unexpected(nested, &join(prefix, key), out);
+    }
+}
+
+/// Keys present in `parsed` but absent in `raw`, `null`s are skipped
+fn collect_missing(raw: &Value, parsed: &Value, prefix: &str, out: &mut Vec<String>) {
+    let (Value::Object(raw), Value::Object(parsed)) = (raw, parsed) else {
+        return;
+    };
+    for (key, value) in parsed {
+        match raw.get(key) {
+            Some(raw) => collect_missing(raw, value, &join(prefix, key), out),
+            None if !value.is_null() => out.push(join(prefix, key)),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        command::{Command, get_device_info::GetDeviceInfo, get_system_setting::GetSystemSetting},
+        legacy::{command::LegacySummary, response::parse},
+    };
+
+    use super::*;
+
+    #[test]
+    fn drift() {
+        let json = include_str!("../.example-response/get.device.info.json");
+        let (response, drift) = GetDeviceInfo::response_from_str_strict(json).unwrap();
+        assert!(drift.is_empty(), "{drift:?}");
+        assert!(response.msg.extra.is_empty());
+
+        let mut raw: Value = serde_json::from_str(json).unwrap();
+        let miner = raw["msg"]["miner"].as_object_mut().unwrap();
+        miner.insert("new-field".into(), "1".into());
+        miner.remove("heatmode");
+        let json = raw.to_string();
+
+        let (response, drift) = GetDeviceInfo::response_from_str_strict(&json).unwrap();
+        assert_eq!(drift.unexpected, ["msg.miner.new-field"]);
+        assert_eq!(drift.missing, ["msg.miner.heatmode"]);
+
+        let miner = response.msg.miner.unwrap();
+        assert_eq!(miner.extra["new-field"], "1");
+        assert_eq!(miner.heatmode, "");
+    }
+
+    #[test]
+    fn envelope_and_settings() {
+        let json = include_str!("../.example-response/get.system.setting.json");
+        let mut raw: Value = serde_json::from_str(json).unwrap();
+        raw["trace"] = "abc".into();
+        let (_, drift) = GetSystemSetting::response_from_str_strict(&raw.to_string()).unwrap();
+        assert_eq!(drift.unexpected, ["trace"]);
+
+        // settings have fixed shape, missing key isn't hidden behind a default
+        raw["msg"].as_object_mut().unwrap().remove("zonename");
+        let e = GetSystemSetting::response_from_str(&raw.to_string()).unwrap_err();
+        assert!(e.to_string().contains("zonename"), "{e}");
+    }
+
+    #[test]
+    fn legacy() {
+        let json = r#"{"STATUS":[{"STATUS":"S","When":1,"Code":11,"Msg":"Summary","Description":""}],"SUMMARY":[{"Elapsed":42,"Chip Temp Max":80}],"id":1}"#;
+        let response = parse::<Vec<LegacySummary>>(json, "summary", Some("SUMMARY")).unwrap();
+        let raw: Value = serde_json::from_str(json).unwrap();
+        let drift = Drift::check(&raw["SUMMARY"], &response.msg).unwrap();
+        assert_eq!(drift.unexpected, ["[].Chip Temp Max"]);
+    }
+}
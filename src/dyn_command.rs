@@ -0,0 +1,163 @@
+//! Define object-safe command module
+//!
+//! [Command] has associated consts, types and generic [Command::execute],
+//! so it can't be boxed. [DynCommand] erases all of them,
+//! so different commands can be stored together (`Vec<Box<dyn DynCommand>>`).
+//!
+//! Every [Command] is a [DynCommand] automatically.
+//!
+//! - Item: [DynCommand], [DynResponse]
+//! - Where: [Actor::send_dyn]
+use std::any::Any;
+
+#[cfg(doc)]
+use crate::actor::Actor;
+use crate::{
+    auth_data::AuthData, capabilities::ApiVersion, command::Command, error::Result,
+    request::Request,
+};
+
+/// Type-erased [Command::Response]
+///
+/// Use [Box::downcast] to get typed response back
+pub type DynResponse = Box<dyn Any + Send>;
+
+/// Object-safe view of [Command]
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::actor::Actor;
+/// use matroskin::account::Account;
+/// use matroskin::password::Password;
+/// use matroskin::dyn_command::DynCommand;
+/// use matroskin::command::{
+///     get_fan_setting::{GetFanSettings, GetFanSettingsResponse},
+///     set_miner_fastboot::SetMinerFastboot,
+/// };
+/// use matroskin::response::Response;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let actor = Actor::new("10.10.10.10:4433", Account::Super, Password::Super).await?;
+///
+///     let jobs: Vec<Box<dyn DynCommand>> =
+///         vec![Box::new(GetFanSettings), Box::new(SetMinerFastboot(true))];
+///     for job in &jobs {
+///         let response = actor.send_dyn(job.as_ref()).await?;
+///         if let Some(fan) = response.downcast_ref::<Response<GetFanSettingsResponse>>() {
+///             println!("Fan: {:#?}", fan);
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait DynCommand: Send + Sync {
+    /// Represents the command name
+    ///
+    /// - Same as [Command::CMD_NAME]
+    fn name(&self) -> &str;
+    /// Include AuthData in request?
+    ///
+    /// - Same as [Command::SECURED]
+    fn secured(&self) -> bool;
+    /// Encrypt params data?
+    ///
+    /// - Same as [Command::ENCRYPTED]
+    fn encrypted(&self) -> bool;
+    /// Minimal api version which supports command
+    ///
+    /// - Same as [Command::MIN_API]
+    fn min_api(&self) -> Option<ApiVersion> {
+        None
+    }
+    /// Is it safe to repeat command?
+    ///
+    /// - Same as [Command::IDEMPOTENT]
+    fn idempotent(&self) -> bool {
+        false
+    }
+    /// Return local params
+    ///
+    /// - Same as [Command::params]
+    fn dyn_params(&self) -> Result<Option<String>>;
+    /// Convert command to request
+    ///
+    /// - Same as [Command::to_request]
+    fn to_dyn_request(&self, auth_data: Option<AuthData>) -> Result<Request>;
+    /// Deserialize raw response into type-erased response
+    ///
+    /// - Same as [Command::response_from_str]
+    fn dyn_response_from_str(&self, json: &str) -> Result<DynResponse>;
+}
+
+impl<C> DynCommand for C
+where
+    C: Command + Send + Sync,
+    C::Response: Send + 'static,
+{
+    fn name(&self) -> &str {
+        C::CMD_NAME
+    }
+    fn secured(&self) -> bool {
+        C::SECURED
+    }
+    fn encrypted(&self) -> bool {
+        C::ENCRYPTED
+    }
+    fn min_api(&self) -> Option<ApiVersion> {
+        C::MIN_API
+    }
+    fn idempotent(&self) -> bool {
+        C::IDEMPOTENT
+    }
+    fn dyn_params(&self) -> Result<Option<String>> {
+        Command::params(self)
+    }
+    fn to_dyn_request(&self, auth_data: Option<AuthData>) -> Result<Request> {
+        Command::to_request(self, auth_data)
+    }
+    fn dyn_response_from_str(&self, json: &str) -> Result<DynResponse> {
+        Ok(Box::new(C::response_from_str(json)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        account::Account,
+        command::{
+            get_fan_setting::{GetFanSettings, GetFanSettingsResponse},
+            set_miner_fastboot::SetMinerFastboot,
+        },
+        response::Response,
+    };
+
+    use super::*;
+
+    #[test]
+    fn heterogeneous() {
+        let jobs: Vec<Box<dyn DynCommand>> =
+            vec![Box::new(GetFanSettings), Box::new(SetMinerFastboot(true))];
+
+        let names: Vec<_> = jobs.iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["get.fan.setting", "set.miner.fastboot"]);
+        assert!(!jobs[0].secured());
+        assert!(jobs[1].secured());
+        assert_eq!(jobs[1].dyn_params().unwrap().as_deref(), Some("enable"));
+
+        let auth = AuthData::for_cmd(jobs[1].name(), Account::Super, "super", "salt").unwrap();
+        let request = jobs[1].to_dyn_request(Some(auth)).unwrap();
+        assert_eq!(request.cmd, "set.miner.fastboot");
+    }
+
+    #[test]
+    fn downcast() {
+        let json = r#"{"code":0,"when":1,"msg":{"fan-poweroff-cool":1,"fan-zero-speed":0,"fan-temp-offset":-5},"desc":"get.fan.setting"}"#;
+        let cmd: Box<dyn DynCommand> = Box::new(GetFanSettings);
+        let response = cmd.dyn_response_from_str(json).unwrap();
+        let response = response
+            .downcast::<Response<GetFanSettingsResponse>>()
+            .unwrap();
+        assert_eq!(response.msg.fan_temp_offset, -5);
+    }
+}
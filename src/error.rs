@@ -1,9 +1,15 @@
 //! Declares errors what can happened in program
+//!
+//! Errors of [Actor](crate::actor::Actor) carry miner address and command name ([Error::Context]),
+//! [Error::kind] tells where it failed, so fleet code can decide whether to retry,
+//! re-authenticate or alert:
+//! - [Error::is_retryable]
+//! - [Error::is_auth_failure]
 use hex::FromHexError;
 use thiserror::Error;
 use tokio::sync::mpsc::error::SendError;
 
-use crate::actor::message::ActorMessage;
+use crate::{account::Account, actor::message::ActorMessage, capabilities::ApiVersion};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -11,20 +17,304 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("Json error: {0}")]
     Json(#[from] serde_json::Error),
-    #[error("Aes error: {0}")]
+    #[error("Actor worker is stopped: {0}")]
     SendMPSC(#[from] SendError<ActorMessage>),
-    #[error("Aes error: {0}")]
+    #[error("Actor worker dropped request: {0}")]
     RecvOneshot(#[from] tokio::sync::oneshot::error::RecvError),
     #[error("Salt not found")]
     SaltNotFound,
-    #[error("Corrupted token")]
-    CurraptedToken,
     #[error("Command {0}: should have AuthData ")]
-    CommandSholdHaveAuthData(&'static str),
+    CommandSholdHaveAuthData(String),
     #[error("Hex got error")]
     Hex(#[from] FromHexError),
     #[error("Encryption failed")]
     EncryptionFailed,
+    #[error("Decryption failed")]
+    DecryptionFailed,
+    #[error("Legacy api error: {0}")]
+    Legacy(String),
+    #[error("Command {cmd} is not supported by api {api}")]
+    Unsupported { cmd: String, api: ApiVersion },
+    #[error("Invalid version: {0}")]
+    InvalidVersion(String),
+    #[error("Invalid account: {0}")]
+    InvalidAccount(String),
+    #[error("Invalid credentials: {0}")]
+    Credentials(String),
+    #[error("No credentials for {0}")]
+    NoCredentials(String),
+    #[error("Invalid permission: {0}")]
+    InvalidPermission(String),
+    #[error("Account {account} has no permission for {cmd}")]
+    PermissionDenied { account: Account, cmd: String },
+    #[error("Invalid power mode: {0}")]
+    InvalidPowerMode(String),
+    #[error("Invalid miner model: {0}")]
+    InvalidModel(String),
+    #[error("Frame of {len} bytes exceeds the limit of {max} bytes")]
+    FrameTooLarge { len: usize, max: usize },
+    #[error("Received zero-length frame")]
+    EmptyFrame,
+    #[error("Can't connect to {addr}: {source}")]
+    Connect {
+        addr: String,
+        source: std::io::Error,
+    },
+    #[error("Handshake with {addr} failed: {source}")]
+    Handshake { addr: String, source: Box<Error> },
+    /// Miner answered with non-zero code
+    #[error("Command {cmd} failed with code {code}: {msg}")]
+    Api { cmd: String, code: i64, msg: String },
+    #[error("Can't decode response of {cmd}: {source}")]
+    Decode {
+        cmd: String,
+        source: serde_json::Error,
+    },
+    #[error("System clock is before unix epoch")]
+    ClockBeforeEpoch,
+    #[error("Invalid spec: {0}")]
+    Spec(String),
+    #[error("Invalid snapshot: {0}")]
+    Snapshot(String),
+    #[error("Mqtt error: {0}")]
+    Mqtt(String),
+    /// `cmd` is empty if error isn't bound to command
+    #[error("{}: {source}", location(.addr, .cmd))]
+    Context {
+        addr: String,
+        cmd: String,
+        source: Box<Error>,
+    },
+}
+
+/// `addr (cmd)` of [Error::Context]
+fn location(addr: &str, cmd: &str) -> String {
+    if cmd.is_empty() {
+        addr.to_string()
+    } else {
+        format!("{addr} ({cmd})")
+    }
+}
+
+/// Where [enum@Error] happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// TCP connect failed
+    Connect,
+    /// Connected, but salt and capabilities weren't received
+    Handshake,
+    /// Wrong password, token or permission
+    Auth,
+    /// Broken framing or unexpected answer shape
+    Protocol,
+    /// Miner rejected command, or api doesn't support it
+    Api,
+    /// Answer doesn't match response type
+    Decode,
+    /// IO failure on established connection
+    Io,
+    /// Actor worker is stopped, new connection is required
+    Closed,
+    /// Invalid input on our side (params, versions, models)
+    Local,
+}
+
+/// Words of [Error::Api] and [Error::Legacy] messages, which mean authentication failure
+const AUTH_WORDS: &[&str] = &["token", "passw", "auth", "permission", "denied", "login"];
+
+fn is_auth_message(msg: &str) -> bool {
+    let msg = msg.to_ascii_lowercase();
+    AUTH_WORDS.iter().any(|w| msg.contains(w))
+}
+
+impl Error {
+    /// Attach miner address and command to error
+    pub fn with_context(self, addr: impl Into<String>, cmd: impl Into<String>) -> Self {
+        match self {
+            Self::Context { .. } => self,
+            source => Self::Context {
+                addr: addr.into(),
+                cmd: cmd.into(),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Attach miner address to error, which isn't bound to one command
+    pub fn with_addr(self, addr: impl Into<String>) -> Self {
+        match self {
+            Self::Context { .. } | Self::Connect { .. } | Self::Handshake { .. } => self,
+            source => {
+                let cmd = source.cmd().unwrap_or_default().to_string();
+                source.with_context(addr, cmd)
+            }
+        }
+    }
+
+    /// Turn [Error::Json] into [Error::Decode] of command
+    pub fn decoding(self, cmd: impl Into<String>) -> Self {
+        match self {
+            Self::Json(source) => Self::Decode {
+                cmd: cmd.into(),
+                source,
+            },
+            e => e,
+        }
+    }
+
+    /// Error without [Error::Context] and [Error::Handshake] wrappers
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } | Self::Handshake { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// Miner address, if known
+    pub fn addr(&self) -> Option<&str> {
+        match self {
+            Self::Context { addr, .. }
+            | Self::Connect { addr, .. }
+            | Self::Handshake { addr, .. } => Some(addr),
+            _ => None,
+        }
+    }
+
+    /// Command name, if known
+    pub fn cmd(&self) -> Option<&str> {
+        if let Self::Context { cmd, .. } = self
+            && !cmd.is_empty()
+        {
+            return Some(cmd);
+        }
+        match self.root() {
+            Self::Api { cmd, .. }
+            | Self::Decode { cmd, .. }
+            | Self::Unsupported { cmd, .. }
+            | Self::PermissionDenied { cmd, .. }
+            | Self::CommandSholdHaveAuthData(cmd) => Some(cmd),
+            _ => None,
+        }
+    }
+
+    /// Where error happened
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Context { source, .. } => source.kind(),
+            Self::Connect { .. } => ErrorKind::Connect,
+            Self::Handshake { .. } | Self::SaltNotFound => ErrorKind::Handshake,
+            Self::Api { msg, .. } if is_auth_message(msg) => ErrorKind::Auth,
+            Self::Legacy(msg) if is_auth_message(msg) => ErrorKind::Auth,
+            Self::PermissionDenied { .. } => ErrorKind::Auth,
+            // legacy AES key is derived from password
+            Self::DecryptionFailed => ErrorKind::Auth,
+            Self::Api { .. } | Self::Unsupported { .. } => ErrorKind::Api,
+            Self::Legacy(_) | Self::FrameTooLarge { .. } | Self::EmptyFrame => ErrorKind::Protocol,
+            Self::Json(_) | Self::Decode { .. } => ErrorKind::Decode,
+            Self::Io(_) | Self::Mqtt(_) => ErrorKind::Io,
+            Self::SendMPSC(_) | Self::RecvOneshot(_) => ErrorKind::Closed,
+            Self::CommandSholdHaveAuthData(_)
+            | Self::Hex(_)
+            | Self::EncryptionFailed
+            | Self::InvalidVersion(_)
+            | Self::InvalidModel(_)
+            | Self::InvalidPowerMode(_)
+            | Self::InvalidAccount(_)
+            | Self::InvalidPermission(_)
+            | Self::Credentials(_)
+            | Self::Spec(_)
+            | Self::Snapshot(_)
+            | Self::NoCredentials(_)
+            | Self::ClockBeforeEpoch => ErrorKind::Local,
+        }
+    }
+
+    /// Could the same request succeed if sent again?
+    ///
+    /// Transient network failures are retryable,
+    /// rejected commands, auth and decode failures are not
+    pub fn is_retryable(&self) -> bool {
+        use std::io::ErrorKind as Io;
+        match self {
+            Self::Context { source, .. } | Self::Handshake { source, .. } => source.is_retryable(),
+            Self::Connect { .. } | Self::EmptyFrame => true,
+            Self::Io(e) => matches!(
+                e.kind(),
+                Io::ConnectionReset
+                    | Io::ConnectionAborted
+                    | Io::ConnectionRefused
+                    | Io::NotConnected
+                    | Io::BrokenPipe
+                    | Io::TimedOut
+                    | Io::UnexpectedEof
+                    | Io::Interrupted
+                    | Io::WouldBlock
+            ),
+            _ => false,
+        }
+    }
+
+    /// Should credentials be checked (or token renewed)?
+    pub fn is_auth_failure(&self) -> bool {
+        self.kind() == ErrorKind::Auth
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        let e = Error::Api {
+            cmd: "set.miner.fastboot".into(),
+            code: -1,
+            msg: "invalid token".into(),
+        }
+        .with_addr("10.10.10.10:4433");
+        assert_eq!(e.kind(), ErrorKind::Auth);
+        assert!(e.is_auth_failure());
+        assert!(!e.is_retryable());
+        assert_eq!(e.addr(), Some("10.10.10.10:4433"));
+        assert_eq!(e.cmd(), Some("set.miner.fastboot"));
+        assert_eq!(
+            e.to_string(),
+            "10.10.10.10:4433 (set.miner.fastboot): Command set.miner.fastboot failed with code -1: invalid token"
+        );
+
+        let e = Error::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            .with_context("10.10.10.10:4433", "get.fan.setting");
+        assert_eq!(e.cmd(), Some("get.fan.setting"));
+        assert!(e.is_retryable());
+        assert_eq!(
+            e.to_string(),
+            "10.10.10.10:4433 (get.fan.setting): Io error: broken pipe"
+        );
+        assert_eq!(
+            Error::SaltNotFound.with_addr("a").to_string(),
+            "a: Salt not found"
+        );
+
+        let e = Error::Handshake {
+            addr: "a".into(),
+            source: Box::new(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into()),
+        };
+        assert_eq!(e.kind(), ErrorKind::Handshake);
+        assert!(e.is_retryable());
+
+        let e =
+            Error::from(serde_json::from_str::<u8>("x").unwrap_err()).decoding("get.fan.setting");
+        assert_eq!(e.kind(), ErrorKind::Decode);
+        assert_eq!(e.cmd(), Some("get.fan.setting"));
+        assert!(!e.is_retryable());
+
+        assert!(Error::EmptyFrame.is_retryable());
+        assert!(
+            !Error::FrameTooLarge { len: 2, max: 1 }
+                .with_addr("a")
+                .is_retryable()
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Define miner error code module
+//!
+//! Catalog of known WhatsMiner error codes with category, severity and suggested action,
+//! so alerting can say "board 1: EEPROM parsing failed" instead of a number.
+//!
+//! Catalog is not exhaustive, unknown codes are classified by their range.
+//!
+//! - Item: [ErrorCode], [ErrorInfo]
+//! - List of errors: <https://www.whatsminer.com/src/views/firmware-download.html#Document>
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+#[cfg(doc)]
+use crate::command::get_device_info::DeviceInfo;
+
+/// What part of miner is broken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorCategory {
+    Fan,
+    Power,
+    Temperature,
+    Hashboard,
+    Eeprom,
+    Network,
+    Other,
+}
+
+/// How bad it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational, no action needed
+    Info,
+    /// Miner works, but degraded
+    Warning,
+    /// Miner (or board) doesn't mine
+    Critical,
+}
+
+/// Catalog entry for range of codes
+struct Entry {
+    first: u32,
+    last: u32,
+    /// `first` is board 0, `first + 1` is board 1, ...
+    per_board: bool,
+    category: ErrorCategory,
+    severity: Severity,
+    description: &'static str,
+    action: &'static str,
+}
+
+const fn entry(
+    first: u32,
+    last: u32,
+    per_board: bool,
+    category: ErrorCategory,
+    severity: Severity,
+    description: &'static str,
+    action: &'static str,
+) -> Entry {
+    Entry {
+        first,
+        last,
+        per_board,
+        category,
+        severity,
+        description,
+        action,
+    }
+}
+
+use ErrorCategory::*;
+use Severity::*;
+
+const CHECK_FAN: &str = "Check fan power cable and clean or replace the fan";
+const CHECK_PSU: &str = "Check power supply, input voltage and power cables";
+const CHECK_AIR: &str = "Check ambient temperature, airflow and dust on heatsinks";
+const CHECK_BOARD: &str = "Check hash board cables, reseat or replace the hash board";
+const CHECK_POOL: &str = "Check network connection and pool settings";
+
+/// Known error codes
+#[rustfmt::skip]
+static CATALOG: &[Entry] = &[
+    entry(110, 111, false, Fan, Critical, "fan speed error", CHECK_FAN),
+    entry(120, 121, false, Fan, Critical, "fan speed deviates from target", CHECK_FAN),
+    entry(130, 131, false, Fan, Critical, "fan speed reading failed", CHECK_FAN),
+    entry(140, 140, false, Fan, Warning, "fan speed is too high", CHECK_AIR),
+    entry(200, 200, false, Power, Critical, "no power supply found", CHECK_PSU),
+    entry(201, 201, false, Power, Critical, "power supply doesn't match configuration", "Use power supply of matched model"),
+    entry(202, 202, false, Power, Critical, "power output voltage error", CHECK_PSU),
+    entry(203, 204, false, Power, Critical, "power protecting due to high environment temperature", CHECK_AIR),
+    entry(205, 205, false, Power, Critical, "power current error", CHECK_PSU),
+    entry(206, 206, false, Power, Critical, "power input voltage is too low", CHECK_PSU),
+    entry(207, 207, false, Power, Critical, "power input current protecting", CHECK_PSU),
+    entry(210, 210, false, Power, Critical, "power error", CHECK_PSU),
+    entry(213, 213, false, Power, Warning, "power input doesn't match power output", CHECK_PSU),
+    entry(216, 216, false, Power, Warning, "power remained unchanged for a long time", CHECK_PSU),
+    entry(217, 217, false, Power, Critical, "power set enable error", CHECK_PSU),
+    entry(218, 218, false, Power, Warning, "power input voltage is too low for high power mode", CHECK_PSU),
+    entry(233, 235, false, Power, Critical, "power output over-temperature protection", CHECK_AIR),
+    entry(236, 238, false, Power, Critical, "power output over-current protection", CHECK_PSU),
+    entry(239, 239, false, Power, Critical, "power output over-voltage protection", CHECK_PSU),
+    entry(240, 240, false, Power, Critical, "power output under-voltage protection", CHECK_PSU),
+    entry(243, 245, false, Power, Critical, "power input over-temperature protection", CHECK_AIR),
+    entry(246, 247, false, Power, Critical, "power input over-voltage protection", CHECK_PSU),
+    entry(248, 249, false, Power, Critical, "power input over-current protection", CHECK_PSU),
+    entry(250, 251, false, Power, Critical, "power input under-voltage protection", CHECK_PSU),
+    entry(253, 254, false, Power, Critical, "power supply fan error", CHECK_FAN),
+    entry(263, 264, false, Power, Warning, "power supply communication error", CHECK_PSU),
+    entry(300, 302, true, Temperature, Critical, "temperature sensor detection failed", CHECK_BOARD),
+    entry(320, 322, true, Temperature, Critical, "temperature reading failed", CHECK_BOARD),
+    entry(329, 329, false, Temperature, Warning, "control board temperature sensor communication error", "Check or replace control board"),
+    entry(350, 352, true, Temperature, Critical, "temperature protecting", CHECK_AIR),
+    entry(360, 360, false, Temperature, Critical, "hash board temperature is too high", CHECK_AIR),
+    entry(410, 412, true, Hashboard, Critical, "detected chip number is not enough", CHECK_BOARD),
+    entry(420, 422, true, Eeprom, Critical, "EEPROM parsing failed", "Reseat hash board or rewrite its EEPROM"),
+    entry(510, 512, true, Eeprom, Critical, "miner type from EEPROM is wrong", "Rewrite hash board EEPROM"),
+    entry(530, 532, true, Hashboard, Critical, "hash board was not found", CHECK_BOARD),
+    entry(600, 600, false, Temperature, Warning, "environment temperature is too high", CHECK_AIR),
+    entry(610, 610, false, Temperature, Warning, "environment temperature is too high for high performance mode", CHECK_AIR),
+    entry(2010, 2010, false, Network, Critical, "all pools are disabled", CHECK_POOL),
+    entry(2020, 2022, false, Network, Warning, "pool connection failed", CHECK_POOL),
+    entry(2030, 2030, false, Network, Warning, "high rejection rate on pool", CHECK_POOL),
+    entry(2040, 2040, false, Network, Critical, "all pools don't support stratum", CHECK_POOL),
+    entry(2310, 2310, false, Hashboard, Warning, "hash rate is too low", CHECK_BOARD),
+    entry(2320, 2340, false, Hashboard, Warning, "hash rate loss is too high", CHECK_BOARD),
+];
+
+/// Description of error code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorInfo {
+    /// Raw code
+    pub code: u32,
+    pub category: ErrorCategory,
+    pub severity: Severity,
+    /// Human-readable description
+    pub description: &'static str,
+    /// Suggested action
+    pub action: &'static str,
+    /// Hash board index (`SM0` is `0`), if error is board specific
+    pub board: Option<u8>,
+    /// Is code found in catalog?
+    pub known: bool,
+}
+
+impl ErrorInfo {
+    /// Describe code, unknown codes are classified by range
+    pub fn lookup(code: u32) -> Self {
+        match CATALOG.iter().find(|e| (e.first..=e.last).contains(&code)) {
+            Some(e) => Self {
+                code,
+                category: e.category,
+                severity: e.severity,
+                description: e.description,
+                action: e.action,
+                board: e.per_board.then(|| (code - e.first) as u8),
+                known: true,
+            },
+            None => Self {
+                code,
+                category: category_of(code),
+                severity: Warning,
+                description: "unknown error",
+                action: "See vendor error code list",
+                board: None,
+                known: false,
+            },
+        }
+    }
+}
+
+impl Display for ErrorInfo {
+    /// `board 1: EEPROM parsing failed (422)`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(board) = self.board {
+            write!(f, "board {board}: ")?;
+        }
+        write!(f, "{} ({})", self.description, self.code)
+    }
+}
+
+/// Category by code range
+fn category_of(code: u32) -> ErrorCategory {
+    match code {
+        100..=199 => Fan,
+        200..=299 => Power,
+        300..=399 | 600..=699 => Temperature,
+        400..=599 | 2300..=2399 | 5000..=5999 => Hashboard,
+        2000..=2099 => Network,
+        _ => Other,
+    }
+}
+
+/// Error code reported by miner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// Raw code
+    pub code: u32,
+    /// When error occurred, as reported by miner
+    pub time: Option<String>,
+}
+
+impl ErrorCode {
+    /// Description of code
+    pub fn info(&self) -> ErrorInfo {
+        ErrorInfo::lookup(self.code)
+    }
+
+    /// Parse raw list from [DeviceInfo::error_codes]
+    ///
+    /// Both `{"110": "2025-01-01 00:00:00"}` and `{"code": "110", "time": "..."}`
+    /// shapes are accepted, entries with non-numeric codes are skipped
+    pub fn parse_list(list: &[HashMap<String, String>]) -> Vec<Self> {
+        let mut out = Vec::with_capacity(list.len());
+        for item in list {
+            if let Some(code) = item.get("code") {
+                if let Ok(code) = code.trim().parse() {
+                    out.push(Self {
+                        code,
+                        time: item.get("time").cloned(),
+                    });
+                }
+                continue;
+            }
+            out.extend(item.iter().filter_map(|(code, time)| {
+                Some(Self {
+                    code: code.trim().parse().ok()?,
+                    time: Some(time.clone()),
+                })
+            }));
+        }
+        out
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.info())?;
+        if let Some(time) = &self.time {
+            write!(f, " at {time}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Group errors by category
+pub fn group_by_category(errors: &[ErrorCode]) -> BTreeMap<ErrorCategory, Vec<&ErrorCode>> {
+    let mut out: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for error in errors {
+        out.entry(error.info().category).or_default().push(error);
+    }
+    out
+}
+
+/// Group errors by hash board, `None` holds errors not related to a board
+pub fn group_by_board(errors: &[ErrorCode]) -> BTreeMap<Option<u8>, Vec<&ErrorCode>> {
+    let mut out: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for error in errors {
+        out.entry(error.info().board).or_default().push(error);
+    }
+    out
+}
+
+/// The worst severity between errors
+pub fn max_severity(errors: &[ErrorCode]) -> Option<Severity> {
+    errors.iter().map(|e| e.info().severity).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let info = ErrorInfo::lookup(421);
+        assert_eq!(info.category, Eeprom);
+        assert_eq!(info.board, Some(1));
+        assert_eq!(info.to_string(), "board 1: EEPROM parsing failed (421)");
+
+        let info = ErrorInfo::lookup(110);
+        assert_eq!(info.category, Fan);
+        assert_eq!(info.board, None);
+
+        let info = ErrorInfo::lookup(299);
+        assert!(!info.known);
+        assert_eq!(info.category, Power);
+    }
+
+    #[test]
+    fn parse_and_group() {
+        let list = vec![
+            HashMap::from([("110".to_string(), "2025-01-01 00:00:00".to_string())]),
+            HashMap::from([
+                ("code".to_string(), "2020".to_string()),
+                ("time".to_string(), "2025-01-01 00:00:01".to_string()),
+            ]),
+            HashMap::from([("412".to_string(), "2025-01-01 00:00:02".to_string())]),
+            HashMap::from([("reason".to_string(), "idk".to_string())]),
+        ];
+        let errors = ErrorCode::parse_list(&list);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[0].to_string(),
+            "fan speed error (110) at 2025-01-01 00:00:00"
+        );
+
+        let by_category = group_by_category(&errors);
+        assert_eq!(by_category[&Fan].len(), 1);
+        assert_eq!(by_category[&Network][0].code, 2020);
+
+        let by_board = group_by_board(&errors);
+        assert_eq!(by_board[&Some(2)][0].code, 412);
+        assert_eq!(max_severity(&errors), Some(Critical));
+    }
+}
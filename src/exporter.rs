@@ -0,0 +1,683 @@
+//! Define Prometheus exporter module
+//!
+//! [Exporter] polls a fleet on interval and keeps the last result of every miner.
+//! Scrapes of `/metrics` are answered from that cache, so Prometheus never talks
+//! to miners directly and many scrapers don't multiply load.
+//!
+//! Every series has `addr`, `model`, `serial` and `hostname` labels.
+//! Miners, which didn't answer, keep labels of their last good poll and report `whatsminer_up 0`.
+//!
+//! - Item: [Exporter], [ExporterConfig], [Sample]
+//! - Format: <https://prometheus.io/docs/instrumenting/exposition_formats/>
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    net::TcpListener,
+    sync::{Mutex, RwLock, Semaphore},
+    task::JoinSet,
+    time::{MissedTickBehavior, timeout},
+};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    actor::{Actor, config::ActorConfig},
+    command::{
+        get_device_info::{GetDeviceInfo, GetDeviceInfoParam},
+        get_fan_setting::GetFanSettings,
+        get_miner_setting::GetMinerSettings,
+        get_miner_status::GetMinerStatus,
+    },
+    credentials::CredentialProvider,
+    error::{Error, Result},
+    error_code::group_by_category,
+    http::{self, HttpRequest, HttpResponse},
+};
+
+/// Exported metrics: name, type and help
+pub const METRICS: &[(&str, &str, &str)] = &[
+    (
+        "whatsminer_up",
+        "gauge",
+        "1 if miner answered the last poll",
+    ),
+    (
+        "whatsminer_poll_duration_seconds",
+        "gauge",
+        "Duration of the last poll",
+    ),
+    (
+        "whatsminer_working",
+        "gauge",
+        "1 if miner reports it's mining",
+    ),
+    (
+        "whatsminer_elapsed_seconds",
+        "gauge",
+        "Seconds since mining started",
+    ),
+    (
+        "whatsminer_hashrate_terahashes",
+        "gauge",
+        "Hash rate by averaging window, TH/s",
+    ),
+    (
+        "whatsminer_factory_hashrate_terahashes",
+        "gauge",
+        "Nominal hash rate, TH/s",
+    ),
+    (
+        "whatsminer_frequency_megahertz",
+        "gauge",
+        "Chip frequency, MHz",
+    ),
+    ("whatsminer_power_watts", "gauge", "Power consumption, W"),
+    (
+        "whatsminer_power_limit_watts",
+        "gauge",
+        "Configured power limit, W",
+    ),
+    (
+        "whatsminer_power_input_volts",
+        "gauge",
+        "Power supply input voltage, V",
+    ),
+    (
+        "whatsminer_power_input_amperes",
+        "gauge",
+        "Power supply input current, A",
+    ),
+    (
+        "whatsminer_power_output_volts",
+        "gauge",
+        "Power supply output voltage, V",
+    ),
+    (
+        "whatsminer_temperature_celsius",
+        "gauge",
+        "Temperature by sensor, °C",
+    ),
+    ("whatsminer_fan_speed_rpm", "gauge", "Fan speed, RPM"),
+    (
+        "whatsminer_fan_zero_speed",
+        "gauge",
+        "1 if fans are allowed to stop",
+    ),
+    (
+        "whatsminer_fan_poweroff_cool",
+        "gauge",
+        "1 if fans cool down after power off",
+    ),
+    (
+        "whatsminer_fan_temp_offset_celsius",
+        "gauge",
+        "Fan temperature offset, °C",
+    ),
+    (
+        "whatsminer_error_codes",
+        "gauge",
+        "Active error codes by category",
+    ),
+];
+
+/// Settings of [Exporter]
+#[derive(Debug, Clone)]
+pub struct ExporterConfig {
+    /// Pause between polls of the fleet
+    pub interval: Duration,
+    /// Max duration of poll of one miner, including connect
+    pub timeout: Duration,
+    /// Miners polled at the same time
+    pub parallel: usize,
+    /// Settings of connections
+    pub actor: ActorConfig,
+}
+
+impl Default for ExporterConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            parallel: 16,
+            actor: ActorConfig::default(),
+        }
+    }
+}
+
+/// One series of metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    /// Metric name from [METRICS]
+    pub name: &'static str,
+    /// Labels in addition to miner labels
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+/// Result of poll of one miner
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sample {
+    pub addr: String,
+    pub model: String,
+    pub serial: String,
+    pub hostname: String,
+    /// Did miner answer?
+    pub up: bool,
+    /// Reason, if miner didn't answer
+    pub error: Option<String>,
+    pub duration: Duration,
+    pub series: Vec<Series>,
+}
+
+impl Sample {
+    fn push(&mut self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        self.series.push(Series {
+            name,
+            labels: labels.iter().map(|(k, v)| (*k, v.to_string())).collect(),
+            value,
+        });
+    }
+
+    fn push_some(
+        &mut self,
+        name: &'static str,
+        labels: &[(&'static str, &str)],
+        value: Option<f64>,
+    ) {
+        if let Some(value) = value {
+            self.push(name, labels, value);
+        }
+    }
+
+    /// Poll miner
+    ///
+    /// [GetDeviceInfo] is required, status and settings are skipped with a warning,
+    /// if firmware or permission doesn't allow them
+    #[instrument(level = "debug", skip(actor), fields(addr = %actor.addr))]
+    pub async fn collect(actor: &Actor) -> Result<Self> {
+        let mut sample = Self {
+            addr: actor.addr.clone(),
+            up: true,
+            ..Default::default()
+        };
+
+        let info = actor
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                power: true,
+                network: true,
+                system: false,
+                salt: false,
+                error_code: true,
+            }))
+            .await?
+            .msg;
+        if let Some(network) = &info.network {
+            sample.hostname = network.hostname.clone();
+        }
+        if let Some(miner) = &info.miner {
+            sample.serial = miner.miner_sn.clone();
+            sample.model = miner
+                .model()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| miner.r#type.clone());
+            sample.push_some(
+                "whatsminer_working",
+                &[],
+                miner.is_working().map(|w| w as u8 as f64),
+            );
+        }
+        if let Some(power) = &info.power {
+            sample.push(
+                "whatsminer_power_watts",
+                &[("source", "psu")],
+                power.pin as f64,
+            );
+            sample.push("whatsminer_power_input_volts", &[], power.vin as f64);
+            sample.push("whatsminer_power_input_amperes", &[], power.iin as f64);
+            sample.push(
+                "whatsminer_power_output_volts",
+                &[],
+                power.vout_volts() as f64,
+            );
+            sample.push(
+                "whatsminer_fan_speed_rpm",
+                &[("fan", "psu")],
+                power.fanspeed as f64,
+            );
+            sample.push(
+                "whatsminer_temperature_celsius",
+                &[("sensor", "psu")],
+                power.temp0 as f64,
+            );
+        }
+        let errors = info.errors();
+        for (category, errors) in group_by_category(&errors) {
+            let category = format!("{category:?}").to_lowercase();
+            sample.push(
+                "whatsminer_error_codes",
+                &[("category", &category)],
+                errors.len() as f64,
+            );
+        }
+
+        match actor.send(&GetMinerStatus::default()).await {
+            Ok(status) => {
+                if let Some(s) = status.msg.summary {
+                    sample.push_some("whatsminer_elapsed_seconds", &[], s.elapsed);
+                    for (window, value) in [
+                        ("realtime", s.hash_realtime),
+                        ("1min", s.hash_1min),
+                        ("15min", s.hash_15min),
+                        ("average", s.hash_average),
+                    ] {
+                        sample.push_some(
+                            "whatsminer_hashrate_terahashes",
+                            &[("window", window)],
+                            value,
+                        );
+                    }
+                    sample.push_some(
+                        "whatsminer_factory_hashrate_terahashes",
+                        &[],
+                        s.factory_hash,
+                    );
+                    sample.push_some(
+                        "whatsminer_frequency_megahertz",
+                        &[("kind", "avg")],
+                        s.freq_avg,
+                    );
+                    sample.push_some(
+                        "whatsminer_frequency_megahertz",
+                        &[("kind", "target")],
+                        s.target_freq,
+                    );
+                    sample.push_some(
+                        "whatsminer_power_watts",
+                        &[("source", "miner")],
+                        s.power_realtime,
+                    );
+                    sample.push_some("whatsminer_power_limit_watts", &[], s.power_limit);
+                    for (sensor, value) in [
+                        ("environment", s.environment_temperature),
+                        ("chip_min", s.chip_temp_min),
+                        ("chip_avg", s.chip_temp_avg),
+                        ("chip_max", s.chip_temp_max),
+                    ] {
+                        sample.push_some(
+                            "whatsminer_temperature_celsius",
+                            &[("sensor", sensor)],
+                            value,
+                        );
+                    }
+                    for (board, value) in s.board_temperatures().into_iter().enumerate() {
+                        sample.push_some(
+                            "whatsminer_temperature_celsius",
+                            &[("sensor", "board"), ("board", &board.to_string())],
+                            value,
+                        );
+                    }
+                    sample.push_some("whatsminer_fan_speed_rpm", &[("fan", "in")], s.fan_speed_in);
+                    sample.push_some(
+                        "whatsminer_fan_speed_rpm",
+                        &[("fan", "out")],
+                        s.fan_speed_out,
+                    );
+                }
+            }
+            Err(e) => warn!(addr = %sample.addr, error = %e, "Can't read miner status."),
+        }
+
+        match actor.send(&GetFanSettings).await {
+            Ok(fan) => {
+                let fan = fan.msg;
+                sample.push("whatsminer_fan_zero_speed", &[], fan.fan_zero_speed as f64);
+                sample.push(
+                    "whatsminer_fan_poweroff_cool",
+                    &[],
+                    fan.fan_poweroff_cool as f64,
+                );
+                sample.push(
+                    "whatsminer_fan_temp_offset_celsius",
+                    &[],
+                    fan.fan_temp_offset as f64,
+                );
+            }
+            Err(e) => warn!(addr = %sample.addr, error = %e, "Can't read fan settings."),
+        }
+
+        // Summary has the limit on most firmwares, settings are the fallback
+        if !sample
+            .series
+            .iter()
+            .any(|s| s.name == "whatsminer_power_limit_watts")
+        {
+            match actor.send(&GetMinerSettings).await {
+                Ok(settings) => sample.push(
+                    "whatsminer_power_limit_watts",
+                    &[],
+                    settings.msg.power_limit as f64,
+                ),
+                Err(e) => warn!(addr = %sample.addr, error = %e, "Can't read miner settings."),
+            }
+        }
+        Ok(sample)
+    }
+}
+
+/// Render samples in Prometheus text format
+pub fn render(samples: &[Sample]) -> String {
+    let mut out = String::new();
+    for (name, kind, help) in METRICS {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        for sample in samples {
+            let base = [
+                ("addr", sample.addr.as_str()),
+                ("model", sample.model.as_str()),
+                ("serial", sample.serial.as_str()),
+                ("hostname", sample.hostname.as_str()),
+            ];
+            let mut line = |extra: &[(&str, String)], value: f64| {
+                let labels: Vec<String> = base
+                    .iter()
+                    .map(|(k, v)| (*k, *v))
+                    .chain(extra.iter().map(|(k, v)| (*k, v.as_str())))
+                    .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+                    .collect();
+                let _ = writeln!(out, "{name}{{{}}} {value}", labels.join(","));
+            };
+            match *name {
+                "whatsminer_up" => line(&[], sample.up as u8 as f64),
+                "whatsminer_poll_duration_seconds" => line(&[], sample.duration.as_secs_f64()),
+                _ => sample
+                    .series
+                    .iter()
+                    .filter(|s| s.name == *name)
+                    .for_each(|s| line(&s.labels, s.value)),
+            }
+        }
+    }
+    out
+}
+
+/// Escape label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Polls fleet and serves cached metrics
+///
+/// # Example
+/// ```rust,ignore
+/// use std::sync::Arc;
+/// use matroskin::credentials::MemoryProvider;
+/// use matroskin::exporter::{Exporter, ExporterConfig};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let exporter = Arc::new(Exporter::new(
+///         vec!["10.10.10.10:4433".to_string()],
+///         Arc::new(MemoryProvider::new()),
+///         ExporterConfig::default(),
+///     ));
+///     tokio::spawn(exporter.clone().run());
+///     let listener = tokio::net::TcpListener::bind("0.0.0.0:9897").await?;
+///     exporter.serve(listener).await?;
+///     Ok(())
+/// }
+/// ```
+pub struct Exporter {
+    pub targets: Vec<String>,
+    pub provider: Arc<dyn CredentialProvider>,
+    pub config: ExporterConfig,
+    /// Connections, which are reused between polls
+    actors: Mutex<HashMap<String, Actor>>,
+    /// The last sample of every miner, by address
+    cache: RwLock<BTreeMap<String, Sample>>,
+}
+
+impl std::fmt::Debug for Exporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Exporter")
+            .field("targets", &self.targets)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Exporter {
+    pub fn new(
+        targets: Vec<String>,
+        provider: Arc<dyn CredentialProvider>,
+        config: ExporterConfig,
+    ) -> Self {
+        Self {
+            targets,
+            provider,
+            config,
+            actors: Mutex::new(HashMap::new()),
+            cache: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Poll every miner once and update cache
+    #[instrument(level = "info", skip(self), fields(targets = self.targets.len()))]
+    pub async fn poll(self: &Arc<Self>) {
+        let started = Instant::now();
+        let limit = Arc::new(Semaphore::new(self.config.parallel.max(1)));
+        let mut tasks = JoinSet::new();
+        for addr in self.targets.clone() {
+            let this = self.clone();
+            let limit = limit.clone();
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                this.poll_one(&addr).await
+            });
+        }
+        let mut up = 0;
+        while let Some(sample) = tasks.join_next().await {
+            let Ok(sample) = sample else { continue };
+            up += sample.up as usize;
+            self.cache.write().await.insert(sample.addr.clone(), sample);
+        }
+        info!(up, elapsed = ?started.elapsed(), "Poll finished.");
+    }
+
+    /// Poll one miner, connection is dropped on failure and made again next time
+    async fn poll_one(&self, addr: &str) -> Sample {
+        let started = Instant::now();
+        let cached = self.actors.lock().await.remove(addr);
+        let result = timeout(self.config.timeout, async {
+            let actor = match cached {
+                Some(actor) => actor,
+                None => {
+                    Actor::with_provider(addr, self.provider.as_ref(), self.config.actor.clone())
+                        .await?
+                }
+            };
+            let sample = Sample::collect(&actor).await?;
+            Ok::<_, Error>((actor, sample))
+        })
+        .await;
+
+        let mut sample = match result {
+            Ok(Ok((actor, sample))) => {
+                self.actors.lock().await.insert(addr.to_string(), actor);
+                sample
+            }
+            failure => {
+                let error = match failure {
+                    Ok(Err(e)) => e.to_string(),
+                    _ => format!("timed out after {:?}", self.config.timeout),
+                };
+                debug!(%addr, %error, "Miner is down.");
+                let last = self.cache.read().await.get(addr).cloned();
+                let last = last.unwrap_or_default();
+                Sample {
+                    addr: addr.to_string(),
+                    model: last.model,
+                    serial: last.serial,
+                    hostname: last.hostname,
+                    error: Some(error),
+                    ..Default::default()
+                }
+            }
+        };
+        sample.duration = started.elapsed();
+        sample
+    }
+
+    /// Cached samples, ordered by address
+    pub async fn samples(&self) -> Vec<Sample> {
+        self.cache.read().await.values().cloned().collect()
+    }
+
+    /// Cached metrics in Prometheus text format, miners aren't contacted
+    pub async fn metrics(&self) -> String {
+        render(&self.samples().await)
+    }
+
+    /// Poll fleet forever, every [ExporterConfig::interval]
+    pub async fn run(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(self.config.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            self.poll().await;
+        }
+    }
+
+    /// Serve `GET /metrics` over HTTP/1.1
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        http::serve(listener, move |request| {
+            let this = self.clone();
+            async move { this.route(request).await }
+        })
+        .await
+    }
+
+    async fn route(&self, request: HttpRequest) -> HttpResponse {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/metrics") => HttpResponse {
+                status: 200,
+                content_type: "text/plain; version=0.0.4; charset=utf-8",
+                body: self.metrics().await,
+            },
+            ("GET", "/") => HttpResponse {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: r#"<html><body><a href="/metrics">Metrics</a></body></html>"#.to_string(),
+            },
+            _ => HttpResponse::text(404, "Not Found\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        account::Account,
+        actor::mock::{self, ok_answer},
+        credentials::MemoryProvider,
+        http::tests::request,
+    };
+
+    fn miner_answer(request: &serde_json::Value) -> Option<serde_json::Value> {
+        let cmd = request["cmd"].as_str()?;
+        match cmd {
+            "get.device.info" if request["param"] == "miner,error-code,network,power" => {
+                Some(ok_answer(
+                    cmd,
+                    json!({
+                        "network": {"hostname": "rack1-\"a\"", "mac": "C6:07:1A:00:00:01"},
+                        "miner": {"type": "M50S++_VK30", "working": "true", "miner-sn": "HTM1"},
+                        "power": {"pin": 3300, "vin": 230.5, "iin": 14.5, "vout": 1209, "fanspeed": 5000},
+                        "error-code": [{"110": "2025-01-01 00:00:00"}, {"111": "2025-01-01 00:00:00"}]
+                    }),
+                ))
+            }
+            "get.miner.status" => Some(ok_answer(
+                cmd,
+                json!({"summary": {"hash-realtime": 150.5, "board-temperature": [60, 61.5],
+                    "power-limit": 3600, "fan-speed-in": 4000}}),
+            )),
+            "get.fan.setting" => Some(ok_answer(
+                cmd,
+                json!({"fan-poweroff-cool": 1, "fan-zero-speed": 0, "fan-temp-offset": -2}),
+            )),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn exporter() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let addr = mock::spawn(move |request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            miner_answer(request)
+        })
+        .await;
+        let mut provider = MemoryProvider::new();
+        provider.insert("*", Account::Super, "super");
+        let exporter = Arc::new(Exporter::new(
+            vec![addr.clone(), "127.0.0.1:1".to_string()],
+            Arc::new(provider),
+            ExporterConfig {
+                timeout: Duration::from_secs(2),
+                ..Default::default()
+            },
+        ));
+        exporter.poll().await;
+        let polled = calls.load(Ordering::SeqCst);
+
+        let metrics = exporter.metrics().await;
+        let labels =
+            format!(r#"addr="{addr}",model="M50S++_VK30",serial="HTM1",hostname="rack1-\"a\"""#);
+        for line in [
+            format!("whatsminer_up{{{labels}}} 1"),
+            format!(r#"whatsminer_hashrate_terahashes{{{labels},window="realtime"}} 150.5"#),
+            format!(r#"whatsminer_power_watts{{{labels},source="psu"}} 3300"#),
+            format!("whatsminer_power_input_volts{{{labels}}} 230.5"),
+            format!("whatsminer_power_output_volts{{{labels}}} 12.09"),
+            format!(r#"whatsminer_temperature_celsius{{{labels},sensor="board",board="1"}} 61.5"#),
+            format!(r#"whatsminer_fan_speed_rpm{{{labels},fan="in"}} 4000"#),
+            format!("whatsminer_fan_temp_offset_celsius{{{labels}}} -2"),
+            format!(r#"whatsminer_error_codes{{{labels},category="fan"}} 2"#),
+            format!("whatsminer_power_limit_watts{{{labels}}} 3600"),
+            r#"whatsminer_up{addr="127.0.0.1:1",model="",serial="",hostname=""} 0"#.to_string(),
+        ] {
+            assert!(metrics.contains(&line), "{line} not in\n{metrics}");
+        }
+        assert!(metrics.contains("# TYPE whatsminer_up gauge"));
+
+        // Scrapes are served from cache
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http = listener.local_addr().unwrap().to_string();
+        tokio::spawn(exporter.clone().serve(listener));
+        for _ in 0..3 {
+            let response = request(&http, "GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n").await;
+            assert_eq!(response, (200, metrics.clone()));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), polled);
+
+        // Connection is reused by the next poll, no handshake
+        exporter.poll().await;
+        let samples = exporter.samples().await;
+        assert!(samples.iter().any(|s| s.addr == addr && s.up));
+        assert_eq!(calls.load(Ordering::SeqCst) - polled, 3);
+    }
+}
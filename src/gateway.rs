@@ -0,0 +1,515 @@
+//! Define HTTP/JSON gateway module
+//!
+//! [Gateway] lets clients, which can't speak the TCP protocol (web dashboards),
+//! read and change miners over HTTP. Connections are pooled, credentials come from
+//! [CredentialProvider], responses are the `msg` of typed responses serialized as JSON.
+//!
+//! Endpoints (`{addr}` is `host` or `host:port`):
+//!
+//! | Method | Path | Command | Body |
+//! |---|---|---|---|
+//! | GET | `/miners/{addr}/device-info` | [GetDeviceInfo] | |
+//! | GET | `/miners/{addr}/status` | [GetMinerStatus] | |
+//! | GET | `/miners/{addr}/settings/miner` | [GetMinerSettings] | |
+//! | GET | `/miners/{addr}/settings/fan` | [GetFanSettings] | |
+//! | GET | `/miners/{addr}/settings/system` | [GetSystemSetting] | |
+//! | POST | `/miners/{addr}/pools` | [SetMinerPools] | `[{"pool": "...", "worker": "...", "passwd": "..."}]` |
+//! | POST | `/miners/{addr}/fastboot` | [SetMinerFastboot] | `{"value": true}` |
+//! | POST | `/miners/{addr}/fan/poweroff-cool` | [SetFanPoweroffCool] | `{"value": 1}` |
+//! | POST | `/miners/{addr}/fan/zero-speed` | [SetFanZeroSpeed] | `{"value": 1}` |
+//! | POST | `/miners/{addr}/fan/temp-offset` | [SetFanTempOffset] | `{"value": -2}` |
+//! | POST | `/miners/{addr}/reboot` | [SetSystemReboot] | `{"confirm": true}` |
+//! | POST | `/miners/{addr}/factory-reset` | [SetSystemFactoryReset] | `{"confirm": true}` |
+//!
+//! Errors are `{"error": "...", "kind": "..."}` with status from [status_of].
+//!
+//! Gateway has no authentication of its own: bind it to a trusted interface
+//! and list reachable miners in [GatewayConfig::allow], nothing is reachable by default.
+//! Miners are addressed by IP only, hostnames are rejected with `400`.
+//!
+//! - Item: [Gateway], [GatewayConfig], [status_of]
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, sync::Mutex, time::timeout};
+use tracing::{debug, instrument};
+
+use crate::{
+    actor::{Actor, config::ActorConfig},
+    command::{
+        Command,
+        get_device_info::GetDeviceInfo,
+        get_fan_setting::GetFanSettings,
+        get_miner_setting::GetMinerSettings,
+        get_miner_status::GetMinerStatus,
+        get_system_setting::GetSystemSetting,
+        set_fan_poweroff_cool::SetFanPoweroffCool,
+        set_fan_temp_offset::SetFanTempOffset,
+        set_fan_zero_speed::SetFanZeroSpeed,
+        set_miner_fastboot::SetMinerFastboot,
+        set_miner_pools::{SetMinerPools, SetMinerPoolsParamItem},
+        set_system_factory_reset::SetSystemFactoryReset,
+        set_system_reboot::SetSystemReboot,
+    },
+    credentials::{CredentialProvider, MinerId},
+    error::{Error, ErrorKind, Result},
+    http::{self, HttpRequest, HttpResponse},
+};
+
+/// Settings of [Gateway]
+#[derive(Debug, Clone)]
+pub struct GatewayConfig {
+    /// Patterns of IPs of reachable miners (see [MinerId::matches])
+    ///
+    /// Empty list allows nothing, `*` allows any address
+    pub allow: Vec<String>,
+    /// Port for addresses without it
+    pub default_port: u16,
+    /// Max duration of one request to miner, including connect
+    pub timeout: Duration,
+    /// Settings of pooled connections
+    pub actor: ActorConfig,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            default_port: 4433,
+            timeout: Duration::from_secs(30),
+            actor: ActorConfig::default(),
+        }
+    }
+}
+
+/// HTTP status of failed miner request
+///
+/// - `403`: wrong password, token or permission; no credentials for miner
+/// - `422`: miner rejected command
+/// - `501`: api of miner doesn't support command
+/// - `502`: miner can't be reached or answered garbage
+/// - `503`: connection was closed, next request reconnects
+/// - `504`: miner didn't answer in time
+/// - `500`: anything on our side
+pub fn status_of(e: &Error) -> u16 {
+    match (e.kind(), e.root()) {
+        (_, Error::Unsupported { .. }) => 501,
+        (_, Error::NoCredentials(_)) => 403,
+        (_, Error::Io(io)) if io.kind() == std::io::ErrorKind::TimedOut => 504,
+        (ErrorKind::Auth, _) => 403,
+        (ErrorKind::Api, _) => 422,
+        (ErrorKind::Connect | ErrorKind::Handshake | ErrorKind::Protocol, _) => 502,
+        (ErrorKind::Decode | ErrorKind::Io, _) => 502,
+        (ErrorKind::Closed, _) => 503,
+        (ErrorKind::Local, _) => 500,
+    }
+}
+
+/// Error body
+fn error_response(status: u16, e: &Error) -> HttpResponse {
+    HttpResponse::json(
+        status,
+        &json!({
+            "error": e.to_string(),
+            "kind": format!("{:?}", e.kind()),
+            "cmd": e.cmd(),
+        }),
+    )
+}
+
+/// Body of simple setters
+#[derive(Debug, Deserialize)]
+struct Set<T> {
+    value: T,
+}
+
+/// Body of destructive commands
+#[derive(Debug, Deserialize)]
+struct Confirm {
+    confirm: bool,
+}
+
+/// Pool of connections, served over HTTP
+///
+/// # Example
+/// ```rust,ignore
+/// use std::sync::Arc;
+/// use matroskin::credentials::FileProvider;
+/// use matroskin::gateway::{Gateway, GatewayConfig};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let gateway = Arc::new(Gateway::new(
+///         Arc::new(FileProvider::load("miners.toml")?),
+///         GatewayConfig {
+///             allow: vec!["10.10.*".to_string()],
+///             ..Default::default()
+///         },
+///     ));
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:8080").await?;
+///     gateway.serve(listener).await?;
+///     Ok(())
+/// }
+/// ```
+pub struct Gateway {
+    pub provider: Arc<dyn CredentialProvider>,
+    pub config: GatewayConfig,
+    /// Connections by address
+    actors: Mutex<HashMap<String, Arc<Actor>>>,
+}
+
+impl std::fmt::Debug for Gateway {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gateway")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Gateway {
+    pub fn new(provider: Arc<dyn CredentialProvider>, config: GatewayConfig) -> Self {
+        Self {
+            provider,
+            config,
+            actors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Pooled connection to miner, made on first use
+    pub async fn actor(&self, addr: &str) -> Result<Arc<Actor>> {
+        if let Some(actor) = self.actors.lock().await.get(addr) {
+            return Ok(actor.clone());
+        }
+        // Lock isn't held while connecting, so slow miners don't block others
+        let actor = Arc::new(
+            Actor::with_provider(addr, self.provider.as_ref(), self.config.actor.clone()).await?,
+        );
+        Ok(self
+            .actors
+            .lock()
+            .await
+            .entry(addr.to_string())
+            .or_insert(actor)
+            .clone())
+    }
+
+    /// Drop pooled connection, the next request connects again
+    pub async fn forget(&self, addr: &str) {
+        self.actors.lock().await.remove(addr);
+    }
+
+    /// Serve endpoints over HTTP/1.1
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        http::serve(listener, move |request| {
+            let this = self.clone();
+            async move { this.route(request).await }
+        })
+        .await
+    }
+
+    /// `ip` or `ip:port` of path, port defaults to [GatewayConfig::default_port]
+    fn socket_addr(&self, addr: &str) -> Option<SocketAddr> {
+        addr.parse().ok().or_else(|| {
+            let ip = addr.parse::<IpAddr>().ok()?;
+            Some(SocketAddr::new(ip, self.config.default_port))
+        })
+    }
+
+    #[instrument(level = "debug", skip_all, fields(method = %request.method, path = %request.path))]
+    async fn route(&self, request: HttpRequest) -> HttpResponse {
+        let Some(rest) = request.path.strip_prefix("/miners/") else {
+            return HttpResponse::text(404, "Not Found\n");
+        };
+        let Some((addr, endpoint)) = rest.split_once('/') else {
+            return HttpResponse::text(404, "Not Found\n");
+        };
+        let Some(addr) = self.socket_addr(addr) else {
+            return HttpResponse::text(400, format!("{addr} is not an IP address\n"));
+        };
+        let ip = addr.ip().to_string();
+        let addr = addr.to_string();
+        let allowed = self
+            .config
+            .allow
+            .iter()
+            .any(|p| MinerId::addr(&ip).matches(p));
+
+        let get = request.method == "GET";
+        let post = request.method == "POST";
+        let body = &request.body;
+        let call = match endpoint {
+            "device-info" | "status" | "settings/miner" | "settings/fan" | "settings/system"
+                if !get =>
+            {
+                return HttpResponse::text(405, "Method Not Allowed\n");
+            }
+            "pools" | "fastboot" | "fan/poweroff-cool" | "fan/zero-speed" | "fan/temp-offset"
+            | "reboot" | "factory-reset"
+                if !post =>
+            {
+                return HttpResponse::text(405, "Method Not Allowed\n");
+            }
+            _ if !allowed => return HttpResponse::text(403, format!("{addr} is not allowed\n")),
+            "device-info" => Call::DeviceInfo,
+            "status" => Call::Status,
+            "settings/miner" => Call::MinerSettings,
+            "settings/fan" => Call::FanSettings,
+            "settings/system" => Call::SystemSettings,
+            "pools" => match parse::<Vec<SetMinerPoolsParamItem>>(body) {
+                Ok(pools) => Call::Pools(pools),
+                Err(e) => return e,
+            },
+            "fastboot" => match parse::<Set<bool>>(body) {
+                Ok(set) => Call::Fastboot(set.value),
+                Err(e) => return e,
+            },
+            "fan/poweroff-cool" => match parse::<Set<u8>>(body) {
+                Ok(set) => Call::FanPoweroffCool(set.value),
+                Err(e) => return e,
+            },
+            "fan/zero-speed" => match parse::<Set<u8>>(body) {
+                Ok(set) => Call::FanZeroSpeed(set.value),
+                Err(e) => return e,
+            },
+            "fan/temp-offset" => match parse::<Set<i64>>(body) {
+                Ok(set) => Call::FanTempOffset(set.value),
+                Err(e) => return e,
+            },
+            "reboot" | "factory-reset" => match parse::<Confirm>(body) {
+                Ok(Confirm { confirm: true }) if endpoint == "reboot" => Call::Reboot,
+                Ok(Confirm { confirm: true }) => Call::FactoryReset,
+                Ok(_) => {
+                    return HttpResponse::text(
+                        400,
+                        "Destructive command, send {\"confirm\": true}\n",
+                    );
+                }
+                Err(e) => return e,
+            },
+            _ => return HttpResponse::text(404, "Not Found\n"),
+        };
+
+        let result = match timeout(self.config.timeout, self.call(&addr, call)).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::Io(std::io::ErrorKind::TimedOut.into()).with_addr(&addr)),
+        };
+        match result {
+            Ok(value) => HttpResponse::json(200, &value),
+            Err(e) => {
+                if matches!(
+                    e.kind(),
+                    ErrorKind::Connect | ErrorKind::Handshake | ErrorKind::Io | ErrorKind::Closed
+                ) {
+                    self.forget(&addr).await;
+                }
+                debug!(%addr, error = %e, "Miner request failed.");
+                error_response(status_of(&e), &e)
+            }
+        }
+    }
+
+    /// Run command and serialize its message
+    async fn call(&self, addr: &str, call: Call) -> Result<Value> {
+        let actor = self.actor(addr).await?;
+        match call {
+            Call::DeviceInfo => msg(&actor, &GetDeviceInfo::default()).await,
+            Call::Status => msg(&actor, &GetMinerStatus::default()).await,
+            Call::MinerSettings => msg(&actor, &GetMinerSettings).await,
+            Call::FanSettings => msg(&actor, &GetFanSettings).await,
+            Call::SystemSettings => msg(&actor, &GetSystemSetting).await,
+            Call::Pools(pools) => msg(&actor, &SetMinerPools(pools)).await,
+            Call::Fastboot(on) => msg(&actor, &SetMinerFastboot(on)).await,
+            Call::FanPoweroffCool(v) => msg(&actor, &SetFanPoweroffCool(v)).await,
+            Call::FanZeroSpeed(v) => msg(&actor, &SetFanZeroSpeed(v)).await,
+            Call::FanTempOffset(v) => msg(&actor, &SetFanTempOffset(v)).await,
+            Call::Reboot => msg(&actor, &SetSystemReboot).await,
+            Call::FactoryReset => msg(&actor, &SetSystemFactoryReset).await,
+        }
+    }
+}
+
+/// Command chosen by route
+enum Call {
+    DeviceInfo,
+    Status,
+    MinerSettings,
+    FanSettings,
+    SystemSettings,
+    Pools(Vec<SetMinerPoolsParamItem>),
+    Fastboot(bool),
+    FanPoweroffCool(u8),
+    FanZeroSpeed(u8),
+    FanTempOffset(i64),
+    Reboot,
+    FactoryReset,
+}
+
+/// Send command, `msg` of response as JSON
+async fn msg<C, T>(actor: &Actor, cmd: &C) -> Result<Value>
+where
+    C: Command<Response = crate::response::Response<T>> + Send + Sync,
+    T: Serialize,
+{
+    Ok(serde_json::to_value(actor.send(cmd).await?.msg)?)
+}
+
+/// Parse JSON body, `400` on failure
+fn parse<T: DeserializeOwned>(body: &[u8]) -> std::result::Result<T, HttpResponse> {
+    serde_json::from_slice(body)
+        .map_err(|e| HttpResponse::json(400, &json!({ "error": format!("Invalid body: {e}") })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use super::*;
+    use crate::{
+        account::Account,
+        actor::mock::{self, ok_answer},
+        credentials::MemoryProvider,
+        http::tests::request,
+    };
+
+    #[test]
+    fn statuses() {
+        let unsupported = Error::Unsupported {
+            cmd: "set.fan.zero_speed".into(),
+            api: crate::capabilities::ApiVersion::new(3, 0, 1),
+        };
+        let api = Error::Api {
+            cmd: "set.fan.zero_speed".into(),
+            code: -2,
+            msg: "invalid param".into(),
+        };
+        let denied = Error::PermissionDenied {
+            account: Account::User1,
+            cmd: "set.miner.pools".into(),
+        };
+        assert_eq!(status_of(&unsupported.with_addr("a")), 501);
+        assert_eq!(status_of(&api), 422);
+        assert_eq!(status_of(&denied), 403);
+        assert_eq!(status_of(&Error::EmptyFrame), 502);
+        assert_eq!(status_of(&Error::NoCredentials("a".into())), 403);
+        assert_eq!(
+            status_of(&Error::Io(std::io::ErrorKind::TimedOut.into())),
+            504
+        );
+    }
+
+    #[tokio::test]
+    async fn gateway() {
+        let sent = Arc::new(StdMutex::new(Vec::new()));
+        let log = sent.clone();
+        let miner = mock::spawn(move |req| {
+            let cmd = req["cmd"].as_str()?;
+            log.lock().unwrap().push(req.clone());
+            match cmd {
+                "get.fan.setting" => Some(ok_answer(
+                    cmd,
+                    json!({"fan-poweroff-cool": 1, "fan-zero-speed": 0, "fan-temp-offset": -2}),
+                )),
+                "set.fan.zero_speed" if req["param"] == "7" => Some(json!({
+                    "code": -2, "when": 1_700_000_000u64, "msg": "invalid param", "desc": cmd
+                })),
+                "set.fan.zero_speed" => Some(ok_answer(cmd, json!("ok"))),
+                _ => None,
+            }
+        })
+        .await;
+        let mut provider = MemoryProvider::new();
+        provider.insert("*", Account::Super, "super");
+        let gateway = Arc::new(Gateway::new(
+            Arc::new(provider),
+            GatewayConfig {
+                allow: vec!["127.0.0.1".into()],
+                timeout: Duration::from_secs(5),
+                ..Default::default()
+            },
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http = listener.local_addr().unwrap().to_string();
+        tokio::spawn(gateway.clone().serve(listener));
+
+        let get = |path: String| format!("GET {path} HTTP/1.1\r\n\r\n");
+        let post = |path: String, body: &str| {
+            format!(
+                "POST {path} HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+        };
+
+        let (status, body) = request(&http, &get(format!("/miners/{miner}/settings/fan"))).await;
+        assert_eq!(status, 200);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["fan-temp-offset"], -2);
+
+        let (status, body) = request(
+            &http,
+            &post(format!("/miners/{miner}/fan/zero-speed"), r#"{"value": 1}"#),
+        )
+        .await;
+        assert_eq!((status, body.as_str()), (200, r#""ok""#));
+        assert_eq!(sent.lock().unwrap().last().unwrap()["param"], "1");
+
+        let (status, body) = request(
+            &http,
+            &post(format!("/miners/{miner}/fan/zero-speed"), r#"{"value": 7}"#),
+        )
+        .await;
+        assert_eq!(status, 422);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["kind"], "Api");
+        assert_eq!(body["cmd"], "set.fan.zero_speed");
+
+        // Connection is reused
+        let connects = |sent: &[Value]| {
+            sent.iter()
+                .filter(|r| r["cmd"] == "get.device.info")
+                .count()
+        };
+        assert_eq!(connects(&sent.lock().unwrap()), 1);
+
+        for (raw, expected) in [
+            (post(format!("/miners/{miner}/fan/zero-speed"), "{}"), 400),
+            (
+                post(format!("/miners/{miner}/reboot"), r#"{"confirm": false}"#),
+                400,
+            ),
+            (get(format!("/miners/{miner}/fan/zero-speed")), 405),
+            (post(format!("/miners/{miner}/settings/fan"), "{}"), 405),
+            (get(format!("/miners/{miner}/unknown")), 404),
+            (get("/miners".to_string()), 404),
+            (get("/miners/10.0.0.1/settings/fan".to_string()), 403),
+            (get("/miners/localhost/settings/fan".to_string()), 400),
+            (get("/miners/localhost:4433/settings/fan".to_string()), 400),
+            (get("/miners/127.0.0.1:1/settings/fan".to_string()), 502),
+        ] {
+            assert_eq!(request(&http, &raw).await.0, expected, "{raw}");
+        }
+        // Empty allow list reaches nothing
+        let closed = Arc::new(Gateway::new(
+            Arc::new(MemoryProvider::new()),
+            GatewayConfig::default(),
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let http = listener.local_addr().unwrap().to_string();
+        tokio::spawn(closed.serve(listener));
+        let (status, _) = request(&http, &get(format!("/miners/{miner}/settings/fan"))).await;
+        assert_eq!(status, 403);
+
+        let reboots = sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r["cmd"] == "set.system.reboot")
+            .count();
+        assert_eq!(reboots, 0);
+    }
+}
@@ -0,0 +1,265 @@
+//! Minimal HTTP/1.1 server of [exporter](crate::exporter) and [gateway](crate::gateway)
+//!
+//! One request per connection, answered with `Connection: close`.
+//! Enough for scrapers and dashboards, not a general purpose server.
+use std::{future::Future, sync::Arc, time::Duration};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tracing::{debug, info, warn};
+
+use crate::error::Result;
+
+/// Max length of request line and headers
+pub(crate) const MAX_HEAD: usize = 8 * 1024;
+/// Max length of request body
+pub(crate) const MAX_BODY: usize = 1024 * 1024;
+/// Max duration of reading whole request, headers and body
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Pause after failed accept (e.g. out of file descriptors)
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Parsed request
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct HttpRequest {
+    pub method: String,
+    /// Path without query
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// Response to write
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "gateway"), allow(dead_code))]
+    pub fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Reason phrase of status
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+/// Accept connections forever, every request goes to `handler`
+///
+/// Failed accepts are logged and retried after a pause
+pub(crate) async fn serve<H, F>(listener: TcpListener, handler: H) -> Result<()>
+where
+    H: Fn(HttpRequest) -> F + Send + Sync + 'static,
+    F: Future<Output = HttpResponse> + Send,
+{
+    info!(addr = ?listener.local_addr().ok(), "Serving HTTP.");
+    let handler = Arc::new(handler);
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(error = %e, "Can't accept HTTP connection.");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let response = match timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+                Err(_) => HttpResponse::text(408, "Request Timeout\n"),
+                Ok(Ok(Ok(request))) => {
+                    debug!(%peer, method = %request.method, path = %request.path, "HTTP request.");
+                    handler(request).await
+                }
+                Ok(Ok(Err(response))) => response,
+                Ok(Err(e)) => {
+                    debug!(%peer, error = %e, "Can't read HTTP request.");
+                    return;
+                }
+            };
+            if let Err(e) = write_response(&mut stream, &response).await {
+                debug!(%peer, error = %e, "Can't write HTTP response.");
+            }
+        });
+    }
+}
+
+/// Read request, malformed and too large requests are answered with error response
+///
+/// Caller limits duration of the whole read, so slow clients can't hold connection
+async fn read_request(
+    stream: &mut TcpStream,
+) -> std::io::Result<std::result::Result<HttpRequest, HttpResponse>> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() > MAX_HEAD {
+            return Ok(Err(HttpResponse::text(413, "Headers are too large\n")));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(Err(HttpResponse::text(400, "Incomplete request\n")));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut words = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Ok(Err(HttpResponse::text(400, "Malformed request line\n")));
+    };
+    let mut length = 0;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            match value.trim().parse::<usize>() {
+                Ok(n) if n <= MAX_BODY => length = n,
+                Ok(_) => return Ok(Err(HttpResponse::text(413, "Body is too large\n"))),
+                Err(_) => return Ok(Err(HttpResponse::text(400, "Invalid Content-Length\n"))),
+            }
+        }
+    }
+
+    let mut body = buf.split_off(head_end);
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(Err(HttpResponse::text(400, "Incomplete body\n")));
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+
+    Ok(Ok(HttpRequest {
+        method: method.to_ascii_uppercase(),
+        path: target.split('?').next().unwrap_or_default().to_string(),
+        body,
+    }))
+}
+
+async fn write_response(stream: &mut TcpStream, response: &HttpResponse) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Send raw request, returns status and body
+    pub(crate) async fn request(addr: &str, raw: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(raw.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, b)| b.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn echo() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(serve(listener, |req: HttpRequest| async move {
+            HttpResponse::text(
+                200,
+                format!(
+                    "{} {} {}",
+                    req.method,
+                    req.path,
+                    String::from_utf8_lossy(&req.body)
+                ),
+            )
+        }));
+
+        let (status, body) = request(
+            &addr,
+            "post /a/b?x=1 HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+        )
+        .await;
+        assert_eq!((status, body.as_str()), (200, "POST /a/b hello"));
+
+        let (status, _) = request(
+            &addr,
+            &format!(
+                "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY + 1
+            ),
+        )
+        .await;
+        assert_eq!(status, 413);
+        let (status, _) = request(&addr, "\r\n\r\n").await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn slow_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(serve(listener, |_| async { HttpResponse::text(200, "") }));
+
+        // Every read is fast, but headers never complete
+        let mut stream = TcpStream::connect(&addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            stream.write_all(b"X-Slow: 1\r\n").await.unwrap();
+        }
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408"), "{response}");
+    }
+}
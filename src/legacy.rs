@@ -0,0 +1,262 @@
+//! Define legacy module
+//!
+//! Client for older firmware, which speaks cgminer-like btminer API v2 on port 4028.
+//!
+//! Protocol:
+//! - one request per TCP connection, plain JSON without length prefix
+//! - miner closes connection after answer
+//! - write commands are signed with [GetToken] salts and AES encrypted ([LegacyAuth])
+//! - default admin password is `admin`
+//!
+//! Responses are mapped into [Response], and read answers convert into v3
+//! [MinerStatus](crate::command::get_miner_status::MinerStatus) ([command::miner_status]),
+//! so fleet code can treat both generations uniformly.
+//!
+//! - Item: [LegacyClient]
+//! - Commands: [command]
+pub mod command;
+pub mod crypt;
+pub mod response;
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+    time::{Instant, timeout},
+};
+use tracing::{debug, info, instrument};
+
+use crate::{
+    actor::config::DEFAULT_MAX_FRAME,
+    error::{Error, Result},
+    legacy::{
+        command::{GetToken, LegacyCommand},
+        crypt::LegacyAuth,
+    },
+    password::Password,
+    response::Response,
+};
+
+/// Default port of btminer API v2
+pub const LEGACY_PORT: u16 = 4028;
+
+/// Token lives about 30 min on miner, renew it a bit earlier
+const TOKEN_TTL: Duration = Duration::from_secs(25 * 60);
+
+/// Client of btminer API v2
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::legacy::{LegacyClient, command::{Summary, PowerOn}};
+/// use matroskin::password::Password;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = LegacyClient::new("10.10.10.10:4028", Password::Custom("admin".into()));
+///
+///     let summary = client.send(&Summary).await?;
+///     println!("Summary: {:#?}", summary);
+///
+///     client.send(&PowerOn).await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LegacyClient {
+    /// Miner address, like `10.10.10.10:4028`
+    pub addr: String,
+    /// Admin password for write commands
+    password: Password,
+    /// Timeout for a single request
+    pub timeout: Duration,
+    /// Max length of answer, longer ones fail with [Error::FrameTooLarge]
+    pub max_frame: usize,
+    /// Cached auth for write commands
+    auth: Mutex<Option<(LegacyAuth, Instant)>>,
+}
+
+impl LegacyClient {
+    /// Creates a new [LegacyClient], no connection is made
+    pub fn new(addr: impl Into<String>, password: impl Into<Password>) -> Self {
+        Self {
+            addr: addr.into(),
+            password: password.into(),
+            timeout: Duration::from_secs(10),
+            max_frame: DEFAULT_MAX_FRAME,
+            auth: Mutex::new(None),
+        }
+    }
+
+    #[instrument(level = "info", skip_all, fields(addr = %self.addr, command_name = %C::CMD_NAME))]
+    /// Execute legacy command
+    pub async fn send<C: LegacyCommand>(&self, cmd: &C) -> Result<Response<C::Response>> {
+        info!("Sending legacy command: {}.", C::CMD_NAME);
+        self.send_inner(cmd).await.map_err(|e| {
+            e.decoding(C::CMD_NAME)
+                .with_context(&self.addr, C::CMD_NAME)
+        })
+    }
+
+    async fn send_inner<C: LegacyCommand>(&self, cmd: &C) -> Result<Response<C::Response>> {
+        let mut request = cmd.params()?;
+        request.insert("cmd".into(), C::CMD_NAME.into());
+
+        if !C::WRITABLE {
+            let out = self
+                .raw(serde_json::to_string(&request)?.as_bytes())
+                .await?;
+            return C::response_from_str(&out);
+        }
+
+        let auth = self.auth().await?;
+        request.insert("token".into(), auth.sign.clone().into());
+        let data = auth.encrypt(serde_json::to_string(&request)?)?;
+        let body = serde_json::json!({ "enc": 1, "data": data });
+        let out = self.raw(serde_json::to_string(&body)?.as_bytes()).await?;
+
+        let out = match serde_json::from_str::<Value>(out.trim_end_matches('\0'))? {
+            Value::Object(obj) if obj.get("enc").is_some_and(Value::is_string) => {
+                let enc = obj["enc"].as_str().unwrap_or_default();
+                String::from_utf8(auth.decrypt(enc)?).map_err(|_| Error::DecryptionFailed)?
+            }
+            _ => out,
+        };
+        C::response_from_str(&out)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(addr = %self.addr))]
+    /// Send raw request and read whole answer
+    pub async fn raw(&self, body: &[u8]) -> Result<String> {
+        let work = async {
+            let mut stream =
+                TcpStream::connect(&self.addr)
+                    .await
+                    .map_err(|source| Error::Connect {
+                        addr: self.addr.clone(),
+                        source,
+                    })?;
+            stream.write_all(body).await?;
+            stream.flush().await?;
+            // one byte over the limit tells too long answer from exact one
+            let mut buf = Vec::new();
+            (&mut stream)
+                .take(self.max_frame as u64 + 1)
+                .read_to_end(&mut buf)
+                .await?;
+            if buf.len() > self.max_frame {
+                return Err(Error::FrameTooLarge {
+                    len: buf.len(),
+                    max: self.max_frame,
+                });
+            }
+            Ok::<_, Error>(buf)
+        };
+        let buf = timeout(self.timeout, work)
+            .await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+        debug!("Legacy response read: {} bytes.", buf.len());
+        String::from_utf8(buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+    }
+
+    /// Get cached [LegacyAuth] or request new token
+    async fn auth(&self) -> Result<LegacyAuth> {
+        let mut cached = self.auth.lock().await;
+        if let Some((auth, at)) = cached.as_ref()
+            && at.elapsed() < TOKEN_TTL
+        {
+            return Ok(auth.clone());
+        }
+        debug!("Requesting new legacy token.");
+        let out = self.raw(br#"{"cmd":"get_token"}"#).await?;
+        let token = GetToken::response_from_str(&out)?.msg;
+        let auth = LegacyAuth::new(self.password.as_ref(), &token)?;
+        *cached = Some((auth.clone(), Instant::now()));
+        Ok(auth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use crate::legacy::command::{LegacyToken, PowerOn, Summary};
+
+    use super::*;
+
+    type Answer = Box<dyn Fn(&str) -> String + Send>;
+
+    /// Serve one connection per answer, like btminer does
+    async fn serve(answers: Vec<Answer>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            for answer in answers {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap();
+                let out = answer(std::str::from_utf8(&buf[..n]).unwrap());
+                stream.write_all(out.as_bytes()).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn read() {
+        let addr = serve(vec![Box::new(|req| {
+            assert_eq!(req, r#"{"cmd":"summary"}"#);
+            r#"{"STATUS":[{"STATUS":"S","When":1,"Code":11,"Msg":"Summary","Description":""}],"SUMMARY":[{"Elapsed":5}],"id":1}"#.to_string()
+        })])
+        .await;
+        let client = LegacyClient::new(addr, Password::Custom("admin".into()));
+        let out = client.send(&Summary).await.unwrap();
+        assert_eq!(out.msg[0].elapsed, Some(5));
+    }
+
+    #[tokio::test]
+    async fn too_large() {
+        let addr = serve(vec![Box::new(|_| "x".repeat(100))]).await;
+        let mut client = LegacyClient::new(addr, Password::Custom("admin".into()));
+        client.max_frame = 64;
+        let e = client.raw(br#"{"cmd":"summary"}"#).await.unwrap_err();
+        assert!(matches!(e, Error::FrameTooLarge { max: 64, .. }));
+    }
+
+    #[tokio::test]
+    async fn write() {
+        let token = LegacyToken {
+            time: "1700000000".to_string(),
+            salt: "BQ5hoXV9".to_string(),
+            newsalt: "2ZKrYhGe".to_string(),
+            ..Default::default()
+        };
+        let auth = LegacyAuth::new("admin", &token).unwrap();
+        let addr = serve(vec![
+            Box::new(|req| {
+                assert_eq!(req, r#"{"cmd":"get_token"}"#);
+                r#"{"STATUS":"S","When":1700000000,"Code":134,"Msg":{"time":"1700000000","salt":"BQ5hoXV9","newsalt":"2ZKrYhGe"},"Description":""}"#.to_string()
+            }),
+            Box::new(move |req| {
+                let req: Value = serde_json::from_str(req).unwrap();
+                assert_eq!(req["enc"], 1);
+                let plain = auth.decrypt(req["data"].as_str().unwrap()).unwrap();
+                let plain: Value = serde_json::from_slice(&plain).unwrap();
+                assert_eq!(plain["cmd"], "power_on");
+                assert_eq!(plain["token"], "oY.9HAbOGAhz71SXtEj5A.");
+                let answer = auth
+                    .encrypt(r#"{"STATUS":"S","When":1700000001,"Code":131,"Msg":"API command OK","Description":""}"#)
+                    .unwrap();
+                serde_json::json!({ "enc": answer }).to_string()
+            }),
+        ])
+        .await;
+        let client = LegacyClient::new(addr, Password::Custom("admin".into()));
+        let out = client.send(&PowerOn).await.unwrap();
+        assert_eq!(out.code, 0);
+        assert_eq!(out.msg, "API command OK");
+    }
+}
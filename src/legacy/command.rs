@@ -0,0 +1,481 @@
+//! Trait for legacy btminer API v2 commands
+//!
+//! ## Base trait for legacy commands
+//! - [LegacyCommand]
+//!
+//! ## list of commands:
+//! - read: [Summary], [Devs], [Pools], [GetVersion], [GetToken]
+//! - write: [RestartBtminer], [PowerOff], [PowerOn], [SetPowerPct]
+//!
+//! ## v3 types
+//! Read responses convert into [MinerStatus] parts ([Summary](crate::command::get_miner_status::Summary),
+//! pools and edevs), see [miner_status]
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    command::{
+        get_miner_status::{MinerStatus, Summary as StatusSummary},
+        set_miner_pools::SetMinerPoolsParamItem,
+    },
+    drift::extra_fields,
+    error::{Error, Result},
+    legacy::response::parse,
+    response::Response,
+};
+
+/// MH/s of legacy api -> TH/s of v3 api
+const MHS_PER_THS: f64 = 1_000_000.0;
+
+/// Base trait for legacy commands
+pub trait LegacyCommand {
+    /// Message of [Response]
+    type Response: for<'a> Deserialize<'a>;
+    /// Represents the command name
+    const CMD_NAME: &'static str;
+    /// Payload key for cgminer-like responses
+    ///
+    /// - `None`: flat response, payload is `Msg`
+    const SECTION: Option<&'static str> = None;
+    /// Write command?
+    ///
+    /// Write commands are signed with token and encrypted
+    const WRITABLE: bool = false;
+
+    /// Extra request fields (next to `cmd`)
+    fn params(&self) -> Result<Map<String, Value>> {
+        Ok(Map::new())
+    }
+
+    /// Allow custom implementation of deserialize to response
+    fn response_from_str(json: &str) -> Result<Response<Self::Response>> {
+        parse(json, Self::CMD_NAME, Self::SECTION)
+    }
+}
+
+/// `summary`: miner summary
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Summary;
+
+/// [Summary] Response item
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacySummary {
+    /// Seconds since btminer start
+    #[serde(rename = "Elapsed")]
+    pub elapsed: Option<u64>,
+    /// Average hash rate (MH/s)
+    #[serde(rename = "MHS av")]
+    pub mhs_av: Option<f64>,
+    /// Hash rate for last 5 seconds (MH/s)
+    #[serde(rename = "MHS 5s")]
+    pub mhs_5s: Option<f64>,
+    /// Hash rate for last 1 minute (MH/s)
+    #[serde(rename = "MHS 1m")]
+    pub mhs_1m: Option<f64>,
+    /// Hash rate for last 15 minutes (MH/s)
+    #[serde(rename = "MHS 15m")]
+    pub mhs_15m: Option<f64>,
+    #[serde(rename = "Accepted")]
+    pub accepted: Option<u64>,
+    #[serde(rename = "Rejected")]
+    pub rejected: Option<u64>,
+    /// Temperature (°C)
+    #[serde(rename = "Temperature")]
+    pub temperature: Option<f64>,
+    /// Environment temperature (°C)
+    #[serde(rename = "Env Temp")]
+    pub env_temp: Option<f64>,
+    /// Fan speed (RPM)
+    #[serde(rename = "Fan Speed In")]
+    pub fan_speed_in: Option<u32>,
+    /// Fan speed (RPM)
+    #[serde(rename = "Fan Speed Out")]
+    pub fan_speed_out: Option<u32>,
+    /// Input power (W)
+    #[serde(rename = "Power")]
+    pub power: Option<u32>,
+    /// Power limit (W)
+    #[serde(rename = "Power Limit")]
+    pub power_limit: Option<u32>,
+    #[serde(rename = "Power Mode")]
+    pub power_mode: Option<String>,
+    /// Nominal factory hash rate (GH/s)
+    #[serde(rename = "Factory GHS")]
+    pub factory_ghs: Option<u64>,
+    /// Seconds since system start
+    #[serde(rename = "Uptime")]
+    pub uptime: Option<u64>,
+    #[serde(rename = "Btminer Fast Boot")]
+    pub fast_boot: Option<String>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(LegacySummary);
+
+impl From<LegacySummary> for StatusSummary {
+    /// Hash rates are converted to TH/s, fields without v3 counterpart go to `extra`
+    fn from(value: LegacySummary) -> Self {
+        let ths = |mhs: Option<f64>| mhs.map(|v| v / MHS_PER_THS);
+        let mut extra = value.extra;
+        for (key, v) in [
+            ("accepted", json!(value.accepted)),
+            ("rejected", json!(value.rejected)),
+            ("temperature", json!(value.temperature)),
+            ("power-mode", json!(value.power_mode)),
+            ("uptime", json!(value.uptime)),
+            ("btminer-fast-boot", json!(value.fast_boot)),
+        ] {
+            if !v.is_null() {
+                extra.insert(key.into(), v);
+            }
+        }
+        Self {
+            elapsed: value.elapsed.map(|v| v as f64),
+            hash_average: ths(value.mhs_av),
+            hash_1min: ths(value.mhs_1m),
+            hash_15min: ths(value.mhs_15m),
+            hash_realtime: ths(value.mhs_5s),
+            factory_hash: value.factory_ghs.map(|v| v as f64 / 1000.0),
+            power_realtime: value.power.map(f64::from),
+            power_limit: value.power_limit.map(f64::from),
+            environment_temperature: value.env_temp,
+            fan_speed_in: value.fan_speed_in.map(f64::from),
+            fan_speed_out: value.fan_speed_out.map(f64::from),
+            extra,
+            ..Default::default()
+        }
+    }
+}
+
+impl LegacyCommand for Summary {
+    type Response = Vec<LegacySummary>;
+    const CMD_NAME: &'static str = "summary";
+    const SECTION: Option<&'static str> = Some("SUMMARY");
+}
+
+/// `devs`: hash boards
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Devs;
+
+/// [Devs] Response item
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyDev {
+    /// Board index
+    #[serde(rename = "ASC")]
+    pub asc: Option<u32>,
+    #[serde(rename = "Slot")]
+    pub slot: Option<u32>,
+    #[serde(rename = "Enabled")]
+    pub enabled: Option<String>,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+    /// Temperature (°C)
+    #[serde(rename = "Temperature")]
+    pub temperature: Option<f64>,
+    /// Chip frequency (MHz)
+    #[serde(rename = "Chip Frequency")]
+    pub chip_frequency: Option<u32>,
+    /// Average hash rate (MH/s)
+    #[serde(rename = "MHS av")]
+    pub mhs_av: Option<f64>,
+    #[serde(rename = "Effective Chips")]
+    pub effective_chips: Option<u32>,
+    /// PCB serial number
+    #[serde(rename = "PCB SN")]
+    pub pcb_sn: Option<String>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(LegacyDev);
+
+impl From<LegacyDev> for Map<String, Value> {
+    /// Edev entry of [MinerStatus], hash rate in TH/s
+    fn from(value: LegacyDev) -> Self {
+        let mut out = value.extra;
+        for (key, v) in [
+            ("id", json!(value.asc)),
+            ("slot", json!(value.slot)),
+            ("enabled", json!(value.enabled)),
+            ("status", json!(value.status)),
+            ("temperature", json!(value.temperature)),
+            ("freq", json!(value.chip_frequency)),
+            ("hash-average", json!(value.mhs_av.map(|v| v / MHS_PER_THS))),
+            ("effective-chips", json!(value.effective_chips)),
+            ("pcb-sn", json!(value.pcb_sn)),
+        ] {
+            if !v.is_null() {
+                out.insert(key.into(), v);
+            }
+        }
+        out
+    }
+}
+
+impl LegacyCommand for Devs {
+    type Response = Vec<LegacyDev>;
+    const CMD_NAME: &'static str = "devs";
+    const SECTION: Option<&'static str> = Some("DEVS");
+}
+
+/// `pools`: pool list
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Pools;
+
+/// [Pools] Response item
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyPool {
+    /// Pool index
+    #[serde(rename = "POOL")]
+    pub pool: Option<u32>,
+    /// Stratum url
+    #[serde(rename = "URL")]
+    pub url: String,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+    #[serde(rename = "Priority")]
+    pub priority: Option<u32>,
+    /// Worker username
+    #[serde(rename = "User")]
+    pub user: String,
+    #[serde(rename = "Accepted")]
+    pub accepted: Option<u64>,
+    #[serde(rename = "Rejected")]
+    pub rejected: Option<u64>,
+    #[serde(rename = "Stratum Active")]
+    pub stratum_active: Option<bool>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+impl From<LegacyPool> for SetMinerPoolsParamItem {
+    /// Password is not reported by legacy api, so it is empty
+    fn from(value: LegacyPool) -> Self {
+        Self {
+            pool: value.url,
+            worker: value.user,
+            password: String::new(),
+        }
+    }
+}
+
+extra_fields!(LegacyPool);
+
+impl From<LegacyPool> for Map<String, Value> {
+    /// Pool entry of [MinerStatus]
+    fn from(value: LegacyPool) -> Self {
+        let mut out = value.extra;
+        out.insert("url".into(), value.url.into());
+        out.insert("user".into(), value.user.into());
+        for (key, v) in [
+            ("id", json!(value.pool)),
+            ("status", json!(value.status)),
+            ("priority", json!(value.priority)),
+            ("accepted", json!(value.accepted)),
+            ("rejected", json!(value.rejected)),
+            ("stratum-active", json!(value.stratum_active)),
+        ] {
+            if !v.is_null() {
+                out.insert(key.into(), v);
+            }
+        }
+        out
+    }
+}
+
+/// [MinerStatus] from legacy [Summary], [Pools] and [Devs] answers
+///
+/// Parts, which weren't read, are `None`, like in `get.miner.status` answer
+pub fn miner_status(
+    summary: Option<Vec<LegacySummary>>,
+    pools: Option<Vec<LegacyPool>>,
+    devs: Option<Vec<LegacyDev>>,
+) -> MinerStatus {
+    MinerStatus {
+        summary: summary.and_then(|s| s.into_iter().next()).map(Into::into),
+        pools: pools.map(|p| p.into_iter().map(Into::into).collect()),
+        edevs: devs.map(|d| d.into_iter().map(Into::into).collect()),
+        extra: Map::new(),
+    }
+}
+
+impl LegacyCommand for Pools {
+    type Response = Vec<LegacyPool>;
+    const CMD_NAME: &'static str = "pools";
+    const SECTION: Option<&'static str> = Some("POOLS");
+}
+
+/// `get_version`: api and firmware versions
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GetVersion;
+
+/// [GetVersion] Response
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyVersion {
+    /// API version, like `2.0.5`
+    pub api_ver: String,
+    /// Firmware version
+    pub fw_ver: String,
+    pub platform: Option<String>,
+    pub chip: Option<String>,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(LegacyVersion);
+
+impl LegacyCommand for GetVersion {
+    type Response = LegacyVersion;
+    const CMD_NAME: &'static str = "get_version";
+}
+
+/// `get_token`: salts for writable commands
+///
+/// Token is valid about 30 min, miner limits how often it can be requested
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GetToken;
+
+/// [GetToken] Response
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyToken {
+    /// Miner time (UNIX timestamp as string)
+    pub time: String,
+    pub salt: String,
+    pub newsalt: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(LegacyToken);
+
+impl LegacyCommand for GetToken {
+    type Response = LegacyToken;
+    const CMD_NAME: &'static str = "get_token";
+}
+
+/// `restart_btminer`: restart mining process
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RestartBtminer;
+
+impl LegacyCommand for RestartBtminer {
+    type Response = Value;
+    const CMD_NAME: &'static str = "restart_btminer";
+    const WRITABLE: bool = true;
+}
+
+/// `power_off`: stop mining
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PowerOff {
+    /// Answer before power off
+    pub respbefore: bool,
+}
+
+impl LegacyCommand for PowerOff {
+    type Response = Value;
+    const CMD_NAME: &'static str = "power_off";
+    const WRITABLE: bool = true;
+    fn params(&self) -> Result<Map<String, Value>> {
+        let mut out = Map::new();
+        out.insert("respbefore".into(), self.respbefore.to_string().into());
+        Ok(out)
+    }
+}
+
+/// `power_on`: start mining
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PowerOn;
+
+impl LegacyCommand for PowerOn {
+    type Response = Value;
+    const CMD_NAME: &'static str = "power_on";
+    const WRITABLE: bool = true;
+}
+
+/// `set_power_pct`: decrease power by percent (0..=100), larger values fail with [Error::Legacy]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SetPowerPct(pub u8);
+
+impl LegacyCommand for SetPowerPct {
+    type Response = Value;
+    const CMD_NAME: &'static str = "set_power_pct";
+    const WRITABLE: bool = true;
+    fn params(&self) -> Result<Map<String, Value>> {
+        let mut out = Map::new();
+        if self.0 > 100 {
+            return Err(Error::Legacy(format!(
+                "power percent must be 0..=100, got {}",
+                self.0
+            )));
+        }
+        out.insert("percent".into(), self.0.to_string().into());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let json = r#"{"STATUS":[{"STATUS":"S","When":1700000000,"Code":11,"Msg":"Summary","Description":"btminer"}],"SUMMARY":[{"Elapsed":3600,"MHS av":100123456.78,"Power":3300,"Fan Speed In":4500,"Btminer Fast Boot":"disable"}],"id":1}"#;
+        let out = Summary::response_from_str(json).unwrap();
+        assert_eq!(out.msg[0].elapsed, Some(3600));
+        assert_eq!(out.msg[0].power, Some(3300));
+        assert_eq!(out.msg[0].fast_boot.as_deref(), Some("disable"));
+    }
+
+    #[test]
+    fn pools() {
+        let json = r#"{"STATUS":[{"STATUS":"S","When":1700000000,"Code":7,"Msg":"1 Pool(s)","Description":""}],"POOLS":[{"POOL":0,"URL":"stratum+tcp://1.1.1.1:3333","Status":"Alive","User":"matroskin.777","Stratum Active":true}],"id":1}"#;
+        let out = Pools::response_from_str(json).unwrap();
+        let item: SetMinerPoolsParamItem = out.msg[0].clone().into();
+        assert_eq!(item.pool, "stratum+tcp://1.1.1.1:3333");
+        assert_eq!(item.worker, "matroskin.777");
+    }
+
+    #[test]
+    fn v3_status() {
+        let summary = LegacySummary {
+            mhs_5s: Some(112_500_000.0),
+            power: Some(3300),
+            uptime: Some(60),
+            ..Default::default()
+        };
+        let pool = LegacyPool {
+            url: "stratum+tcp://1.1.1.1:3333".into(),
+            user: "matroskin.777".into(),
+            status: Some("Alive".into()),
+            ..Default::default()
+        };
+        let dev = LegacyDev {
+            asc: Some(0),
+            mhs_av: Some(37_000_000.0),
+            ..Default::default()
+        };
+        let status = miner_status(Some(vec![summary]), Some(vec![pool]), Some(vec![dev]));
+        let summary = status.summary.unwrap();
+        assert_eq!(summary.hash_realtime, Some(112.5));
+        assert_eq!(summary.power_realtime, Some(3300.0));
+        assert_eq!(summary.extra["uptime"], 60);
+        let pools = status.pools.unwrap();
+        assert_eq!(pools[0]["url"], "stratum+tcp://1.1.1.1:3333");
+        assert_eq!(pools[0]["status"], "Alive");
+        assert_eq!(status.edevs.unwrap()[0]["hash-average"], 37.0);
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(
+            Value::Object(SetPowerPct(100).params().unwrap()),
+            serde_json::json!({"percent": "100"})
+        );
+        assert!(matches!(SetPowerPct(150).params(), Err(Error::Legacy(_))));
+    }
+}
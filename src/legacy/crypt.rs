@@ -0,0 +1,199 @@
+//! Define legacy crypt module
+//!
+//! btminer API v2 signs and encrypts write commands with
+//! MD5-crypt (`$1$`) hashes of the admin password and token salts.
+//!
+//! - Item: [LegacyAuth], [md5_crypt]
+//! - ApiDoc: WhatsMiner API V2.0.5, "Writable API"
+use aes::Aes256;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use base64_light::{base64_decode, base64_encode_bytes};
+#[allow(deprecated)]
+use cipher::generic_array::GenericArray;
+use md5::{Digest, Md5};
+use sha256::digest;
+use std::fmt::Display;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    error::{Error, Result},
+    legacy::command::LegacyToken,
+};
+
+/// Alphabet of crypt(3) base64 encoding
+const ITOA64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// MD5-crypt (`$1$salt$hash`) of `password`
+///
+/// Returns only `hash` part, salt is cut to 8 chars
+pub fn md5_crypt(password: &[u8], salt: &[u8]) -> String {
+    const MAGIC: &[u8] = b"$1$";
+    let salt = &salt[..salt.len().min(8)];
+
+    let alt = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut ctx = Md5::new()
+        .chain_update(password)
+        .chain_update(MAGIC)
+        .chain_update(salt);
+    for chunk in password.chunks(16) {
+        ctx.update(&alt[..chunk.len()]);
+    }
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.update([0u8]);
+        } else {
+            ctx.update(&password[..1]);
+        }
+        i >>= 1;
+    }
+    let mut fin = ctx.finalize();
+
+    for i in 0..1000 {
+        let mut ctx = Md5::new();
+        if i & 1 == 1 {
+            ctx.update(password);
+        } else {
+            ctx.update(fin);
+        }
+        if i % 3 != 0 {
+            ctx.update(salt);
+        }
+        if i % 7 != 0 {
+            ctx.update(password);
+        }
+        if i & 1 == 1 {
+            ctx.update(fin);
+        } else {
+            ctx.update(password);
+        }
+        fin = ctx.finalize();
+    }
+
+    let mut out = String::with_capacity(22);
+    let mut to64 = |mut v: u32, n: usize| {
+        for _ in 0..n {
+            out.push(ITOA64[(v & 0x3f) as usize] as char);
+            v >>= 6;
+        }
+    };
+    for (a, b, c) in [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        to64(
+            (fin[a] as u32) << 16 | (fin[b] as u32) << 8 | fin[c] as u32,
+            4,
+        );
+    }
+    to64(fin[11] as u32, 2);
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Zeroize, ZeroizeOnDrop)]
+/// Data for writable legacy commands
+///
+/// It is built from [LegacyToken] and admin password
+pub struct LegacyAuth {
+    /// Command signature
+    ///
+    /// It generated from md5crypt(md5crypt(password, salt) + time, newsalt)
+    pub(crate) sign: String,
+    /// sha256 of md5crypt(password, salt) (AES key)
+    aes_key: Vec<u8>,
+}
+
+impl Display for LegacyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LegacyAuth")
+            .field("sign", &"<hidden>")
+            .field("aes_key", &"<hidden>")
+            .finish()
+    }
+}
+
+impl LegacyAuth {
+    /// Generate auth data
+    pub fn new(password: impl AsRef<str>, token: &LegacyToken) -> Result<Self> {
+        let mut key = md5_crypt(password.as_ref().as_bytes(), token.salt.as_bytes());
+        let aes_key = hex::decode(digest(key.as_str()))?;
+        let sign = md5_crypt(
+            format!("{}{}", key, token.time).as_bytes(),
+            token.newsalt.as_bytes(),
+        );
+        key.zeroize();
+        Ok(Self { sign, aes_key })
+    }
+
+    /// Encrypt data using AES-256-ECB with zero padding
+    pub fn encrypt(&self, data: impl AsRef<[u8]>) -> Result<String> {
+        let cipher = Aes256::new_from_slice(&self.aes_key).map_err(|_| Error::EncryptionFailed)?;
+
+        let mut buffer = data.as_ref().to_vec();
+        buffer.resize(buffer.len().div_ceil(16) * 16, 0);
+        for chunk in buffer.chunks_mut(16) {
+            #[allow(deprecated)]
+            let mut block = *GenericArray::from_slice(chunk);
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block);
+        }
+        Ok(base64_encode_bytes(&buffer))
+    }
+
+    /// Decrypt data encrypted by [LegacyAuth::encrypt], trailing zeros are dropped
+    pub fn decrypt(&self, data: &str) -> Result<Vec<u8>> {
+        let cipher = Aes256::new_from_slice(&self.aes_key).map_err(|_| Error::DecryptionFailed)?;
+
+        let mut buffer = base64_decode(data.trim());
+        if buffer.is_empty() || !buffer.len().is_multiple_of(16) {
+            return Err(Error::DecryptionFailed);
+        }
+        for chunk in buffer.chunks_mut(16) {
+            #[allow(deprecated)]
+            let mut block = *GenericArray::from_slice(chunk);
+            cipher.decrypt_block(&mut block);
+            chunk.copy_from_slice(&block);
+        }
+        while buffer.last() == Some(&0) {
+            buffer.pop();
+        }
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference: `openssl passwd -1 -salt <salt> <password>`
+    #[test]
+    fn md5_crypt_known_answer() {
+        assert_eq!(md5_crypt(b"admin", b"BQ5hoXV9"), "RxmaDUO33TS7O26yeMHZ81");
+        assert_eq!(
+            md5_crypt(b"RxmaDUO33TS7O26yeMHZ811700000000", b"2ZKrYhGe"),
+            "oY.9HAbOGAhz71SXtEj5A."
+        );
+    }
+
+    #[test]
+    fn encrypt_known_answer() {
+        let token = LegacyToken {
+            time: "1700000000".to_string(),
+            salt: "BQ5hoXV9".to_string(),
+            newsalt: "2ZKrYhGe".to_string(),
+            ..Default::default()
+        };
+        let auth = LegacyAuth::new("admin", &token).unwrap();
+        assert_eq!(auth.sign, "oY.9HAbOGAhz71SXtEj5A.");
+
+        let plain = r#"{"cmd":"power_on","token":"oY.9HAbOGAhz71SXtEj5A."}"#;
+        let encrypted = auth.encrypt(plain).unwrap();
+        assert_eq!(
+            encrypted,
+            "WVz5U209eNn5F0vAECDsXu/EGD9IatBhIz1iUjhjmcvlt+O1Xfnx+em/kAuNXTRxVwj7mmVM3YXBJFrqIq3H4Q=="
+        );
+        assert_eq!(auth.decrypt(&encrypted).unwrap(), plain.as_bytes());
+    }
+}
@@ -0,0 +1,142 @@
+//! Declare legacy response module
+//!
+//! btminer API v2 answers in two shapes:
+//! - cgminer-like: `{"STATUS":[{..}],"SUMMARY":[{..}],"id":1}`
+//! - flat: `{"STATUS":"S","When":1,"Code":131,"Msg":{..},"Description":""}`
+//!
+//! Both are mapped into [Response], so fleet code can handle both generations uniformly.
+//!
+//! - Item: [LegacyStatus], [parse]
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    drift::extra_fields,
+    error::{Error, Result},
+    response::Response,
+};
+
+/// Status block of legacy response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyStatus {
+    /// Status letter:
+    /// - S: success
+    /// - I: info
+    /// - W: warning
+    /// - E: error
+    /// - F: fatal
+    #[serde(rename = "STATUS")]
+    pub status: String,
+    /// UNIX Timestamp
+    #[serde(default)]
+    pub when: u64,
+    /// Legacy message code (not the same as v3 codes)
+    #[serde(default)]
+    pub code: i64,
+    /// Message (text for cgminer-like responses)
+    #[serde(default)]
+    pub msg: Value,
+    #[serde(default)]
+    pub description: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+extra_fields!(LegacyStatus);
+
+impl LegacyStatus {
+    /// Status is `S` or `I`
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status.as_str(), "S" | "I")
+    }
+
+    /// v3-like code: 0 for success, -1 otherwise
+    pub fn code(&self) -> i8 {
+        if self.is_ok() { 0 } else { -1 }
+    }
+}
+
+/// Parse legacy response into [Response]
+///
+/// - `cmd`: called command, used as [Response::desc]
+/// - `section`: payload key for cgminer-like responses (`SUMMARY`, `DEVS`, `POOLS`),
+///   `None` takes `Msg` of flat response
+pub fn parse<T>(json: &str, cmd: &str, section: Option<&str>) -> Result<Response<T>>
+where
+    T: for<'a> Deserialize<'a>,
+{
+    let mut value: Value = serde_json::from_str(json.trim_end_matches('\0'))?;
+    let (status, msg) = match value.get_mut("STATUS") {
+        Some(Value::Array(statuses)) => {
+            let status: LegacyStatus = statuses
+                .first()
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            let msg = match section {
+                Some(section) => value.get_mut(section).map(Value::take),
+                None => Some(status.msg.clone()),
+            };
+            (status, msg)
+        }
+        Some(_) => {
+            let status: LegacyStatus = serde_json::from_value(value)?;
+            let msg = status.msg.clone();
+            (status, Some(msg))
+        }
+        None => return Err(Error::Legacy(format!("{cmd}: no STATUS in response"))),
+    };
+    if !status.is_ok() {
+        return Err(Error::Api {
+            cmd: cmd.to_string(),
+            code: status.code,
+            msg: match status.msg {
+                Value::String(s) => format!("{s} {}", status.description),
+                v => format!("{v} {}", status.description),
+            }
+            .trim_end()
+            .to_string(),
+        });
+    }
+    let msg = msg.ok_or_else(|| Error::Legacy(format!("{cmd}: no payload in response")))?;
+
+    Ok(Response {
+        code: status.code(),
+        when: status.when,
+        msg: serde_json::from_value(msg)?,
+        desc: cmd.to_string(),
+        extra: Map::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cgminer_like() {
+        let json = r#"{"STATUS":[{"STATUS":"S","When":1700000000,"Code":11,"Msg":"Summary","Description":"btminer"}],"SUMMARY":[{"Elapsed":42}],"id":1}"#;
+        let out: Response<Vec<Value>> = parse(json, "summary", Some("SUMMARY")).unwrap();
+        assert_eq!(out.code, 0);
+        assert_eq!(out.when, 1700000000);
+        assert_eq!(out.msg[0]["Elapsed"], 42);
+        assert_eq!(out.desc, "summary");
+    }
+
+    #[test]
+    fn flat() {
+        let json = r#"{"STATUS":"S","When":1700000000,"Code":131,"Msg":{"api_ver":"2.0.5"},"Description":""}"#;
+        let out: Response<Value> = parse(json, "get_version", None).unwrap();
+        assert_eq!(out.msg["api_ver"], "2.0.5");
+    }
+
+    #[test]
+    fn error() {
+        let json =
+            r#"{"STATUS":"E","When":1700000000,"Code":23,"Msg":"invalid cmd","Description":""}"#;
+        assert!(parse::<Value>(json, "nope", None).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+//! Lenient parsers for stringly typed miner fields
+//!
+//! Miner reports many values as strings: `"true"`, `"enable"`, `"3"`, `""`.
+//! Helpers here never fail, unknown values become `None`.
+
+/// `""` (and whitespace) -> `None`
+pub(crate) fn non_empty(s: &str) -> Option<&str> {
+    let s = s.trim();
+    (!s.is_empty()).then_some(s)
+}
+
+/// `true/false`, `enable/disable`, `on/off`, `yes/no`, `1/0`
+pub(crate) fn parse_bool(s: &str) -> Option<bool> {
+    match non_empty(s)?.to_ascii_lowercase().as_str() {
+        "true" | "enable" | "enabled" | "on" | "yes" | "1" => Some(true),
+        "false" | "disable" | "disabled" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Number with surrounding spaces, `""` -> `None`
+pub(crate) fn parse_num<T: std::str::FromStr>(s: &str) -> Option<T> {
+    non_empty(s)?.parse().ok()
+}
+
+/// Serde helper: number or numeric string, anything else -> `None`
+///
+/// `42`, `"42.5"` and `" 7 "` are accepted, `""` and `null` become `None`
+pub(crate) fn number<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    use serde::Deserialize;
+    Ok(match serde_json::Value::deserialize(d)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => parse_num(&s),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenient() {
+        assert_eq!(non_empty("  "), None);
+        assert_eq!(parse_bool("Enable"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+        assert_eq!(parse_num::<u8>(" 3 "), Some(3));
+        assert_eq!(parse_num::<u8>("40***"), None);
+        assert_eq!(parse_num::<u8>(""), None);
+
+        #[derive(serde::Deserialize)]
+        struct N(#[serde(deserialize_with = "number")] Option<f64>);
+        let n = |raw: &str| serde_json::from_str::<N>(raw).unwrap().0;
+        assert_eq!(n("42"), Some(42.0));
+        assert_eq!(n(r#"" 42.5 ""#), Some(42.5));
+        assert_eq!(n(r#""""#), None);
+        assert_eq!(n("null"), None);
+    }
+}
@@ -49,11 +49,42 @@
 //! }
 //! ```
 
+// Lets derive macros use `::matroskin` paths inside this crate too
+extern crate self as matroskin;
+
+#[doc(hidden)]
+pub use serde_json;
+
 pub mod account;
 pub mod actor;
+pub mod audit;
 pub mod auth_data;
+pub mod capabilities;
+pub mod clock;
 pub mod command;
+pub mod credentials;
+pub mod drift;
+pub mod dyn_command;
 pub mod error;
+pub mod error_code;
+#[cfg(feature = "exporter")]
+#[cfg_attr(docsrs, doc(cfg(feature = "exporter")))]
+pub mod exporter;
+#[cfg(feature = "gateway")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gateway")))]
+pub mod gateway;
+#[cfg(any(feature = "exporter", feature = "gateway"))]
+mod http;
+pub mod legacy;
+mod lenient;
+pub mod model;
+#[cfg(feature = "mqtt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mqtt")))]
+pub mod mqtt;
 pub mod password;
+pub mod permission;
+pub mod reconcile;
 pub mod request;
 pub mod response;
+pub mod rotation;
+pub mod snapshot;
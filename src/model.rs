@@ -0,0 +1,264 @@
+//! Define miner model module
+//!
+//! Parses [Miner::type](crate::command::get_device_info::Miner) strings like `M50S++_VK30`
+//! and [Miner::hash_board](crate::command::get_device_info::Miner) like `H84`,
+//! and looks up nominal specs for capacity planning.
+//!
+//! Nominal values are approximate vendor ratings of stock firmware,
+//! specific variants (chip bins) may differ by several percent.
+//!
+//! - Item: [MinerModel], [Nominal], [CapacityReport]
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    command::get_device_info::DeviceInfo,
+    error::{Error, Result},
+};
+
+/// Cooling type of miner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cooling {
+    Air,
+    Hydro,
+    Immersion,
+}
+
+/// Parsed miner type
+///
+/// `M50S++_VK30` -> series `M5x`, model `M50S++`, variant `VK30`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MinerModel {
+    /// Model number, like `50` for `M50S++`
+    pub number: u16,
+    /// Model suffix: ``, `S`, `S+`, `S++`
+    pub suffix: String,
+    /// Variant (chip bin), like `VK30`
+    pub variant: Option<String>,
+    /// Hash board generation, like `H84`
+    pub hash_board: Option<String>,
+}
+
+impl MinerModel {
+    /// Series, like `M5x`
+    pub fn series(&self) -> String {
+        format!("M{}x", self.number / 10)
+    }
+
+    /// Generation, like `5` for `M5x`
+    pub fn generation(&self) -> u16 {
+        self.number / 10
+    }
+
+    /// Model name, like `M50S++`
+    pub fn model(&self) -> String {
+        format!("M{}{}", self.number, self.suffix)
+    }
+
+    /// Cooling by model number:
+    /// - `x3`: hydro
+    /// - `x6`: immersion
+    /// - others: air
+    pub fn cooling(&self) -> Cooling {
+        match self.number % 10 {
+            3 => Cooling::Hydro,
+            6 => Cooling::Immersion,
+            _ => Cooling::Air,
+        }
+    }
+
+    /// Nominal specs from built-in table
+    pub fn nominal(&self) -> Option<Nominal> {
+        let model = self.model();
+        NOMINAL
+            .iter()
+            .find(|(name, ..)| *name == model)
+            .map(|&(_, hashrate, power)| Nominal {
+                hashrate_ths: hashrate,
+                power_w: power,
+            })
+    }
+
+    /// Parse type and hash board strings from [Miner](crate::command::get_device_info::Miner)
+    pub fn parse(r#type: &str, hash_board: Option<&str>) -> Result<Self> {
+        let mut model = r#type.parse::<Self>()?;
+        model.hash_board = hash_board
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+        Ok(model)
+    }
+}
+
+impl FromStr for MinerModel {
+    type Err = Error;
+
+    /// Parse `M50S++_VK30`, `M60S`, `m30s+_vf20`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidModel(s.to_string());
+        let s = s.trim();
+        let (model, variant) = match s.split_once(['_', '-']) {
+            Some((model, variant)) => (model, Some(variant)),
+            None => (s, None),
+        };
+        let rest = model.strip_prefix(['M', 'm']).ok_or_else(invalid)?;
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        let number = rest[..digits].parse().map_err(|_| invalid())?;
+        let suffix = rest[digits..].to_ascii_uppercase();
+        if !suffix.chars().all(|c| c == 'S' || c == '+') {
+            return Err(invalid());
+        }
+        Ok(Self {
+            number,
+            suffix,
+            variant: variant
+                .filter(|v| !v.is_empty())
+                .map(str::to_ascii_uppercase),
+            hash_board: None,
+        })
+    }
+}
+
+impl Display for MinerModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.model())?;
+        if let Some(variant) = &self.variant {
+            write!(f, "_{variant}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Nominal specs of model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nominal {
+    /// Hash rate (TH/s)
+    pub hashrate_ths: f32,
+    /// Power consumption (W)
+    pub power_w: u32,
+}
+
+impl Nominal {
+    /// Efficiency (J/TH)
+    pub fn efficiency(&self) -> f32 {
+        self.power_w as f32 / self.hashrate_ths
+    }
+}
+
+/// Model, nominal hash rate (TH/s), nominal power (W)
+#[rustfmt::skip]
+static NOMINAL: &[(&str, f32, u32)] = &[
+    ("M30S", 88.0, 3344),
+    ("M30S+", 100.0, 3400),
+    ("M30S++", 112.0, 3472),
+    ("M31S", 76.0, 3220),
+    ("M31S+", 80.0, 3360),
+    ("M32", 62.0, 3348),
+    ("M33S", 170.0, 6120),
+    ("M33S++", 236.0, 7080),
+    ("M50", 114.0, 3306),
+    ("M50S", 126.0, 3276),
+    ("M50S+", 140.0, 3360),
+    ("M50S++", 150.0, 3300),
+    ("M53", 226.0, 6554),
+    ("M53S", 260.0, 6760),
+    ("M56", 194.0, 5550),
+    ("M56S", 212.0, 5550),
+    ("M60", 172.0, 3422),
+    ("M60S", 186.0, 3441),
+    ("M63", 334.0, 6646),
+    ("M63S", 390.0, 7215),
+    ("M66", 280.0, 5572),
+    ("M66S", 298.0, 5513),
+];
+
+/// Actual vs nominal values of miner
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityReport {
+    pub model: MinerModel,
+    /// `None` if model is not in built-in table
+    pub nominal: Option<Nominal>,
+    /// Sum of detected board hash rates (TH/s)
+    pub hashrate_ths: Option<f32>,
+    /// Power supply input power (W)
+    pub power_w: Option<u32>,
+}
+
+impl CapacityReport {
+    /// Build report from [DeviceInfo] alone
+    ///
+    /// Needs `miner` section, `power` section is optional
+    pub fn from_device_info(info: &DeviceInfo) -> Option<Self> {
+        let miner = info.miner.as_ref()?;
+        let model = MinerModel::parse(&miner.r#type, Some(&miner.hash_board)).ok()?;
+        Some(Self {
+            nominal: model.nominal(),
+            model,
+            // boards report GH/s
+            hashrate_ths: miner
+                .detected_hash_rate_total()
+                .map(|ghs| ghs as f32 / 1000.0),
+            power_w: info.power.as_ref().map(|p| p.pin.max(0) as u32),
+        })
+    }
+
+    /// Actual / nominal hash rate, `1.0` is nominal
+    pub fn hashrate_ratio(&self) -> Option<f32> {
+        Some(self.hashrate_ths? / self.nominal?.hashrate_ths)
+    }
+
+    /// Actual / nominal power, `1.0` is nominal
+    pub fn power_ratio(&self) -> Option<f32> {
+        Some(self.power_w? as f32 / self.nominal?.power_w as f32)
+    }
+
+    /// Actual efficiency (J/TH)
+    pub fn efficiency(&self) -> Option<f32> {
+        let hashrate = self.hashrate_ths.filter(|h| *h > 0.0)?;
+        Some(self.power_w? as f32 / hashrate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::{Command, get_device_info::GetDeviceInfo};
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let model = MinerModel::parse("M50_VH84", Some("H84")).unwrap();
+        assert_eq!(model.number, 50);
+        assert_eq!(model.series(), "M5x");
+        assert_eq!(model.model(), "M50");
+        assert_eq!(model.variant.as_deref(), Some("VH84"));
+        assert_eq!(model.hash_board.as_deref(), Some("H84"));
+        assert_eq!(model.cooling(), Cooling::Air);
+        assert_eq!(model.to_string(), "M50_VH84");
+
+        let model: MinerModel = "m56s++_vk30".parse().unwrap();
+        assert_eq!(model.model(), "M56S++");
+        assert_eq!(model.cooling(), Cooling::Immersion);
+        assert_eq!(
+            "M63S".parse::<MinerModel>().unwrap().cooling(),
+            Cooling::Hydro
+        );
+
+        assert!("S19_XP".parse::<MinerModel>().is_err());
+        assert!("M50X_VH84".parse::<MinerModel>().is_err());
+    }
+
+    #[test]
+    fn capacity() {
+        let json = include_str!("../.example-response/get.device.info.json")
+            .replace("40***:41***:40***", "38000:38000:38000");
+        let info = GetDeviceInfo::response_from_str(&json).unwrap().msg;
+        let report = CapacityReport::from_device_info(&info).unwrap();
+
+        let nominal = report.nominal.unwrap();
+        assert_eq!(nominal.power_w, 3306);
+        assert_eq!(report.hashrate_ths, Some(114.0));
+        assert_eq!(report.power_w, Some(3573));
+        assert_eq!(report.hashrate_ratio(), Some(1.0));
+        assert!((report.efficiency().unwrap() - 31.34).abs() < 0.01);
+    }
+}
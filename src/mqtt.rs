@@ -0,0 +1,911 @@
+//! Define MQTT bridge module
+//!
+//! [Bridge] publishes telemetry of every miner on interval and runs commands received on MQTT.
+//!
+//! Topics (`{prefix}` is [MqttConfig::prefix], `{id}` is serial number of miner, see [miner_id]):
+//! - `{prefix}/{id}/state`: flat JSON, used by dashboards and Home Assistant
+//! - `{prefix}/{id}/power`, `/miner`, `/network`: sections of [DeviceInfo]
+//! - `{prefix}/{id}/status`: [Summary] of [GetMinerStatus]
+//! - `{prefix}/{id}/errors`: active error codes with descriptions
+//! - `{prefix}/{id}/availability`: `online` or `offline`, retained
+//! - `{prefix}/{id}/set/{command}`: commands, see [BridgeCommand]
+//! - `{prefix}/{id}/ack/{command}`: `{"ok": true, "msg": ...}` or `{"ok": false, "error": ..., "kind": ...}`
+//! - `{prefix}/bridge/availability`: `online`, `offline` is the last will
+//!
+//! With [MqttConfig::discovery] set, Home Assistant discovery configs are published once per miner,
+//! so heater-style deployments get sensors, a power percent slider and a fast boot switch.
+//!
+//! Bridge has no authentication of its own: anyone, who can publish to the broker, runs commands.
+//! Use it on a trusted network with broker ACLs, and enable commands in [MqttConfig::commands],
+//! `pools` is disabled by default, as it redirects hashrate.
+//!
+//! - Item: [Bridge], [MqttConfig], [BridgeCommand], [Message]
+//! - Discovery: <https://www.home-assistant.io/integrations/mqtt/#mqtt-discovery>
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::{Map, Value, json};
+use tokio::{
+    sync::{Mutex, RwLock, Semaphore},
+    task::JoinSet,
+    time::{MissedTickBehavior, timeout},
+};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    actor::{Actor, config::ActorConfig},
+    command::{
+        get_device_info::{DeviceInfo, GetDeviceInfo, GetDeviceInfoParam},
+        get_miner_setting::{GetMinerSettings, GetMinerSettingsResponse},
+        get_miner_status::{GetMinerStatus, Summary},
+        set_miner_fastboot::SetMinerFastboot,
+        set_miner_pools::{SetMinerPools, SetMinerPoolsParamItem},
+        set_miner_power_percent::SetMinerPowerPercent,
+    },
+    credentials::CredentialProvider,
+    error::{Error, ErrorKind, Result},
+    lenient::{parse_bool, parse_num},
+};
+
+/// Settings of [Bridge]
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// First level of topics
+    pub prefix: String,
+    /// Prefix of Home Assistant discovery topics, `None` disables discovery
+    pub discovery: Option<String>,
+    /// Pause between polls of the fleet
+    pub interval: Duration,
+    /// Max duration of poll of one miner or command, including connect
+    pub timeout: Duration,
+    /// Miners polled at the same time
+    pub parallel: usize,
+    /// Names of enabled commands (see [BridgeCommand::name]), others are rejected with error ack
+    pub commands: Vec<String>,
+    /// Settings of connections
+    pub actor: ActorConfig,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "whatsminer".to_string(),
+            discovery: Some("homeassistant".to_string()),
+            interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(10),
+            parallel: 16,
+            commands: vec!["fastboot".to_string(), "power_percent".to_string()],
+            actor: ActorConfig::default(),
+        }
+    }
+}
+
+/// Message to publish
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+    pub retain: bool,
+}
+
+impl Message {
+    fn new(topic: String, payload: impl ToString, retain: bool) -> Self {
+        Self {
+            topic,
+            payload: payload.to_string(),
+            retain,
+        }
+    }
+}
+
+/// Command received on `{prefix}/{id}/set/{name}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeCommand {
+    /// `fastboot`: `on`/`off` ([SetMinerFastboot])
+    Fastboot(bool),
+    /// `power_percent`: `0..=100` ([SetMinerPowerPercent])
+    PowerPercent(u8),
+    /// `pools`: `[{"pool": "...", "worker": "...", "passwd": "..."}]` ([SetMinerPools])
+    Pools(Vec<SetMinerPoolsParamItem>),
+}
+
+impl BridgeCommand {
+    /// Parse command from topic suffix and payload
+    pub fn parse(name: &str, payload: &[u8]) -> Result<Self> {
+        let text = String::from_utf8_lossy(payload);
+        let invalid = || Error::Mqtt(format!("invalid payload of {name}: {text}"));
+        match name {
+            "fastboot" => parse_bool(&text).map(Self::Fastboot).ok_or_else(invalid),
+            "power_percent" => parse_num::<f64>(&text)
+                .filter(|p| (0.0..=100.0).contains(p))
+                .map(|p| Self::PowerPercent(p.round() as u8))
+                .ok_or_else(invalid),
+            "pools" => Ok(Self::Pools(serde_json::from_slice(payload)?)),
+            _ => Err(Error::Mqtt(format!("unknown command: {name}"))),
+        }
+    }
+
+    /// Name in topics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fastboot(_) => "fastboot",
+            Self::PowerPercent(_) => "power_percent",
+            Self::Pools(_) => "pools",
+        }
+    }
+
+    /// Run command, `msg` of response
+    pub async fn run(self, actor: &Actor) -> Result<String> {
+        Ok(match self {
+            Self::Fastboot(on) => actor.send(&SetMinerFastboot(on)).await?.msg,
+            Self::PowerPercent(p) => actor.send(&SetMinerPowerPercent(p)).await?.msg,
+            Self::Pools(pools) => actor.send(&SetMinerPools(pools)).await?.msg,
+        })
+    }
+}
+
+/// Id of miner in topics: serial number, or address if serial is unknown
+///
+/// MQTT wildcards and separators are replaced with `_`
+pub fn miner_id(info: &DeviceInfo, addr: &str) -> String {
+    let serial = info.miner.as_ref().map(|m| m.miner_sn.trim()).unwrap_or("");
+    let raw = if serial.is_empty() { addr } else { serial };
+    raw.chars()
+        .map(|c| match c {
+            '/' | '+' | '#' | ':' | '.' | ' ' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Flat state for dashboards, `null` for values which aren't known
+pub fn state(
+    info: &DeviceInfo,
+    summary: Option<&Summary>,
+    settings: Option<&GetMinerSettingsResponse>,
+) -> Value {
+    let miner = info.miner.as_ref();
+    let power = info.power.as_ref();
+    json!({
+        "working": miner.and_then(|m| m.is_working()),
+        "hashrate": summary.and_then(|s| s.hash_realtime),
+        "power": summary
+            .and_then(|s| s.power_realtime)
+            .or(power.map(|p| p.pin as f64)),
+        "power_limit": summary.and_then(|s| s.power_limit),
+        "power_percent": settings.and_then(|s| s.power_percent),
+        "fastboot": settings.and_then(|s| s.fast_boot_enabled()),
+        "temperature": summary.and_then(|s| s.environment_temperature),
+        "chip_temperature": summary.and_then(|s| s.chip_temp_max),
+        "fan_in": summary.and_then(|s| s.fan_speed_in),
+        "fan_out": summary.and_then(|s| s.fan_speed_out),
+        "psu_voltage": power.map(|p| p.vin),
+        "psu_current": power.map(|p| p.iin),
+        "errors": info.errors().len(),
+    })
+}
+
+/// Telemetry messages of miner
+pub fn telemetry(
+    prefix: &str,
+    id: &str,
+    info: &DeviceInfo,
+    summary: Option<&Summary>,
+    settings: Option<&GetMinerSettingsResponse>,
+) -> Vec<Message> {
+    let topic = |name: &str| format!("{prefix}/{id}/{name}");
+    let mut out = vec![
+        Message::new(topic("availability"), "online", true),
+        Message::new(topic("state"), state(info, summary, settings), false),
+    ];
+    let sections = [
+        ("power", serde_json::to_value(&info.power)),
+        ("miner", serde_json::to_value(&info.miner)),
+        ("network", serde_json::to_value(&info.network)),
+        ("status", serde_json::to_value(summary)),
+    ];
+    for (name, value) in sections {
+        match value {
+            Ok(Value::Null) | Err(_) => {}
+            Ok(value) => out.push(Message::new(topic(name), value, false)),
+        }
+    }
+    let errors: Vec<Value> = info
+        .errors()
+        .iter()
+        .map(|e| {
+            let i = e.info();
+            json!({
+                "code": e.code,
+                "time": e.time,
+                "board": i.board,
+                "category": format!("{:?}", i.category).to_lowercase(),
+                "severity": format!("{:?}", i.severity).to_lowercase(),
+                "description": i.description,
+                "action": i.action,
+            })
+        })
+        .collect();
+    out.push(Message::new(topic("errors"), Value::from(errors), false));
+    out
+}
+
+/// Sensors of [state]: key, name, unit, device class
+const SENSORS: &[(&str, &str, Option<&str>, Option<&str>)] = &[
+    ("hashrate", "Hash rate", Some("TH/s"), None),
+    ("power", "Power", Some("W"), Some("power")),
+    ("power_limit", "Power limit", Some("W"), Some("power")),
+    (
+        "temperature",
+        "Temperature",
+        Some("°C"),
+        Some("temperature"),
+    ),
+    (
+        "chip_temperature",
+        "Chip temperature",
+        Some("°C"),
+        Some("temperature"),
+    ),
+    ("fan_in", "Inlet fan", Some("RPM"), None),
+    ("fan_out", "Outlet fan", Some("RPM"), None),
+    ("psu_voltage", "PSU voltage", Some("V"), Some("voltage")),
+    ("psu_current", "PSU current", Some("A"), Some("current")),
+    ("errors", "Errors", None, None),
+];
+
+/// Home Assistant discovery configs of miner, retained
+pub fn discovery(config: &MqttConfig, id: &str, info: &DeviceInfo) -> Vec<Message> {
+    let Some(discovery) = &config.discovery else {
+        return Vec::new();
+    };
+    let prefix = &config.prefix;
+    let miner = info.miner.as_ref();
+    let name = info
+        .network
+        .as_ref()
+        .map(|n| n.hostname.trim())
+        .filter(|h| !h.is_empty())
+        .unwrap_or(id);
+    let device = json!({
+        "identifiers": [format!("whatsminer_{id}")],
+        "name": name,
+        "manufacturer": "MicroBT",
+        "model": miner.and_then(|m| m.model()).map(|m| m.to_string())
+            .or(miner.map(|m| m.r#type.clone())),
+        "serial_number": miner.map(|m| m.miner_sn.clone()),
+    });
+    let state_topic = format!("{prefix}/{id}/state");
+    let entity = |component: &str, key: &str, name: &str, mut extra: Map<String, Value>| {
+        let mut payload = json!({
+            "name": name,
+            "unique_id": format!("whatsminer_{id}_{key}"),
+            "object_id": format!("whatsminer_{id}_{key}"),
+            "state_topic": state_topic,
+            "value_template": format!("{{{{ value_json.{key} }}}}"),
+            "availability_topic": format!("{prefix}/{id}/availability"),
+            "device": device,
+        });
+        if let Value::Object(map) = &mut payload {
+            map.append(&mut extra);
+        }
+        Message::new(
+            format!("{discovery}/{component}/whatsminer_{id}/{key}/config"),
+            payload,
+            true,
+        )
+    };
+
+    let mut out = Vec::with_capacity(SENSORS.len() + 3);
+    for (key, name, unit, class) in SENSORS {
+        let mut extra = Map::new();
+        extra.insert("state_class".into(), "measurement".into());
+        if let Some(unit) = unit {
+            extra.insert("unit_of_measurement".into(), (*unit).into());
+        }
+        if let Some(class) = class {
+            extra.insert("device_class".into(), (*class).into());
+        }
+        out.push(entity("sensor", key, name, extra));
+    }
+    let extra = |pairs: Value| pairs.as_object().cloned().unwrap_or_default();
+    out.push(entity(
+        "binary_sensor",
+        "working",
+        "Mining",
+        extra(json!({
+            "device_class": "running",
+            "payload_on": true,
+            "payload_off": false,
+            "value_template": "{{ value_json.working }}",
+        })),
+    ));
+    out.push(entity(
+        "number",
+        "power_percent",
+        "Power percent",
+        extra(json!({
+            "command_topic": format!("{prefix}/{id}/set/power_percent"),
+            "min": 0,
+            "max": 100,
+            "step": 1,
+            "mode": "slider",
+            "unit_of_measurement": "%",
+        })),
+    ));
+    out.push(entity(
+        "switch",
+        "fastboot",
+        "Fast boot",
+        extra(json!({
+            "command_topic": format!("{prefix}/{id}/set/fastboot"),
+            "payload_on": "on",
+            "payload_off": "off",
+            "state_on": true,
+            "state_off": false,
+        })),
+    ));
+    out
+}
+
+/// Polls fleet into MQTT and runs commands from it
+///
+/// [Bridge::poll] and [Bridge::handle] don't touch MQTT, [Bridge::run] wires them to a broker.
+///
+/// # Example
+/// ```rust,ignore
+/// use std::sync::Arc;
+/// use matroskin::credentials::FileProvider;
+/// use matroskin::mqtt::{Bridge, MqttConfig};
+/// use rumqttc::MqttOptions;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bridge = Arc::new(Bridge::new(
+///         vec!["10.10.10.10:4433".to_string()],
+///         Arc::new(FileProvider::load("miners.toml")?),
+///         MqttConfig::default(),
+///     ));
+///     bridge.run(MqttOptions::new("matroskin", "localhost", 1883)).await?;
+///     Ok(())
+/// }
+/// ```
+pub struct Bridge {
+    pub targets: Vec<String>,
+    pub provider: Arc<dyn CredentialProvider>,
+    pub config: MqttConfig,
+    /// Connections by address
+    actors: Mutex<HashMap<String, Arc<Actor>>>,
+    /// Addresses by miner id
+    ids: RwLock<HashMap<String, String>>,
+    /// Miners with published discovery configs
+    announced: Mutex<HashSet<String>>,
+}
+
+impl std::fmt::Debug for Bridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bridge")
+            .field("targets", &self.targets)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Bridge {
+    pub fn new(
+        targets: Vec<String>,
+        provider: Arc<dyn CredentialProvider>,
+        config: MqttConfig,
+    ) -> Self {
+        Self {
+            targets,
+            provider,
+            config,
+            actors: Mutex::new(HashMap::new()),
+            ids: RwLock::new(HashMap::new()),
+            announced: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Pooled connection to miner, made on first use
+    async fn actor(&self, addr: &str) -> Result<Arc<Actor>> {
+        if let Some(actor) = self.actors.lock().await.get(addr) {
+            return Ok(actor.clone());
+        }
+        let actor = Arc::new(
+            Actor::with_provider(addr, self.provider.as_ref(), self.config.actor.clone()).await?,
+        );
+        Ok(self
+            .actors
+            .lock()
+            .await
+            .entry(addr.to_string())
+            .or_insert(actor)
+            .clone())
+    }
+
+    /// Drop connection after failure of connection, the next use connects again
+    async fn forget_on(&self, addr: &str, e: &Error) {
+        if matches!(
+            e.kind(),
+            ErrorKind::Connect | ErrorKind::Handshake | ErrorKind::Io | ErrorKind::Closed
+        ) {
+            self.actors.lock().await.remove(addr);
+        }
+    }
+
+    /// Poll every miner once, messages to publish
+    #[instrument(level = "info", skip(self), fields(targets = self.targets.len()))]
+    pub async fn poll(self: &Arc<Self>) -> Vec<Message> {
+        let limit = Arc::new(Semaphore::new(self.config.parallel.max(1)));
+        let mut tasks = JoinSet::new();
+        for addr in self.targets.clone() {
+            let this = self.clone();
+            let limit = limit.clone();
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                this.poll_one(&addr).await
+            });
+        }
+        let mut out = Vec::new();
+        while let Some(messages) = tasks.join_next().await {
+            out.extend(messages.unwrap_or_default());
+        }
+        out
+    }
+
+    async fn poll_one(&self, addr: &str) -> Vec<Message> {
+        let result = timeout(self.config.timeout, async {
+            let actor = self.actor(addr).await?;
+            let info = actor
+                .send(&GetDeviceInfo(GetDeviceInfoParam {
+                    miner: true,
+                    power: true,
+                    network: true,
+                    system: false,
+                    salt: false,
+                    error_code: true,
+                }))
+                .await?
+                .msg;
+            let summary = match actor.send(&GetMinerStatus::default()).await {
+                Ok(status) => status.msg.summary,
+                Err(e) => {
+                    warn!(%addr, error = %e, "Can't read miner status.");
+                    None
+                }
+            };
+            let settings = match actor.send(&GetMinerSettings).await {
+                Ok(settings) => Some(settings.msg),
+                Err(e) => {
+                    warn!(%addr, error = %e, "Can't read miner settings.");
+                    None
+                }
+            };
+            Ok::<_, Error>((info, summary, settings))
+        })
+        .await
+        .unwrap_or_else(|_| Err(Error::Io(std::io::ErrorKind::TimedOut.into()).with_addr(addr)));
+
+        match result {
+            Ok((info, summary, settings)) => {
+                let id = miner_id(&info, addr);
+                self.ids.write().await.insert(id.clone(), addr.to_string());
+                let mut out = Vec::new();
+                if self.announced.lock().await.insert(id.clone()) {
+                    out.extend(discovery(&self.config, &id, &info));
+                }
+                out.extend(telemetry(
+                    &self.config.prefix,
+                    &id,
+                    &info,
+                    summary.as_ref(),
+                    settings.as_ref(),
+                ));
+                out
+            }
+            Err(e) => {
+                debug!(%addr, error = %e, "Miner is down.");
+                self.forget_on(addr, &e).await;
+                let ids = self.ids.read().await;
+                ids.iter()
+                    .filter(|(_, a)| *a == addr)
+                    .map(|(id, _)| {
+                        Message::new(
+                            format!("{}/{id}/availability", self.config.prefix),
+                            "offline",
+                            true,
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Run command from `{prefix}/{id}/set/{name}`, ack to publish
+    ///
+    /// `None` if topic isn't a command topic
+    #[instrument(level = "info", skip(self, payload))]
+    pub async fn handle(&self, topic: &str, payload: &[u8]) -> Option<Message> {
+        let rest = topic.strip_prefix(&self.config.prefix)?.strip_prefix('/')?;
+        let (id, name) = rest.split_once("/set/")?;
+        let result = async {
+            if !self.config.commands.iter().any(|c| c == name) {
+                return Err(Error::Mqtt(format!("command is disabled: {name}")));
+            }
+            let cmd = BridgeCommand::parse(name, payload)?;
+            let addr = self
+                .ids
+                .read()
+                .await
+                .get(id)
+                .cloned()
+                .ok_or_else(|| Error::Mqtt(format!("unknown miner: {id}")))?;
+            let result = timeout(self.config.timeout, async {
+                cmd.run(self.actor(&addr).await?.as_ref()).await
+            })
+            .await
+            .unwrap_or_else(|_| Err(Error::Io(std::io::ErrorKind::TimedOut.into())));
+            if let Err(e) = &result {
+                self.forget_on(&addr, e).await;
+            }
+            result
+        }
+        .await;
+        let ack = match result {
+            Ok(msg) => json!({ "ok": true, "msg": msg }),
+            Err(e) => {
+                warn!(%id, %name, error = %e, "Command failed.");
+                json!({ "ok": false, "error": e.to_string(), "kind": format!("{:?}", e.kind()) })
+            }
+        };
+        Some(Message::new(
+            format!("{}/{id}/ack/{name}", self.config.prefix),
+            ack,
+            false,
+        ))
+    }
+
+    /// Connect to broker, poll fleet every [MqttConfig::interval] and serve commands forever
+    ///
+    /// `{prefix}/bridge/availability` is set as last will in `options`.
+    /// Broker errors are logged and retried, the future completes only when dropped
+    pub async fn run(self: Arc<Self>, mut options: MqttOptions) -> Result<()> {
+        let bridge_topic = format!("{}/bridge/availability", self.config.prefix);
+        options.set_last_will(LastWill::new(
+            &bridge_topic,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        let (client, mut events) = AsyncClient::new(options, 256);
+
+        // Poller stops, when bridge is dropped
+        let mut poller = JoinSet::new();
+        {
+            let this = self.clone();
+            let client = client.clone();
+            poller.spawn(async move {
+                let mut ticker = tokio::time::interval(this.config.interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    ticker.tick().await;
+                    for m in this.poll().await {
+                        if let Err(e) = publish(&client, m).await {
+                            warn!(error = %e, "Can't publish telemetry.");
+                        }
+                    }
+                }
+            });
+        }
+
+        let commands = format!("{}/+/set/+", self.config.prefix);
+        loop {
+            match events.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    info!(%commands, "Connected to broker.");
+                    // Subscriptions don't survive clean sessions, renew them on every connect.
+                    // Requests wait for free space in queue, which is drained by this loop
+                    let (client, commands, bridge_topic) =
+                        (client.clone(), commands.clone(), bridge_topic.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = client.subscribe(&commands, QoS::AtLeastOnce).await {
+                            warn!(error = %e, "Can't subscribe, retrying on next connect.");
+                        }
+                        if let Err(e) = client
+                            .publish(&bridge_topic, QoS::AtLeastOnce, true, "online")
+                            .await
+                        {
+                            warn!(error = %e, "Can't publish availability.");
+                        }
+                    });
+                }
+                Ok(Event::Incoming(Packet::Publish(p))) => {
+                    let this = self.clone();
+                    let client = client.clone();
+                    tokio::spawn(async move {
+                        if let Some(ack) = this.handle(&p.topic, &p.payload).await
+                            && let Err(e) = publish(&client, ack).await
+                        {
+                            warn!(error = %e, "Can't publish ack.");
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(error = %e, "Broker connection failed, reconnecting.");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+}
+
+async fn publish(client: &AsyncClient, m: Message) -> Result<()> {
+    client
+        .publish(m.topic, QoS::AtLeastOnce, m.retain, m.payload)
+        .await
+        .map_err(|e| Error::Mqtt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use super::*;
+    use crate::{
+        account::Account,
+        actor::mock::{self, ok_answer},
+        credentials::MemoryProvider,
+    };
+
+    fn info() -> DeviceInfo {
+        serde_json::from_value(json!({
+            "network": {"hostname": "heater-1"},
+            "miner": {"type": "M50S++_VK30", "working": "true", "miner-sn": "HTM1"},
+            "power": {"pin": 3300, "vin": 230.5, "iin": 14.5},
+            "error-code": [{"110": "2025-01-01 00:00:00"}]
+        }))
+        .unwrap()
+    }
+
+    fn find<'a>(messages: &'a [Message], topic: &str) -> &'a Message {
+        messages
+            .iter()
+            .find(|m| m.topic == topic)
+            .unwrap_or_else(|| panic!("{topic} not published"))
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            BridgeCommand::parse("fastboot", b"ON").unwrap(),
+            BridgeCommand::Fastboot(true)
+        );
+        assert_eq!(
+            BridgeCommand::parse("power_percent", b"49.6").unwrap(),
+            BridgeCommand::PowerPercent(50)
+        );
+        let pools = BridgeCommand::parse(
+            "pools",
+            br#"[{"pool": "stratum+tcp://p:3333", "worker": "w.1"}]"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(&pools, BridgeCommand::Pools(p) if p[0].worker == "w.1" && p[0].password.is_empty())
+        );
+        assert_eq!(pools.name(), "pools");
+        assert!(BridgeCommand::parse("power_percent", b"101").is_err());
+        assert!(BridgeCommand::parse("fastboot", b"maybe").is_err());
+        assert!(BridgeCommand::parse("reboot", b"").is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let info = info();
+        assert_eq!(miner_id(&info, "10.0.0.1:4433"), "HTM1");
+        assert_eq!(
+            miner_id(&DeviceInfo::default(), "10.0.0.1:4433"),
+            "10_0_0_1_4433"
+        );
+
+        let out = telemetry("whatsminer", "HTM1", &info, None, None);
+        assert_eq!(find(&out, "whatsminer/HTM1/availability").payload, "online");
+        let state: Value =
+            serde_json::from_str(&find(&out, "whatsminer/HTM1/state").payload).unwrap();
+        assert_eq!(state["power"], 3300.0);
+        assert_eq!(state["working"], true);
+        assert_eq!(state["hashrate"], Value::Null);
+        let power: Value =
+            serde_json::from_str(&find(&out, "whatsminer/HTM1/power").payload).unwrap();
+        assert_eq!(power["vin"], 230.5);
+        let errors: Value =
+            serde_json::from_str(&find(&out, "whatsminer/HTM1/errors").payload).unwrap();
+        assert_eq!(errors[0]["code"], 110);
+        assert!(out.iter().all(|m| m.topic != "whatsminer/HTM1/status"));
+
+        let config = MqttConfig::default();
+        let out = discovery(&config, "HTM1", &info);
+        let hashrate: Value = serde_json::from_str(
+            &find(&out, "homeassistant/sensor/whatsminer_HTM1/hashrate/config").payload,
+        )
+        .unwrap();
+        assert_eq!(hashrate["state_topic"], "whatsminer/HTM1/state");
+        assert_eq!(hashrate["value_template"], "{{ value_json.hashrate }}");
+        assert_eq!(hashrate["device"]["name"], "heater-1");
+        assert_eq!(hashrate["device"]["model"], "M50S++_VK30");
+        let slider: Value = serde_json::from_str(
+            &find(
+                &out,
+                "homeassistant/number/whatsminer_HTM1/power_percent/config",
+            )
+            .payload,
+        )
+        .unwrap();
+        assert_eq!(slider["command_topic"], "whatsminer/HTM1/set/power_percent");
+        assert!(out.iter().all(|m| m.retain));
+        let off = MqttConfig {
+            discovery: None,
+            ..Default::default()
+        };
+        assert!(discovery(&off, "HTM1", &info).is_empty());
+    }
+
+    #[tokio::test]
+    async fn bridge() {
+        let sent = Arc::new(StdMutex::new(Vec::new()));
+        let log = sent.clone();
+        let miner = mock::spawn(move |req| {
+            let cmd = req["cmd"].as_str()?;
+            match cmd {
+                "get.device.info" if req["param"] == "miner,error-code,network,power" => {
+                    Some(ok_answer(
+                        cmd,
+                        json!({"miner": {"miner-sn": "HTM1", "working": "true"}}),
+                    ))
+                }
+                // power percent needs api 3.0.3
+                "get.device.info" => Some(mock::handshake_answer("3.0.3")),
+                "get.miner.status" => Some(ok_answer(
+                    cmd,
+                    json!({"summary": {"hash-realtime": 120.5, "power-realtime": 3100}}),
+                )),
+                "get.miner.setting" => Some(ok_answer(
+                    cmd,
+                    mock::msg::<GetMinerSettingsResponse>(
+                        json!({"power-percent": 80, "fast-boot": "enable"}),
+                    ),
+                )),
+                "set.miner.power_percent" => {
+                    log.lock().unwrap().push(req["param"].clone());
+                    Some(ok_answer(cmd, json!("ok")))
+                }
+                _ => None,
+            }
+        })
+        .await;
+        let mut provider = MemoryProvider::new();
+        provider.insert("*", Account::Super, "super");
+        let bridge = Arc::new(Bridge::new(
+            vec![miner.clone(), "127.0.0.1:1".to_string()],
+            Arc::new(provider),
+            MqttConfig {
+                timeout: Duration::from_secs(5),
+                ..Default::default()
+            },
+        ));
+
+        let out = bridge.poll().await;
+        let state: Value =
+            serde_json::from_str(&find(&out, "whatsminer/HTM1/state").payload).unwrap();
+        assert_eq!(state["hashrate"], 120.5);
+        assert_eq!(state["power"], 3100.0);
+        assert_eq!(state["power_percent"], 80);
+        assert_eq!(state["fastboot"], true);
+        find(&out, "homeassistant/sensor/whatsminer_HTM1/power/config");
+        // Discovery is published once
+        let again = bridge.poll().await;
+        assert!(again.iter().all(|m| !m.topic.starts_with("homeassistant/")));
+
+        let ack = bridge
+            .handle("whatsminer/HTM1/set/power_percent", b"50")
+            .await
+            .unwrap();
+        assert_eq!(ack.topic, "whatsminer/HTM1/ack/power_percent");
+        assert_eq!(ack.payload, r#"{"msg":"ok","ok":true}"#);
+        assert_eq!(*sent.lock().unwrap(), [json!("50")]);
+
+        let ack = bridge
+            .handle("whatsminer/HTM2/set/fastboot", b"on")
+            .await
+            .unwrap();
+        let ack: Value = serde_json::from_str(&ack.payload).unwrap();
+        assert_eq!(ack["ok"], false);
+        assert!(ack["error"].as_str().unwrap().contains("unknown miner"));
+        let ack = bridge
+            .handle(
+                "whatsminer/HTM1/set/pools",
+                br#"[{"pool": "x", "worker": "y"}]"#,
+            )
+            .await
+            .unwrap();
+        let ack: Value = serde_json::from_str(&ack.payload).unwrap();
+        assert_eq!(ack["ok"], false);
+        assert!(ack["error"].as_str().unwrap().contains("disabled"));
+        assert!(bridge.handle("whatsminer/HTM1/state", b"").await.is_none());
+        assert!(
+            bridge
+                .handle("other/HTM1/set/fastboot", b"on")
+                .await
+                .is_none()
+        );
+    }
+
+    /// Needs broker, run with `MQTT_BROKER=localhost:1883 cargo test --features mqtt -- --ignored broker`
+    #[tokio::test]
+    #[ignore]
+    async fn broker() {
+        let broker = std::env::var("MQTT_BROKER").unwrap_or_else(|_| "localhost:1883".into());
+        let (host, port) = broker.rsplit_once(':').unwrap();
+        let port: u16 = port.parse().unwrap();
+        let miner = mock::spawn(|req| {
+            let cmd = req["cmd"].as_str()?;
+            match cmd {
+                "get.device.info" if req["param"] == "miner,error-code,network,power" => {
+                    Some(ok_answer(cmd, json!({"miner": {"miner-sn": "BROKER1"}})))
+                }
+                "set.miner.fastboot" => Some(ok_answer(cmd, json!("ok"))),
+                _ => None,
+            }
+        })
+        .await;
+        let mut provider = MemoryProvider::new();
+        provider.insert("*", Account::Super, "super");
+        let bridge = Arc::new(Bridge::new(
+            vec![miner],
+            Arc::new(provider),
+            MqttConfig {
+                prefix: "matroskin-test".into(),
+                interval: Duration::from_millis(200),
+                ..Default::default()
+            },
+        ));
+        tokio::spawn(bridge.run(MqttOptions::new("matroskin-bridge", host, port)));
+
+        let (client, mut events) =
+            AsyncClient::new(MqttOptions::new("matroskin-probe", host, port), 16);
+        client
+            .subscribe("matroskin-test/BROKER1/#", QoS::AtLeastOnce)
+            .await
+            .unwrap();
+        let mut commanded = false;
+        let ack = timeout(Duration::from_secs(10), async {
+            loop {
+                if let Event::Incoming(Packet::Publish(p)) = events.poll().await.unwrap() {
+                    if p.topic.ends_with("/state") && !commanded {
+                        commanded = true;
+                        client
+                            .publish(
+                                "matroskin-test/BROKER1/set/fastboot",
+                                QoS::AtLeastOnce,
+                                false,
+                                "on",
+                            )
+                            .await
+                            .unwrap();
+                    }
+                    if p.topic.ends_with("/ack/fastboot") {
+                        break String::from_utf8_lossy(&p.payload).to_string();
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(ack.contains(r#""ok":true"#));
+    }
+}
@@ -21,7 +21,7 @@ pub enum Password {
     User1,
     User2,
     User3,
-    // TODO: it can be useless
+    /// Changed password, e.g. from [CredentialProvider](crate::credentials::CredentialProvider)
     Custom(String),
 }
 
@@ -59,3 +59,15 @@ impl From<Account> for Password {
         }
     }
 }
+
+impl From<String> for Password {
+    fn from(value: String) -> Self {
+        Self::Custom(value)
+    }
+}
+
+impl From<&str> for Password {
+    fn from(value: &str) -> Self {
+        Self::Custom(value.to_string())
+    }
+}
@@ -0,0 +1,189 @@
+//! Define permission module
+//!
+//! [Miner::permission] reports rights of accounts as bit masks.
+//! If [ActorConfig::check_permission] is on, [Actor](crate::actor::Actor) reads the mask
+//! of its account on connect, and secured commands, which aren't allowed,
+//! fail with [Error::PermissionDenied] before they are sent.
+//!
+//! - Item: [Permission], [GROUPS]
+//! - ApiDoc: <https://apidoc.whatsminer.com/#api-User-user_set_permission>
+use std::fmt::Display;
+
+use crate::{
+    account::Account,
+    command::API_COMMANDS,
+    error::{Error, Result},
+};
+#[cfg(doc)]
+use crate::{actor::config::ActorConfig, command::get_device_info::Miner};
+
+/// Bits of permission mask and command namespaces (`set.<namespace>.*`) they allow
+///
+/// ApiDoc doesn't describe the bits, miners only report masks like `super=255 user1=0`.
+/// The layout is assumed from namespaces of `set.user.permission`,
+/// so checks are opt-in ([ActorConfig::check_permission])
+pub const GROUPS: &[(u32, &str)] = &[
+    (1 << 0, "device"),
+    (1 << 1, "fan"),
+    (1 << 2, "log"),
+    (1 << 3, "miner"),
+    (1 << 4, "system"),
+    (1 << 5, "user"),
+];
+
+/// Permission mask of account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Permission(pub u32);
+
+impl Permission {
+    /// Everything is allowed, `super` always has it
+    pub const ALL: Self = Self(u32::MAX);
+
+    /// Mask of `account` from [Miner::permission]
+    ///
+    /// Accepted formats:
+    /// - `super=255,user1=8` (`:` instead of `=`, spaces and `;` as separators)
+    /// - bare mask `8` for the account, which asked
+    ///
+    /// Masks are decimal or `0x` hex. `None` if account isn't mentioned.
+    /// Accounts unknown to this version of the crate are skipped
+    pub fn parse(raw: &str, account: Account) -> Result<Option<Self>> {
+        if account == Account::Super {
+            return Ok(Some(Self::ALL));
+        }
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+        if !raw.contains(['=', ':']) {
+            return parse_mask(raw).map(|m| Some(Self(m)));
+        }
+        for pair in raw
+            .split([',', ';', ' ', '\t', '\n'])
+            .filter(|s| !s.is_empty())
+        {
+            let (name, mask) = pair
+                .split_once(['=', ':'])
+                .ok_or_else(|| Error::InvalidPermission(raw.to_string()))?;
+            if name.trim().parse::<Account>().ok() == Some(account) {
+                return parse_mask(mask).map(|m| Some(Self(m)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Can account run command?
+    ///
+    /// Unsecured commands are always allowed
+    pub fn allows(&self, cmd: &str, secured: bool) -> bool {
+        if !secured || *self == Self::ALL {
+            return true;
+        }
+        let namespace = cmd.split('.').nth(1).unwrap_or_default();
+        GROUPS
+            .iter()
+            .any(|(bit, ns)| *ns == namespace && self.0 & bit != 0)
+    }
+
+    /// Known commands, which account may use
+    ///
+    /// `set.*` commands are treated as secured, `get.*` as unsecured
+    pub fn commands(&self) -> Vec<&'static str> {
+        API_COMMANDS
+            .iter()
+            .copied()
+            .filter(|cmd| self.allows(cmd, cmd.starts_with("set.")))
+            .collect()
+    }
+
+    /// Fail with [Error::PermissionDenied] if command isn't allowed
+    pub fn check(&self, account: Account, cmd: &str, secured: bool) -> Result<()> {
+        if self.allows(cmd, secured) {
+            Ok(())
+        } else {
+            Err(Error::PermissionDenied {
+                account,
+                cmd: cmd.to_string(),
+            })
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Self::ALL {
+            return write!(f, "all");
+        }
+        let names: Vec<&str> = GROUPS
+            .iter()
+            .filter(|(bit, _)| self.0 & bit != 0)
+            .map(|(_, ns)| *ns)
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+fn parse_mask(raw: &str) -> Result<u32> {
+    let raw = raw.trim();
+    match raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => raw.parse(),
+    }
+    .map_err(|_| Error::InvalidPermission(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let raw = "super=255, user1=0x18; user2:0";
+        assert_eq!(
+            Permission::parse(raw, Account::User1).unwrap(),
+            Some(Permission(0x18))
+        );
+        assert_eq!(
+            Permission::parse(raw, Account::User2).unwrap(),
+            Some(Permission(0))
+        );
+        assert_eq!(Permission::parse(raw, Account::User3).unwrap(), None);
+        // account of newer firmware
+        assert_eq!(
+            Permission::parse("admin=255,user1=8", Account::User1).unwrap(),
+            Some(Permission(8))
+        );
+        assert_eq!(
+            Permission::parse("anything", Account::Super).unwrap(),
+            Some(Permission::ALL)
+        );
+        assert_eq!(
+            Permission::parse("8", Account::User1).unwrap(),
+            Some(Permission(8))
+        );
+        assert!(Permission::parse("user1=x", Account::User1).is_err());
+        assert_eq!(Permission::parse("root=1", Account::User1).unwrap(), None);
+    }
+
+    #[test]
+    fn allows() {
+        let p = Permission(0x18);
+        assert_eq!(p.to_string(), "miner,system");
+        assert!(p.allows("set.miner.pools", true));
+        assert!(p.allows("set.system.reboot", true));
+        assert!(!p.allows("set.user.change_passwd", true));
+        assert!(Permission(0).allows("get.device.info", false));
+
+        let e = p
+            .check(Account::User1, "set.fan.zero_speed", true)
+            .unwrap_err();
+        assert!(e.is_auth_failure());
+        assert_eq!(e.cmd(), Some("set.fan.zero_speed"));
+
+        let commands = Permission(1 << 1).commands();
+        assert!(commands.contains(&"set.fan.zero_speed"));
+        assert!(commands.contains(&"get.miner.status"));
+        assert!(!commands.contains(&"set.miner.pools"));
+        assert_eq!(Permission::ALL.commands().len(), API_COMMANDS.len());
+    }
+}
@@ -0,0 +1,887 @@
+//! Define reconcile module
+//!
+//! Fleet keeps a declarative [DesiredState] per group of miners ([Spec]).
+//! [Reconciler] reads what miner has ([CurrentState]), computes typed [Change]s
+//! and runs only the setters needed, reporting result of every field ([Report]).
+//!
+//! - Item: [DesiredState], [Spec], [CurrentState], [Change], [Reconciler], [Report]
+use std::{fmt::Display, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    actor::Actor,
+    command::{
+        get_device_custom_data::{GetDeviceCustomData, GetDeviceCustomDataResponse},
+        get_device_info::{DeviceInfo, GetDeviceInfo, GetDeviceInfoParam},
+        get_fan_setting::{GetFanSettings, GetFanSettingsResponse},
+        get_miner_setting::{GetMinerSettings, GetMinerSettingsResponse},
+        get_miner_status::{GetMinerStatus, GetMinerStatusParam},
+        get_system_setting::{GetSystemSetting, GetSystemSettingResponse},
+        set_device_custom_data::SetDeviceCustomData,
+        set_fan_poweroff_cool::SetFanPoweroffCool,
+        set_fan_temp_offset::SetFanTempOffset,
+        set_fan_zero_speed::SetFanZeroSpeed,
+        set_miner_fastboot::SetMinerFastboot,
+        set_miner_pools::{SetMinerPools, SetMinerPoolsParamItem},
+        set_miner_power_limit::SetMinerPowerLimit,
+        set_miner_power_mode::{PowerMode, SetMinerPowerMode},
+        set_miner_power_percent::SetMinerPowerPercent,
+        set_system_hostname::SetSystemHostname,
+        set_system_ntp_server::SetSystemNtpServer,
+        set_system_timezone::SetSystemTimezone,
+    },
+    credentials::MinerId,
+    error::{Error, Result},
+};
+
+/// What miner should look like, `None` fields are left as they are
+///
+/// `hostname` is a template, see [render_hostname]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesiredState {
+    /// Pools in priority order, passwords aren't compared (miner doesn't report them)
+    pub pools: Option<Vec<SetMinerPoolsParamItem>>,
+    pub power_mode: Option<PowerMode>,
+    /// Watts
+    pub power_limit: Option<u32>,
+    /// Share of power limit, `0..=100`, since 3.0.3v
+    pub power_percent: Option<u8>,
+    pub fast_boot: Option<bool>,
+    pub fan_poweroff_cool: Option<u8>,
+    pub fan_zero_speed: Option<u8>,
+    pub fan_temp_offset: Option<i64>,
+    pub ntp_servers: Option<Vec<String>>,
+    /// POSIX timezone, like `CST-8`
+    pub timezone: Option<String>,
+    /// Zone name, like `Asia/Shanghai`
+    pub zonename: Option<String>,
+    /// Template with `{serial}`, `{mac}`, `{ip}` and `{model}`
+    pub hostname: Option<String>,
+    pub custom_data: Option<GetDeviceCustomDataResponse>,
+}
+
+impl DesiredState {
+    /// Fields set in `other` override fields of `self`
+    pub fn merge(&mut self, other: &DesiredState) {
+        fn take<T: Clone>(to: &mut Option<T>, from: &Option<T>) {
+            if from.is_some() {
+                to.clone_from(from);
+            }
+        }
+        take(&mut self.pools, &other.pools);
+        take(&mut self.power_mode, &other.power_mode);
+        take(&mut self.power_limit, &other.power_limit);
+        take(&mut self.power_percent, &other.power_percent);
+        take(&mut self.fast_boot, &other.fast_boot);
+        take(&mut self.fan_poweroff_cool, &other.fan_poweroff_cool);
+        take(&mut self.fan_zero_speed, &other.fan_zero_speed);
+        take(&mut self.fan_temp_offset, &other.fan_temp_offset);
+        take(&mut self.ntp_servers, &other.ntp_servers);
+        take(&mut self.timezone, &other.timezone);
+        take(&mut self.zonename, &other.zonename);
+        take(&mut self.hostname, &other.hostname);
+        take(&mut self.custom_data, &other.custom_data);
+    }
+}
+
+/// Desired state of miner groups, from TOML or JSON file
+///
+/// Every group, which matches miner ([MinerId::matches]), is merged in file order,
+/// so later groups override earlier ones:
+/// ```toml
+/// [[group]]
+/// match = "*"
+/// ntp_servers = ["pool.ntp.org"]
+/// hostname = "wm-{serial}"
+///
+/// [[group]]
+/// match = "10.10.1.*"
+/// power_mode = "low"
+/// power_limit = 3000
+/// pools = [{ pool = "stratum+tcp://pool:3333", worker = "farm.rack1" }]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spec {
+    #[serde(default, rename = "group")]
+    pub groups: Vec<Group>,
+}
+
+/// Group of [Spec]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    #[serde(rename = "match")]
+    pub pattern: String,
+    #[serde(flatten)]
+    pub state: DesiredState,
+}
+
+impl Spec {
+    /// Read spec file, `.json` is parsed as JSON, anything else as TOML
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)?;
+        if path.extension().is_some_and(|e| e == "json") {
+            Self::parse_json(&raw)
+        } else {
+            Self::parse_toml(&raw)
+        }
+    }
+
+    pub fn parse_toml(raw: &str) -> Result<Self> {
+        toml::from_str(raw).map_err(|e| Error::Spec(e.message().to_string()))
+    }
+
+    pub fn parse_json(raw: &str) -> Result<Self> {
+        serde_json::from_str(raw).map_err(|e| Error::Spec(e.to_string()))
+    }
+
+    /// Merged state of matching groups, `None` if no group matches
+    pub fn desired(&self, id: &MinerId) -> Option<DesiredState> {
+        let mut out: Option<DesiredState> = None;
+        for group in self.groups.iter().filter(|g| id.matches(&g.pattern)) {
+            out.get_or_insert_default().merge(&group.state);
+        }
+        out
+    }
+}
+
+/// Render hostname template
+///
+/// - `{serial}`: miner serial number
+/// - `{mac}`: MAC address without separators
+/// - `{ip}`: IP address with dots replaced by `-`
+/// - `{model}`: miner type
+///
+/// Characters, which aren't allowed in hostname, are replaced by `-` in substituted values
+pub fn render_hostname(template: &str, info: &DeviceInfo) -> String {
+    fn clean(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect()
+    }
+    let (serial, model) = info
+        .miner
+        .as_ref()
+        .map_or(("", ""), |m| (m.miner_sn.as_str(), m.r#type.as_str()));
+    let (ip, mac) = info
+        .network
+        .as_ref()
+        .map_or(("", ""), |n| (n.ip.as_str(), n.mac.as_str()));
+    template
+        .replace("{serial}", &clean(serial))
+        .replace("{mac}", &mac.replace([':', '-'], "").to_lowercase())
+        .replace("{ip}", &clean(ip))
+        .replace("{model}", &clean(model))
+}
+
+/// Pool as reported by `get.miner.status`
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pool {
+    pub url: String,
+    pub worker: String,
+}
+
+impl Pool {
+    /// Keys are matched case-insensitively, `url`/`pool` and `user`/`worker`/`account`
+    pub(crate) fn from_map(map: &Map<String, Value>) -> Self {
+        let find = |keys: &[&str]| {
+            map.iter()
+                .find(|(k, _)| keys.iter().any(|key| k.eq_ignore_ascii_case(key)))
+                .and_then(|(_, v)| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        Self {
+            url: find(&["url", "pool"]),
+            worker: find(&["user", "worker", "account"]),
+        }
+    }
+}
+
+/// What miner has, read by `get.*` commands
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CurrentState {
+    pub info: DeviceInfo,
+    pub miner: GetMinerSettingsResponse,
+    pub fan: GetFanSettingsResponse,
+    pub system: GetSystemSettingResponse,
+    /// `None` if miner didn't report pools
+    pub pools: Option<Vec<Pool>>,
+    /// `None` if miner didn't report custom data
+    pub custom_data: Option<GetDeviceCustomDataResponse>,
+}
+
+impl CurrentState {
+    pub async fn read(actor: &Actor) -> Result<Self> {
+        let info = actor
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                power: false,
+                network: true,
+                system: false,
+                salt: false,
+                error_code: false,
+            }))
+            .await?
+            .msg;
+        let miner = actor.send(&GetMinerSettings).await?.msg;
+        let fan = actor.send(&GetFanSettings).await?.msg;
+        let system = actor.send(&GetSystemSetting).await?.msg;
+        let pools = match actor
+            .send(&GetMinerStatus(GetMinerStatusParam {
+                summary: false,
+                pools: true,
+                edevs: false,
+            }))
+            .await
+        {
+            Ok(status) => status
+                .msg
+                .pools
+                .map(|pools| pools.iter().map(Pool::from_map).collect()),
+            Err(e) => {
+                warn!(addr = %actor.addr, error = %e, "Can't read pools.");
+                None
+            }
+        };
+        let custom_data = match actor.send(&GetDeviceCustomData).await {
+            Ok(data) => Some(data.msg),
+            Err(e) => {
+                warn!(addr = %actor.addr, error = %e, "Can't read custom data.");
+                None
+            }
+        };
+        Ok(Self {
+            info,
+            miner,
+            fan,
+            system,
+            pools,
+            custom_data,
+        })
+    }
+}
+
+/// Difference of one field, with current and desired values
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// `current` is `None` if miner didn't report pools
+    Pools {
+        current: Option<Vec<Pool>>,
+        desired: Vec<SetMinerPoolsParamItem>,
+    },
+    PowerMode {
+        current: String,
+        desired: PowerMode,
+    },
+    PowerLimit {
+        current: i64,
+        desired: u32,
+    },
+    /// `current` is `None` if miner didn't report power percent
+    PowerPercent {
+        current: Option<i64>,
+        desired: u8,
+    },
+    FastBoot {
+        current: String,
+        desired: bool,
+    },
+    FanPoweroffCool {
+        current: u8,
+        desired: u8,
+    },
+    FanZeroSpeed {
+        current: i64,
+        desired: u8,
+    },
+    FanTempOffset {
+        current: i64,
+        desired: i64,
+    },
+    NtpServers {
+        current: Vec<String>,
+        desired: Vec<String>,
+    },
+    Timezone {
+        current: SetSystemTimezone,
+        desired: SetSystemTimezone,
+    },
+    Hostname {
+        current: String,
+        desired: String,
+    },
+    /// `current` is `None` if miner didn't report custom data
+    CustomData {
+        current: Option<Box<GetDeviceCustomDataResponse>>,
+        desired: Box<GetDeviceCustomDataResponse>,
+    },
+}
+
+impl Change {
+    /// Name of field, as in [DesiredState]
+    pub fn field(&self) -> &'static str {
+        match self {
+            Self::Pools { .. } => "pools",
+            Self::PowerMode { .. } => "power_mode",
+            Self::PowerLimit { .. } => "power_limit",
+            Self::PowerPercent { .. } => "power_percent",
+            Self::FastBoot { .. } => "fast_boot",
+            Self::FanPoweroffCool { .. } => "fan_poweroff_cool",
+            Self::FanZeroSpeed { .. } => "fan_zero_speed",
+            Self::FanTempOffset { .. } => "fan_temp_offset",
+            Self::NtpServers { .. } => "ntp_servers",
+            Self::Timezone { .. } => "timezone",
+            Self::Hostname { .. } => "hostname",
+            Self::CustomData { .. } => "custom_data",
+        }
+    }
+
+    /// Current and desired values for humans, pool passwords are never shown
+    pub fn values(&self) -> (String, String) {
+        fn pools<'a>(pools: impl Iterator<Item = (&'a str, &'a str)>) -> String {
+            let pools: Vec<_> = pools
+                .map(|(url, worker)| format!("{url} {worker}"))
+                .collect();
+            format!("[{}]", pools.join(", "))
+        }
+        fn json(data: &GetDeviceCustomDataResponse) -> String {
+            serde_json::to_string(data).unwrap_or_default()
+        }
+        fn tz(tz: &SetSystemTimezone) -> String {
+            format!("{} ({})", tz.zonename, tz.timezone)
+        }
+        match self {
+            Self::Pools { current, desired } => (
+                current.as_ref().map_or("?".to_string(), |c| {
+                    pools(c.iter().map(|p| (p.url.as_str(), p.worker.as_str())))
+                }),
+                pools(desired.iter().map(|p| (p.pool.as_str(), p.worker.as_str()))),
+            ),
+            Self::PowerMode { current, desired } => (current.clone(), desired.to_string()),
+            Self::PowerLimit { current, desired } => (current.to_string(), desired.to_string()),
+            Self::PowerPercent { current, desired } => (
+                current.map_or("?".to_string(), |c| c.to_string()),
+                desired.to_string(),
+            ),
+            Self::FastBoot { current, desired } => (
+                current.clone(),
+                if *desired { "enable" } else { "disable" }.to_string(),
+            ),
+            Self::FanPoweroffCool { current, desired } => {
+                (current.to_string(), desired.to_string())
+            }
+            Self::FanZeroSpeed { current, desired } => (current.to_string(), desired.to_string()),
+            Self::FanTempOffset { current, desired } => (current.to_string(), desired.to_string()),
+            Self::NtpServers { current, desired } => (current.join(","), desired.join(",")),
+            Self::Timezone { current, desired } => (tz(current), tz(desired)),
+            Self::Hostname { current, desired } => (current.clone(), desired.clone()),
+            Self::CustomData { current, desired } => (
+                current.as_deref().map_or("?".to_string(), json),
+                json(desired),
+            ),
+        }
+    }
+
+    /// Run the setter of field
+    pub async fn apply(&self, actor: &Actor) -> Result<()> {
+        match self {
+            Self::Pools { desired, .. } => actor.send(&SetMinerPools(desired.clone())).await?,
+            Self::PowerMode { desired, .. } => actor.send(&SetMinerPowerMode(*desired)).await?,
+            Self::PowerLimit { desired, .. } => actor.send(&SetMinerPowerLimit(*desired)).await?,
+            Self::PowerPercent { desired, .. } => {
+                actor.send(&SetMinerPowerPercent(*desired)).await?
+            }
+            Self::FastBoot { desired, .. } => actor.send(&SetMinerFastboot(*desired)).await?,
+            Self::FanPoweroffCool { desired, .. } => {
+                actor.send(&SetFanPoweroffCool(*desired)).await?
+            }
+            Self::FanZeroSpeed { desired, .. } => actor.send(&SetFanZeroSpeed(*desired)).await?,
+            Self::FanTempOffset { desired, .. } => actor.send(&SetFanTempOffset(*desired)).await?,
+            Self::NtpServers { desired, .. } => {
+                actor.send(&SetSystemNtpServer(desired.clone())).await?
+            }
+            Self::Timezone { desired, .. } => actor.send(desired).await?,
+            Self::Hostname { desired, .. } => {
+                actor.send(&SetSystemHostname(desired.clone())).await?
+            }
+            Self::CustomData { desired, .. } => {
+                actor
+                    .send(&SetDeviceCustomData((**desired).clone()))
+                    .await?
+            }
+        };
+        Ok(())
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (current, desired) = self.values();
+        write!(f, "{}: {current} -> {desired}", self.field())
+    }
+}
+
+/// Changes needed to bring `current` to `desired`
+///
+/// Hostname goes last, miner may restart networking after it
+pub fn diff(current: &CurrentState, desired: &DesiredState) -> Vec<Change> {
+    let mut out = Vec::new();
+    if let Some(pools) = &desired.pools {
+        let same = current.pools.as_ref().is_some_and(|c| {
+            c.len() == pools.len()
+                && c.iter()
+                    .zip(pools)
+                    .all(|(c, d)| c.url.trim() == d.pool.trim() && c.worker == d.worker)
+        });
+        if !same {
+            out.push(Change::Pools {
+                current: current.pools.clone(),
+                desired: pools.clone(),
+            });
+        }
+    }
+    if let Some(mode) = desired.power_mode
+        && current.miner.power_mode.parse::<PowerMode>().ok() != Some(mode)
+    {
+        out.push(Change::PowerMode {
+            current: current.miner.power_mode.clone(),
+            desired: mode,
+        });
+    }
+    if let Some(limit) = desired.power_limit
+        && current.miner.power_limit != i64::from(limit)
+    {
+        out.push(Change::PowerLimit {
+            current: current.miner.power_limit,
+            desired: limit,
+        });
+    }
+    if let Some(percent) = desired.power_percent
+        && current.miner.power_percent != Some(i64::from(percent))
+    {
+        out.push(Change::PowerPercent {
+            current: current.miner.power_percent,
+            desired: percent,
+        });
+    }
+    if let Some(on) = desired.fast_boot
+        && current.miner.fast_boot_enabled() != Some(on)
+    {
+        out.push(Change::FastBoot {
+            current: current.miner.fast_boot.clone(),
+            desired: on,
+        });
+    }
+    if let Some(cool) = desired.fan_poweroff_cool
+        && current.fan.fan_poweroff_cool != cool
+    {
+        out.push(Change::FanPoweroffCool {
+            current: current.fan.fan_poweroff_cool,
+            desired: cool,
+        });
+    }
+    if let Some(zero) = desired.fan_zero_speed
+        && current.fan.fan_zero_speed != i64::from(zero)
+    {
+        out.push(Change::FanZeroSpeed {
+            current: current.fan.fan_zero_speed,
+            desired: zero,
+        });
+    }
+    if let Some(offset) = desired.fan_temp_offset
+        && current.fan.fan_temp_offset != offset
+    {
+        out.push(Change::FanTempOffset {
+            current: current.fan.fan_temp_offset,
+            desired: offset,
+        });
+    }
+    if let Some(servers) = &desired.ntp_servers
+        && current.system.ntp_server != *servers
+    {
+        out.push(Change::NtpServers {
+            current: current.system.ntp_server.clone(),
+            desired: servers.clone(),
+        });
+    }
+    if desired.timezone.is_some() || desired.zonename.is_some() {
+        let now = SetSystemTimezone {
+            timezone: current.system.timezone.clone(),
+            zonename: current.system.zonename.clone(),
+        };
+        // setter needs both, missing one is kept
+        let want = SetSystemTimezone {
+            timezone: desired.timezone.clone().unwrap_or(now.timezone.clone()),
+            zonename: desired.zonename.clone().unwrap_or(now.zonename.clone()),
+        };
+        if want != now {
+            out.push(Change::Timezone {
+                current: now,
+                desired: want,
+            });
+        }
+    }
+    if let Some(data) = &desired.custom_data
+        && current.custom_data.as_ref() != Some(data)
+    {
+        out.push(Change::CustomData {
+            current: current.custom_data.clone().map(Box::new),
+            desired: Box::new(data.clone()),
+        });
+    }
+    if let Some(template) = &desired.hostname {
+        let hostname = render_hostname(template, &current.info);
+        if !hostname.eq_ignore_ascii_case(&current.system.hostname) {
+            out.push(Change::Hostname {
+                current: current.system.hostname.clone(),
+                desired: hostname,
+            });
+        }
+    }
+    out
+}
+
+/// What happened to one field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "error", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Dry run, setter wasn't called
+    Planned,
+    Applied,
+    Failed(String),
+}
+
+/// Result of one field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldResult {
+    pub field: &'static str,
+    pub current: String,
+    pub desired: String,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// Result of reconciling one miner, fields in drift only
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub addr: String,
+    pub dry_run: bool,
+    pub fields: Vec<FieldResult>,
+}
+
+impl Report {
+    /// Miner already matches desired state
+    pub fn in_sync(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Fields whose setters failed
+    pub fn failed(&self) -> impl Iterator<Item = &FieldResult> {
+        self.fields
+            .iter()
+            .filter(|f| matches!(f.outcome, Outcome::Failed(_)))
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.in_sync() {
+            return writeln!(f, "{}: in sync", self.addr);
+        }
+        for field in &self.fields {
+            let status = match &field.outcome {
+                Outcome::Planned => "planned".to_string(),
+                Outcome::Applied => "applied".to_string(),
+                Outcome::Failed(e) => format!("failed: {e}"),
+            };
+            writeln!(
+                f,
+                "{}: {}: {} -> {} [{status}]",
+                self.addr, field.field, field.current, field.desired
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Brings miners to [DesiredState]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reconciler {
+    /// Only report changes, don't run setters
+    pub dry_run: bool,
+}
+
+impl Reconciler {
+    pub fn new(dry_run: bool) -> Self {
+        Self { dry_run }
+    }
+
+    /// Read current state, diff and apply changes
+    ///
+    /// Fails only if current state can't be read,
+    /// failed setters are reported per field and don't stop the others
+    #[instrument(level = "info", skip_all, fields(addr = %actor.addr, dry_run = self.dry_run))]
+    pub async fn reconcile(&self, actor: &Actor, desired: &DesiredState) -> Result<Report> {
+        let current = CurrentState::read(actor).await?;
+        Ok(self.apply(actor, &current, desired).await)
+    }
+
+    /// Like [Reconciler::reconcile], desired state is picked from `spec`
+    ///
+    /// Groups may match address, MAC or serial number.
+    /// `None` if no group matches miner
+    #[instrument(level = "info", skip_all, fields(addr = %actor.addr, dry_run = self.dry_run))]
+    pub async fn reconcile_spec(&self, actor: &Actor, spec: &Spec) -> Result<Option<Report>> {
+        let current = CurrentState::read(actor).await?;
+        let id = MinerId {
+            addr: &actor.addr,
+            mac: current.info.network.as_ref().map(|n| n.mac.as_str()),
+            serial: current.info.miner.as_ref().map(|m| m.miner_sn.as_str()),
+        };
+        let Some(desired) = spec.desired(&id) else {
+            return Ok(None);
+        };
+        Ok(Some(self.apply(actor, &current, &desired).await))
+    }
+
+    async fn apply(&self, actor: &Actor, current: &CurrentState, desired: &DesiredState) -> Report {
+        let mut fields = Vec::new();
+        for change in diff(current, desired) {
+            let outcome = if self.dry_run {
+                Outcome::Planned
+            } else {
+                match change.apply(actor).await {
+                    Ok(()) => {
+                        info!(%change, "Applied.");
+                        Outcome::Applied
+                    }
+                    Err(e) => {
+                        warn!(%change, error = %e, "Can't apply.");
+                        Outcome::Failed(e.to_string())
+                    }
+                }
+            };
+            let (current, desired) = change.values();
+            fields.push(FieldResult {
+                field: change.field(),
+                current,
+                desired,
+                outcome,
+            });
+        }
+        Report {
+            addr: actor.addr.clone(),
+            dry_run: self.dry_run,
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        account::Account,
+        actor::mock::{self, ok_answer},
+        password::Password,
+    };
+
+    fn current() -> CurrentState {
+        CurrentState {
+            info: serde_json::from_value(json!({
+                "miner": {"miner-sn": "HTM1", "type": "M50S++_VK30"},
+                "network": {"ip": "10.10.1.17", "mac": "C4:11:04:AA:BB:CC"}
+            }))
+            .unwrap(),
+            miner: serde_json::from_value(mock::msg::<GetMinerSettingsResponse>(json!({
+                "power-limit": 3300, "power-mode": "Normal",
+                "power-percent": 100, "fast-boot": "enable"
+            })))
+            .unwrap(),
+            system: serde_json::from_value(mock::msg::<GetSystemSettingResponse>(json!({
+                "timezone": "CST-8", "zonename": "Asia/Shanghai",
+                "hostname": "wm-htm1", "ntp-server": ["pool.ntp.org"]
+            })))
+            .unwrap(),
+            pools: Some(vec![Pool {
+                url: "stratum+tcp://pool:3333".into(),
+                worker: "farm.1".into(),
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spec() {
+        let spec = Spec::parse_toml(
+            r#"
+            [[group]]
+            match = "*"
+            power_mode = "normal"
+            hostname = "wm-{serial}"
+
+            [[group]]
+            match = "10.10.1.*"
+            power_mode = "low"
+            pools = [{ pool = "stratum+tcp://pool:3333", worker = "farm.1" }]
+            "#,
+        )
+        .unwrap();
+        let rack = spec.desired(&MinerId::addr("10.10.1.17:4433")).unwrap();
+        assert_eq!(rack.power_mode, Some(PowerMode::Low));
+        assert_eq!(rack.hostname.as_deref(), Some("wm-{serial}"));
+        assert_eq!(rack.pools.unwrap()[0].password, "");
+        let other = spec.desired(&MinerId::addr("10.10.2.1")).unwrap();
+        assert_eq!(other.power_mode, Some(PowerMode::Normal));
+        assert!(
+            Spec::default()
+                .desired(&MinerId::addr("10.10.2.1"))
+                .is_none()
+        );
+        assert!(Spec::parse_toml("[[group]]\nmatch = 1").is_err());
+    }
+
+    #[test]
+    fn hostname() {
+        let info = current().info;
+        assert_eq!(
+            render_hostname("{model}-{ip}-{mac}", &info),
+            "M50S---VK30-10-10-1-17-c41104aabbcc"
+        );
+    }
+
+    #[test]
+    fn changes() {
+        let current = current();
+        let same = DesiredState {
+            pools: Some(vec![SetMinerPoolsParamItem {
+                pool: "stratum+tcp://pool:3333".into(),
+                worker: "farm.1".into(),
+                password: "x".into(),
+            }]),
+            power_mode: Some(PowerMode::Normal),
+            power_limit: Some(3300),
+            power_percent: Some(100),
+            fast_boot: Some(true),
+            zonename: Some("Asia/Shanghai".into()),
+            hostname: Some("WM-{serial}".into()),
+            ..Default::default()
+        };
+        assert!(diff(&current, &same).is_empty());
+
+        let changed = DesiredState {
+            power_limit: Some(3000),
+            power_percent: Some(50),
+            fast_boot: Some(false),
+            zonename: Some("UTC".into()),
+            hostname: Some("rack1-{serial}".into()),
+            pools: Some(vec![]),
+            ..Default::default()
+        };
+        let changes: Vec<_> = diff(&current, &changed)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "pools: [stratum+tcp://pool:3333 farm.1] -> []",
+                "power_limit: 3300 -> 3000",
+                "power_percent: 100 -> 50",
+                "fast_boot: enable -> disable",
+                "timezone: Asia/Shanghai (CST-8) -> UTC (CST-8)",
+                "hostname: wm-htm1 -> rack1-HTM1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reconcile() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let miner = mock::spawn(move |req| {
+            let cmd = req["cmd"].as_str()?;
+            let answer = match cmd {
+                "get.device.info" if req["param"] == "miner,network" => {
+                    json!({"miner": {"miner-sn": "HTM1"}})
+                }
+                // power limit needs api 3.0.3
+                "get.device.info" => return Some(mock::handshake_answer("3.0.3")),
+                "get.miner.setting" => mock::msg::<GetMinerSettingsResponse>(
+                    json!({"power-limit": 3300, "power-mode": "high"}),
+                ),
+                "get.fan.setting" => mock::msg::<GetFanSettingsResponse>(json!({})),
+                "get.system.setting" => {
+                    mock::msg::<GetSystemSettingResponse>(json!({"hostname": "wm-htm1"}))
+                }
+                "get.miner.status" => json!({"pools": []}),
+                "get.device.custom_data" => {
+                    mock::msg::<GetDeviceCustomDataResponse>(json!({"msg0": "rack1"}))
+                }
+                "set.miner.power_limit" => return Some(json!({"code": -1, "msg": "busy"})),
+                _ => {
+                    log.lock()
+                        .unwrap()
+                        .push((cmd.to_string(), req["param"].clone()));
+                    json!("ok")
+                }
+            };
+            Some(ok_answer(cmd, answer))
+        })
+        .await;
+        let actor = Actor::new(&miner, Account::Super, Password::Super)
+            .await
+            .unwrap();
+        let desired = DesiredState {
+            power_mode: Some(PowerMode::Low),
+            power_limit: Some(3000),
+            fan_temp_offset: Some(-2),
+            hostname: Some("wm-{serial}".into()),
+            ..Default::default()
+        };
+
+        let plan = Reconciler::new(true)
+            .reconcile(&actor, &desired)
+            .await
+            .unwrap();
+        assert_eq!(plan.fields.len(), 3);
+        assert!(plan.fields.iter().all(|f| f.outcome == Outcome::Planned));
+        assert!(sent.lock().unwrap().is_empty());
+
+        let report = Reconciler::new(false)
+            .reconcile(&actor, &desired)
+            .await
+            .unwrap();
+        assert_eq!(report.failed().count(), 1);
+        assert_eq!(report.failed().next().unwrap().field, "power_limit");
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [
+                ("set.miner.power_mode".to_string(), json!("low")),
+                ("set.fan.temp_offset".to_string(), json!("-2")),
+            ]
+        );
+        let spec =
+            Spec::parse_json(r#"{"group": [{"match": "htm1", "fan_temp_offset": 0}]}"#).unwrap();
+        let synced = Reconciler::new(false)
+            .reconcile_spec(&actor, &spec)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(synced.in_sync());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["fields"][0]["status"], "applied");
+        assert_eq!(json["fields"][1]["status"], "failed");
+    }
+}
@@ -2,20 +2,28 @@
 //!
 //! - Item: [Request]
 //! - (about [Command::SECURED], [Command::ENCRYPTED], [AuthData::new], [AuthData::encrypt]) ApiDoc: <https://apidoc.whatsminer.com/#api-Token-generate_token>
-use std::fmt::{Debug, Display};
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+};
 
 #[cfg(doc)]
-use crate::command::Command;
+use crate::command::{Command, raw::RawCommand};
 
 use serde::Serialize;
 
-use crate::auth_data::AuthData;
+use crate::{
+    auth_data::AuthData,
+    error::{Error, Result},
+};
 
 /// Represents a request for building public endpoints
 #[derive(Debug, Serialize)]
 pub struct Request {
     /// Command name
-    pub cmd: &'static str,
+    ///
+    /// Static for typed commands, owned for [RawCommand]
+    pub cmd: Cow<'static, str>,
     /// Authentication data (optional)
     #[serde(flatten)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,11 +52,35 @@ impl Display for Request {
 
 impl Request {
     /// Creates a new [Request] instance.
-    pub fn new(cmd: &'static str, auth_data: Option<AuthData>, parameter: Option<String>) -> Self {
+    pub fn new(
+        cmd: impl Into<Cow<'static, str>>,
+        auth_data: Option<AuthData>,
+        parameter: Option<String>,
+    ) -> Self {
         Self {
-            cmd,
+            cmd: cmd.into(),
             auth_data,
             parameter,
         }
     }
+
+    /// Creates a new [Request] instance, encrypting parameter if needed.
+    ///
+    /// - encryption: [AuthData::encrypt]
+    pub fn build(
+        cmd: impl Into<Cow<'static, str>>,
+        auth_data: Option<AuthData>,
+        parameter: Option<String>,
+        encrypted: bool,
+    ) -> Result<Self> {
+        let cmd = cmd.into();
+        let parameter = match (&auth_data, parameter) {
+            (Some(data), Some(x)) if encrypted => Some(data.encrypt(x.as_bytes())?),
+            (None, _) if encrypted => {
+                return Err(Error::CommandSholdHaveAuthData(cmd.into_owned()));
+            }
+            (_, parameter) => parameter,
+        };
+        Ok(Self::new(cmd, auth_data, parameter))
+    }
 }
@@ -3,13 +3,15 @@
 //! - msg is any T. Usual declare into [Command::Response]
 //! - Item: [Response]
 //! - (random command as example) ApiDoc: <https://apidoc.whatsminer.com/#api-Device-device_set_custom_data>
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[cfg(doc)]
 use crate::command::Command;
+use crate::error::{Error, Result};
 
 /// Represents a response from the WhatsMiner API.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Response<T> {
     // TODO: make enum
     /// Response code:
@@ -28,4 +30,48 @@ pub struct Response<T> {
     ///
     /// mb it's more then that
     pub desc: String,
+    /// Fields unknown to this version of the crate
+    #[serde(flatten, default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+}
+
+/// Fail with [Error::Api] if miner answered with non-zero code
+///
+/// Answers without `code` are passed as is
+pub fn check_code(json: &str, cmd: &str) -> Result<()> {
+    #[derive(Deserialize)]
+    struct Status {
+        code: Option<i64>,
+        #[serde(default)]
+        msg: Value,
+    }
+    let status: Status = serde_json::from_str(json).map_err(|e| Error::from(e).decoding(cmd))?;
+    match status.code {
+        Some(code) if code != 0 => Err(Error::Api {
+            cmd: cmd.to_string(),
+            code,
+            msg: match status.msg {
+                Value::String(s) => s,
+                v => v.to_string(),
+            },
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code() {
+        assert!(check_code(r#"{"code":0,"when":1,"msg":{},"desc":"a"}"#, "a").is_ok());
+        let e = check_code(
+            r#"{"code":-1,"when":1,"msg":"invalid token","desc":"a"}"#,
+            "a",
+        )
+        .unwrap_err();
+        assert!(e.is_auth_failure());
+        assert!(matches!(e, Error::Api { code: -1, .. }));
+    }
 }
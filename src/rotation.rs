@@ -0,0 +1,410 @@
+//! Define password rotation module
+//!
+//! Replaces passwords of miners with strong random ones
+//! via [SetUserChangePasswd] and keeps fleet reachable if interrupted:
+//! 1. New password is written to the journal **before** the miner is touched
+//! 2. Password is changed with the old secret
+//! 3. Miner is reconnected and authenticated with the new secret ([PROBE_CMD])
+//! 4. Verified passwords are merged into the credential file ([FileProvider]):
+//!    entries of rotated addresses are replaced and go first, other entries are kept
+//!
+//! Rerun with the same journal resumes: verified miners are skipped,
+//! pending ones are checked with the new secret first and with the old one next.
+//!
+//! - Item: [Rotation], [Journal], [generate_password]
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{
+    account::Account,
+    actor::{Actor, config::ActorConfig},
+    command::{raw::RawCommand, set_user_change_passwd::SetUserChangePasswd},
+    credentials::{CredentialProvider, Credentials, FileProvider, MemoryProvider, MinerId},
+    error::{Error, Result},
+    password::Password,
+};
+
+/// Default length of [generate_password]
+pub const DEFAULT_PASSWORD_LEN: usize = 16;
+
+/// Read-only command sent with token to check password
+///
+/// Miner rejects request with token built from wrong password (`-1`),
+/// nothing on miner is changed by the check
+pub const PROBE_CMD: &str = "get.miner.setting";
+
+const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789";
+
+/// Random password from OS random source
+///
+/// Alphanumeric without look-alike chars (`0`, `O`, `1`, `l`, `I`)
+pub fn generate_password(len: usize) -> Result<String> {
+    let mut out = String::with_capacity(len);
+    let mut buf = [0u8; 64];
+    // drop bytes over the biggest multiple of alphabet size, so chars are uniform
+    let limit = 256 - 256 % ALPHABET.len();
+    while out.len() < len {
+        getrandom::fill(&mut buf).map_err(|e| std::io::Error::other(e.to_string()))?;
+        out.extend(
+            buf.iter()
+                .filter(|&&b| (b as usize) < limit)
+                .map(|&b| ALPHABET[b as usize % ALPHABET.len()] as char)
+                .take(len - out.len()),
+        );
+    }
+    buf.zeroize();
+    Ok(out)
+}
+
+/// Progress of miner in [Journal]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationState {
+    /// New password is generated, miner may have it or not
+    Pending,
+    /// Miner accepted the new password
+    Verified,
+}
+
+/// Both secrets of miner, so it's reachable whatever happened
+#[derive(Clone, PartialEq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct JournalEntry {
+    #[zeroize(skip)]
+    pub account: Account,
+    pub old: String,
+    pub new: String,
+    #[zeroize(skip)]
+    pub state: RotationState,
+}
+
+impl std::fmt::Debug for JournalEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JournalEntry")
+            .field("account", &self.account)
+            .field("old", &"<hidden>")
+            .field("new", &"<hidden>")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// Rotation state by miner address, stored as JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+    pub miners: BTreeMap<String, JournalEntry>,
+}
+
+impl Journal {
+    /// Read journal, empty if file doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(mut raw) => {
+                let out = serde_json::from_str(&raw);
+                raw.zeroize();
+                Ok(out?)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write journal atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut raw = serde_json::to_string_pretty(self)?;
+        let out = crate::credentials::file::write_atomic(path.as_ref(), raw.as_bytes());
+        raw.zeroize();
+        out
+    }
+
+    /// Verified passwords by address
+    pub fn verified(&self) -> MemoryProvider {
+        let mut out = MemoryProvider::new();
+        for (addr, entry) in &self.miners {
+            if entry.state == RotationState::Verified {
+                out.insert(addr.clone(), entry.account, entry.new.as_str());
+            }
+        }
+        out
+    }
+}
+
+/// Rotation of passwords with journal
+///
+/// # Example
+/// ```rust,ignore
+/// use matroskin::credentials::EnvProvider;
+/// use matroskin::rotation::Rotation;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut rotation = Rotation::open("rotation.journal.json", "miners.toml")?;
+///     let report = rotation
+///         .rotate_all(&["10.10.1.17:4433", "10.10.1.18:4433"], &EnvProvider::default())
+///         .await;
+///     for (addr, result) in report {
+///         println!("{addr}: {result:?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Rotation {
+    /// Journal with both secrets, keep it until all miners are verified
+    pub journal_path: PathBuf,
+    /// Credential file with verified passwords, merged with its existing entries
+    pub output: PathBuf,
+    pub journal: Journal,
+    pub password_len: usize,
+    pub config: ActorConfig,
+}
+
+impl Rotation {
+    /// Open rotation, existing journal is resumed
+    pub fn open(journal: impl Into<PathBuf>, output: impl Into<PathBuf>) -> Result<Self> {
+        let journal_path = journal.into();
+        Ok(Self {
+            journal: Journal::load(&journal_path)?,
+            journal_path,
+            output: output.into(),
+            password_len: DEFAULT_PASSWORD_LEN,
+            config: ActorConfig::default(),
+        })
+    }
+
+    /// Rotate every miner, current credentials come from `provider`
+    ///
+    /// Provider's fallback is used, when it has no entry for the address
+    ///
+    /// Miners are processed one by one, a failure doesn't stop the rest
+    pub async fn rotate_all(
+        &mut self,
+        addrs: &[impl AsRef<str>],
+        provider: &dyn CredentialProvider,
+    ) -> Vec<(String, Result<()>)> {
+        let mut out = Vec::with_capacity(addrs.len());
+        for addr in addrs {
+            let addr = addr.as_ref();
+            let current = match provider.credentials(&MinerId::addr(addr)) {
+                Ok(None) => provider.fallback(),
+                found => found,
+            };
+            let result = match current {
+                Ok(Some(current)) => self.rotate(addr, &current).await,
+                Ok(None) => Err(Error::NoCredentials(addr.to_string())),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &result {
+                warn!(%addr, error = %e, "Rotation failed.");
+            }
+            out.push((addr.to_string(), result));
+        }
+        out
+    }
+
+    #[instrument(level = "info", skip(self, current))]
+    /// Rotate password of one miner
+    ///
+    /// `current` is used only if journal doesn't know the miner yet
+    pub async fn rotate(&mut self, addr: &str, current: &Credentials) -> Result<()> {
+        if !self.journal.miners.contains_key(addr) {
+            let entry = JournalEntry {
+                account: current.account,
+                old: current.password.as_ref().to_string(),
+                new: generate_password(self.password_len)?,
+                state: RotationState::Pending,
+            };
+            self.journal.miners.insert(addr.to_string(), entry);
+            self.journal.save(&self.journal_path)?;
+        }
+        let entry = self.journal.miners[addr].clone();
+        if entry.state == RotationState::Verified {
+            info!("Already rotated.");
+            return Ok(());
+        }
+
+        // interrupted after change, but before verification
+        if self.verify(addr, entry.account, &entry.new).await.is_err() {
+            info!("Changing password.");
+            let actor =
+                Actor::with_config(addr, entry.account, entry.old.as_str(), self.config.clone())
+                    .await?;
+            actor
+                .send(&SetUserChangePasswd::new(
+                    entry.account,
+                    entry.old.as_str(),
+                    entry.new.as_str(),
+                ))
+                .await?;
+            self.verify(addr, entry.account, &entry.new).await?;
+        }
+
+        info!("New password verified.");
+        if let Some(entry) = self.journal.miners.get_mut(addr) {
+            entry.state = RotationState::Verified;
+        }
+        self.journal.save(&self.journal_path)?;
+        self.save_output()
+    }
+
+    /// Merge verified passwords into [Rotation::output]
+    ///
+    /// Verified entries go first, so they win over wildcards of existing file
+    fn save_output(&self) -> Result<()> {
+        let mut merged = self.journal.verified();
+        let existing = match FileProvider::load(&self.output) {
+            Ok(file) => file.inner.entries,
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        merged.entries.extend(
+            existing
+                .into_iter()
+                .filter(|(pattern, _)| !self.journal.miners.contains_key(pattern)),
+        );
+        FileProvider::save(&self.output, &merged)
+    }
+
+    /// Reconnect and authenticate with `password`
+    ///
+    /// [PROBE_CMD] is sent with token, which is built from `password`
+    async fn verify(&self, addr: &str, account: Account, password: &str) -> Result<()> {
+        let actor =
+            Actor::with_config(addr, account, Password::from(password), self.config.clone())
+                .await?;
+        let mut probe = RawCommand::new(PROBE_CMD);
+        probe.secured = true;
+        probe.execute(&actor).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{actor::mock, auth_data::AuthData, credentials::MemoryProvider};
+
+    /// Fake miner, which checks tokens and changes password
+    async fn miner(password: Arc<Mutex<String>>, changes: Arc<AtomicU32>) -> String {
+        mock::spawn(move |req: &Value| {
+            let cmd = req["cmd"].as_str()?;
+            if cmd == "get.device.info" {
+                return None;
+            }
+            let current = password.lock().unwrap().clone();
+            let auth = AuthData::with_ts(
+                cmd,
+                Account::Super,
+                &current,
+                mock::SALT,
+                req["ts"].as_u64()?,
+            )
+            .ok()?;
+            if serde_json::to_value(&auth).ok()?["token"] != req["token"] {
+                return Some(json!({"code": -1, "when": 1, "msg": "invalid token", "desc": cmd}));
+            }
+            if cmd == PROBE_CMD {
+                return Some(mock::ok_answer(cmd, json!({})));
+            }
+            let param = auth.decrypt(req["param"].as_str()?).ok()?;
+            let param: Value = serde_json::from_slice(&param).ok()?;
+            if param["old"] != current {
+                return Some(json!({"code": -1, "when": 1, "msg": "wrong password", "desc": cmd}));
+            }
+            // verification never writes, every change request counts
+            changes.fetch_add(1, Ordering::SeqCst);
+            *password.lock().unwrap() = param["new"].as_str()?.to_string();
+            Some(mock::ok_answer(cmd, json!("ok")))
+        })
+        .await
+    }
+
+    #[test]
+    fn password() {
+        let a = generate_password(DEFAULT_PASSWORD_LEN).unwrap();
+        assert_eq!(a.len(), DEFAULT_PASSWORD_LEN);
+        assert!(a.bytes().all(|b| ALPHABET.contains(&b)));
+        assert_ne!(a, generate_password(DEFAULT_PASSWORD_LEN).unwrap());
+    }
+
+    #[tokio::test]
+    async fn rotate() {
+        let dir = std::env::temp_dir();
+        let journal = dir.join(format!("matroskin-rotation-{}.json", std::process::id()));
+        let output = dir.join(format!("matroskin-rotated-{}.toml", std::process::id()));
+        let password = Arc::new(Mutex::new("super".to_string()));
+        let changes = Arc::new(AtomicU32::new(0));
+        let addr = miner(password.clone(), changes.clone()).await;
+        let mut provider = MemoryProvider::new();
+        provider.insert("*", Account::Super, "super");
+        // operator's file: stale entry of rotated miner and unrelated ones
+        std::fs::write(
+            &output,
+            format!(
+                "[[miner]]\nmatch = \"10.10.9.*\"\npassword = \"keep\"\n\n\
+                 [[miner]]\nmatch = \"{addr}\"\npassword = \"stale\"\n\n\
+                 [[miner]]\nmatch = \"*\"\npassword = \"super\"\n"
+            ),
+        )
+        .unwrap();
+
+        let mut rotation = Rotation::open(&journal, &output).unwrap();
+        let report = rotation.rotate_all(&[&addr], &provider).await;
+        assert!(report[0].1.is_ok());
+        let new = rotation.journal.miners[&addr].new.clone();
+        assert_eq!(*password.lock().unwrap(), new);
+        assert_eq!(changes.swap(0, Ordering::SeqCst), 1);
+        let saved = FileProvider::load(&output).unwrap();
+        let c = saved.credentials(&MinerId::addr(&addr)).unwrap().unwrap();
+        assert_eq!(c.password.as_ref(), new);
+        let patterns: Vec<_> = saved
+            .inner
+            .entries
+            .iter()
+            .map(|(p, _)| p.as_str())
+            .collect();
+        assert_eq!(patterns, [addr.as_str(), "10.10.9.*", "*"]);
+
+        // interrupted after change: journal still says pending
+        let mut interrupted = Journal::load(&journal).unwrap();
+        interrupted.miners.get_mut(&addr).unwrap().state = RotationState::Pending;
+        interrupted.save(&journal).unwrap();
+        let mut rotation = Rotation::open(&journal, &output).unwrap();
+        let report = rotation.rotate_all(&[&addr], &provider).await;
+        assert!(report[0].1.is_ok());
+        assert_eq!(changes.load(Ordering::SeqCst), 0);
+        assert_eq!(*password.lock().unwrap(), new);
+        assert_eq!(
+            rotation.journal.miners[&addr].state,
+            RotationState::Verified
+        );
+
+        // wrong current password keeps miner pending with both secrets
+        let other = miner(
+            Arc::new(Mutex::new("custom".to_string())),
+            Arc::new(AtomicU32::new(0)),
+        )
+        .await;
+        let report = rotation.rotate_all(&[&other], &provider).await;
+        assert!(report[0].1.as_ref().unwrap_err().is_auth_failure());
+        assert_eq!(
+            Journal::load(&journal).unwrap().miners[&other].state,
+            RotationState::Pending
+        );
+
+        std::fs::remove_file(&journal).unwrap();
+        std::fs::remove_file(&output).unwrap();
+    }
+}
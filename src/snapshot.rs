@@ -0,0 +1,498 @@
+//! Define snapshot module
+//!
+//! [Snapshot] keeps everything a miner reports about itself in a versioned JSON file,
+//! e.g. before maintenance. Two snapshots can be compared ([diff]),
+//! and a snapshot can be written back ([Snapshot::restore]) via [Reconciler],
+//! so only settings which changed since are set.
+//!
+//! - Item: [Snapshot], [RestoreOptions], [Difference], [diff]
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{instrument, warn};
+
+use crate::{
+    actor::Actor,
+    command::{
+        get_device_custom_data::{GetDeviceCustomData, GetDeviceCustomDataResponse},
+        get_device_info::{DeviceInfo, GetDeviceInfo, GetDeviceInfoParam},
+        get_fan_setting::{GetFanSettings, GetFanSettingsResponse},
+        get_miner_setting::{GetMinerSettings, GetMinerSettingsResponse},
+        get_miner_status::{GetMinerStatus, GetMinerStatusParam},
+        get_system_setting::{GetSystemSetting, GetSystemSettingResponse},
+        set_miner_pools::SetMinerPoolsParamItem,
+    },
+    credentials::file::write_atomic,
+    error::{Error, Result},
+    reconcile::{DesiredState, Pool, Reconciler, Report},
+};
+
+/// Version of snapshot format, written to every file
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything miner reports about itself
+///
+/// Sections keep fields unknown to this version of the crate, so nothing is lost.
+/// Optional sections are `None` if miner didn't answer them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// [SNAPSHOT_VERSION] of writer
+    pub version: u32,
+    /// Address, which snapshot was taken from
+    pub addr: String,
+    /// Unix time of capture
+    pub taken_at: u64,
+    pub device_info: DeviceInfo,
+    pub miner: GetMinerSettingsResponse,
+    pub fan: GetFanSettingsResponse,
+    pub system: GetSystemSettingResponse,
+    #[serde(default)]
+    pub custom_data: Option<GetDeviceCustomDataResponse>,
+    /// Pools as reported by `get.miner.status`
+    #[serde(default)]
+    pub pools: Option<Vec<Map<String, Value>>>,
+}
+
+/// Settings of [Snapshot::restore]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreOptions {
+    /// Restore on miner with other serial number than snapshot
+    pub allow_other_miner: bool,
+    /// Pool passwords in order of snapshot pools, pools aren't restored without them
+    pub pool_passwords: Option<Vec<String>>,
+}
+
+impl Snapshot {
+    /// Read every section from miner
+    #[instrument(level = "info", skip_all, fields(addr = %actor.addr))]
+    pub async fn capture(actor: &Actor) -> Result<Self> {
+        let device_info = actor
+            .send(&GetDeviceInfo(GetDeviceInfoParam {
+                miner: true,
+                power: true,
+                network: true,
+                system: true,
+                salt: false,
+                error_code: true,
+            }))
+            .await?
+            .msg;
+        let miner = actor.send(&GetMinerSettings).await?.msg;
+        let fan = actor.send(&GetFanSettings).await?.msg;
+        let system = actor.send(&GetSystemSetting).await?.msg;
+        let custom_data = match actor.send(&GetDeviceCustomData).await {
+            Ok(data) => Some(data.msg),
+            Err(e) => {
+                warn!(error = %e, "Can't read custom data.");
+                None
+            }
+        };
+        let pools = match actor
+            .send(&GetMinerStatus(GetMinerStatusParam {
+                summary: false,
+                pools: true,
+                edevs: false,
+            }))
+            .await
+        {
+            Ok(status) => status.msg.pools,
+            Err(e) => {
+                warn!(error = %e, "Can't read pools.");
+                None
+            }
+        };
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            addr: actor.addr.clone(),
+            taken_at: actor.now()?,
+            device_info,
+            miner,
+            fan,
+            system,
+            custom_data,
+            pools,
+        })
+    }
+
+    /// Read snapshot file, newer versions are refused
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let snapshot: Self =
+            serde_json::from_str(raw).map_err(|e| Error::Snapshot(e.to_string()))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(Error::Snapshot(format!(
+                "version {} is newer than supported {SNAPSHOT_VERSION}",
+                snapshot.version
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    /// Write snapshot as pretty JSON, atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_atomic(
+            path.as_ref(),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )
+    }
+
+    /// File name like `HTM1-1700000000.json`, serial number or address if serial is unknown
+    pub fn file_name(&self) -> String {
+        let id = self.miner_sn().unwrap_or(&self.addr);
+        let id: String = id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("{id}-{}.json", self.taken_at)
+    }
+
+    /// Settings of snapshot, which have setters
+    ///
+    /// Pools are left unset: miners don't report pool passwords,
+    /// and setting pools without them would wipe passwords on miner. See [RestoreOptions::pool_passwords]
+    pub fn desired_state(&self) -> DesiredState {
+        DesiredState {
+            pools: None,
+            power_mode: self.miner.power_mode.parse().ok(),
+            power_limit: u32::try_from(self.miner.power_limit).ok(),
+            power_percent: self.miner.power_percent.and_then(|p| u8::try_from(p).ok()),
+            fast_boot: self.miner.fast_boot_enabled(),
+            fan_poweroff_cool: Some(self.fan.fan_poweroff_cool),
+            fan_zero_speed: u8::try_from(self.fan.fan_zero_speed).ok(),
+            fan_temp_offset: Some(self.fan.fan_temp_offset),
+            ntp_servers: Some(self.system.ntp_server.clone()),
+            timezone: Some(self.system.timezone.clone()),
+            zonename: Some(self.system.zonename.clone()),
+            // literal name, braces would be read as template
+            hostname: Some(self.system.hostname.replace(['{', '}'], "")),
+            custom_data: self.custom_data.clone(),
+        }
+    }
+
+    /// Pools of snapshot with `passwords`, one per pool in order of snapshot
+    ///
+    /// `None` if snapshot has no pools, [Error::Snapshot] if number of passwords differs
+    pub fn pools_with(&self, passwords: &[String]) -> Result<Option<Vec<SetMinerPoolsParamItem>>> {
+        let Some(pools) = &self.pools else {
+            return Ok(None);
+        };
+        if pools.len() != passwords.len() {
+            return Err(Error::Snapshot(format!(
+                "snapshot has {} pools, {} passwords given",
+                pools.len(),
+                passwords.len()
+            )));
+        }
+        Ok(Some(
+            pools
+                .iter()
+                .map(Pool::from_map)
+                .zip(passwords)
+                .map(|(pool, password)| SetMinerPoolsParamItem {
+                    pool: pool.url,
+                    worker: pool.worker,
+                    password: password.clone(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Serial number of miner, which snapshot was taken from
+    pub fn miner_sn(&self) -> Option<&str> {
+        self.device_info
+            .miner
+            .as_ref()
+            .map(|m| m.miner_sn.as_str())
+            .filter(|sn| !sn.is_empty())
+    }
+
+    /// Write settings of snapshot back to miner, only fields which differ are set
+    ///
+    /// Fails with [Error::Snapshot] if serial number of miner differs from snapshot,
+    /// unless [RestoreOptions::allow_other_miner] is set
+    pub async fn restore(
+        &self,
+        actor: &Actor,
+        reconciler: Reconciler,
+        options: &RestoreOptions,
+    ) -> Result<Report> {
+        if !options.allow_other_miner {
+            let info = actor
+                .send(&GetDeviceInfo(GetDeviceInfoParam {
+                    miner: true,
+                    power: false,
+                    network: false,
+                    system: false,
+                    salt: false,
+                    error_code: false,
+                }))
+                .await?
+                .msg;
+            let live = info
+                .miner
+                .as_ref()
+                .map(|m| m.miner_sn.as_str())
+                .filter(|sn| !sn.is_empty());
+            if live.is_none() || live != self.miner_sn() {
+                return Err(Error::Snapshot(format!(
+                    "snapshot of {} can't be restored on {}",
+                    self.miner_sn().unwrap_or("unknown miner"),
+                    live.unwrap_or("unknown miner")
+                )));
+            }
+        }
+        let mut desired = self.desired_state();
+        if let Some(passwords) = &options.pool_passwords {
+            desired.pools = self.pools_with(passwords)?;
+        }
+        reconciler.reconcile(actor, &desired).await
+    }
+
+    /// Sections as one JSON object, for [diff]
+    fn sections(&self) -> Value {
+        serde_json::json!({
+            "device_info": self.device_info,
+            "miner": self.miner,
+            "fan": self.fan,
+            "system": self.system,
+            "custom_data": self.custom_data,
+            "pools": self.pools,
+        })
+    }
+}
+
+/// Field, which differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Difference {
+    /// Dotted path, like `system.hostname`
+    pub path: String,
+    /// `None` if field is missing in left snapshot
+    pub left: Option<Value>,
+    /// `None` if field is missing in right snapshot
+    pub right: Option<Value>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |v: &Option<Value>| v.as_ref().map_or("-".to_string(), Value::to_string);
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            show(&self.left),
+            show(&self.right)
+        )
+    }
+}
+
+/// Fields, which differ between snapshots, ordered by path
+///
+/// Works for the same miner over time as well as for two miners.
+/// Arrays are compared as a whole, metadata (`addr`, `taken_at`) is skipped
+pub fn diff(left: &Snapshot, right: &Snapshot) -> Vec<Difference> {
+    let mut l = BTreeMap::new();
+    let mut r = BTreeMap::new();
+    flatten("", left.sections(), &mut l);
+    flatten("", right.sections(), &mut r);
+    let mut out = Vec::new();
+    for (path, left) in &l {
+        match r.remove(path) {
+            Some(right) if right == *left => {}
+            right => out.push(Difference {
+                path: path.clone(),
+                left: Some(left.clone()),
+                right,
+            }),
+        }
+    }
+    out.extend(r.into_iter().map(|(path, right)| Difference {
+        path,
+        left: None,
+        right: Some(right),
+    }));
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    out
+}
+
+/// Leaves of objects by dotted path, `null` leaves are skipped
+fn flatten(prefix: &str, value: Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&path, value, out);
+            }
+        }
+        Value::Null => {}
+        leaf => {
+            out.insert(prefix.to_string(), leaf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        account::Account,
+        actor::mock::{self, ok_answer},
+        password::Password,
+    };
+
+    /// Miner with hostname `name` and serial number `sn`, records setters
+    async fn miner(name: &'static str, sn: &'static str, sent: Arc<Mutex<Vec<String>>>) -> String {
+        mock::spawn(move |req| {
+            let cmd = req["cmd"].as_str()?;
+            let answer = match cmd {
+                "get.device.info" if !req["param"].as_str()?.contains("miner") => return None,
+                "get.device.info" => json!({
+                    "miner": {"miner-sn": sn, "type": "M50S"},
+                    "network": {"hostname": name}
+                }),
+                "get.miner.setting" => mock::msg::<GetMinerSettingsResponse>(json!({
+                    "power-limit": 3300, "power-mode": "normal",
+                    "power-percent": 80, "fast-boot": "enable"
+                })),
+                "get.fan.setting" => mock::msg::<GetFanSettingsResponse>(
+                    json!({"fan-temp-offset": 0, "fan-zero-speed": 1}),
+                ),
+                "get.system.setting" => mock::msg::<GetSystemSettingResponse>(json!({
+                    "hostname": name, "timezone": "CST-8", "zonename": "Asia/Shanghai",
+                    "ntp-server": ["pool.ntp.org"]
+                })),
+                "get.device.custom_data" => {
+                    mock::msg::<GetDeviceCustomDataResponse>(json!({"msg0": "rack1"}))
+                }
+                "get.miner.status" => {
+                    json!({"pools": [{"url": "stratum+tcp://pool:3333", "user": "farm.1"}]})
+                }
+                _ => {
+                    sent.lock().unwrap().push(format!("{cmd} {}", req["param"]));
+                    json!("ok")
+                }
+            };
+            Some(ok_answer(cmd, answer))
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn capture_diff_restore() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let a = Actor::new(
+            &miner("wm-a", "HTM1", sent.clone()).await,
+            Account::Super,
+            Password::Super,
+        )
+        .await
+        .unwrap();
+        let b = Actor::new(
+            &miner("wm-b", "HTM2", sent.clone()).await,
+            Account::Super,
+            Password::Super,
+        )
+        .await
+        .unwrap();
+
+        let snap_a = Snapshot::capture(&a).await.unwrap();
+        let snap_b = Snapshot::capture(&b).await.unwrap();
+        assert_eq!(snap_a.version, SNAPSHOT_VERSION);
+        assert_eq!(snap_a.custom_data.as_ref().unwrap().msg0, "rack1");
+        assert!(snap_a.file_name().starts_with("HTM1-"));
+        let desired = snap_a.desired_state();
+        assert_eq!(desired.power_percent, Some(80));
+        assert_eq!(desired.fast_boot, Some(true));
+        assert!(desired.pools.is_none());
+        let pools = snap_a.pools_with(&["x".to_string()]).unwrap().unwrap();
+        assert_eq!(pools[0].worker, "farm.1");
+        assert_eq!(pools[0].password, "x");
+        assert!(matches!(snap_a.pools_with(&[]), Err(Error::Snapshot(_))));
+
+        let changes: Vec<_> = diff(&snap_a, &snap_b)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                r#"device_info.miner.miner-sn: "HTM1" -> "HTM2""#,
+                r#"device_info.network.hostname: "wm-a" -> "wm-b""#,
+                r#"system.hostname: "wm-a" -> "wm-b""#,
+            ]
+        );
+        assert!(diff(&snap_a, &snap_a).is_empty());
+
+        let dir = std::env::temp_dir().join(format!("matroskin-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(snap_a.file_name());
+        snap_a.save(&path).unwrap();
+        let loaded = Snapshot::load(&path).unwrap();
+        assert_eq!(loaded, snap_a);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Own snapshot with pool passwords, pools are the same
+        let pools = RestoreOptions {
+            pool_passwords: Some(vec!["x".to_string()]),
+            ..Default::default()
+        };
+        let report = loaded
+            .restore(&a, Reconciler::new(false), &pools)
+            .await
+            .unwrap();
+        assert!(report.fields.is_empty());
+
+        // Snapshot of `a` isn't restored on `b` by default
+        let e = loaded
+            .restore(&b, Reconciler::new(false), &RestoreOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(e, Error::Snapshot(_)), "{e}");
+        assert!(sent.lock().unwrap().is_empty());
+
+        // Restoring snapshot of `a` on `b` changes only hostname
+        let other = RestoreOptions {
+            allow_other_miner: true,
+            ..Default::default()
+        };
+        let report = loaded
+            .restore(&b, Reconciler::new(false), &other)
+            .await
+            .unwrap();
+        assert_eq!(report.fields.len(), 1);
+        assert_eq!(report.fields[0].field, "hostname");
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [r#"set.system.hostname "wm-a""#.to_string()]
+        );
+    }
+
+    #[test]
+    fn version() {
+        let raw = json!({
+            "version": SNAPSHOT_VERSION + 1, "addr": "10.0.0.1:4433", "taken_at": 0,
+            "device_info": {},
+            "miner": mock::msg::<GetMinerSettingsResponse>(json!({})),
+            "fan": mock::msg::<GetFanSettingsResponse>(json!({})),
+            "system": mock::msg::<GetSystemSettingResponse>(json!({})),
+        })
+        .to_string();
+        assert!(matches!(Snapshot::from_json(&raw), Err(Error::Snapshot(_))));
+        let raw = raw.replace(
+            &format!("\"version\":{}", SNAPSHOT_VERSION + 1),
+            &format!("\"version\":{SNAPSHOT_VERSION}"),
+        );
+        assert!(Snapshot::from_json(&raw).unwrap().pools.is_none());
+    }
+}
@@ -0,0 +1,75 @@
+//! Check `#[derive(Command)]` from outside of crate
+use matroskin::{
+    account::Account,
+    auth_data::AuthData,
+    command::{Command, params_to_string},
+    error::Result,
+};
+use serde::Serialize;
+
+#[derive(Debug, Default, Command)]
+#[command(
+    name = "set.system.led",
+    secured,
+    snapshot = r#"{"cmd":"set.system.led","param":"auto"}"#
+)]
+pub struct SetSystemLed(pub Led);
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Led {
+    #[default]
+    Auto,
+    Manual,
+}
+
+#[derive(Debug, Default, Serialize, Command)]
+#[command(name = "set.system.hostname", secured, encrypted)]
+pub struct SetSystemHostname {
+    pub hostname: String,
+}
+
+#[derive(Debug, Default, Command)]
+#[command(name = "set.miner.fastboot", secured, params_with = fastboot)]
+pub struct SetMinerFastboot(pub bool);
+
+fn fastboot(cmd: &SetMinerFastboot) -> Result<Option<String>> {
+    params_to_string(&if cmd.0 { "enable" } else { "disable" })
+}
+
+#[test]
+fn consts() {
+    assert_eq!(SetSystemLed::CMD_NAME, "set.system.led");
+    const {
+        assert!(SetSystemLed::SECURED);
+        assert!(!SetSystemLed::ENCRYPTED);
+        assert!(SetSystemHostname::ENCRYPTED);
+    }
+}
+
+#[test]
+fn params() {
+    assert_eq!(
+        SetSystemLed(Led::Manual).params().unwrap().as_deref(),
+        Some("manual")
+    );
+    let cmd = SetSystemHostname {
+        hostname: "matroskin".to_string(),
+    };
+    assert_eq!(
+        cmd.params().unwrap().as_deref(),
+        Some(r#"{"hostname":"matroskin"}"#)
+    );
+    assert_eq!(
+        SetMinerFastboot(true).params().unwrap().as_deref(),
+        Some("enable")
+    );
+}
+
+#[test]
+fn encrypted() {
+    let cmd = SetSystemHostname::default();
+    assert!(cmd.to_request(None).is_err());
+    let auth = AuthData::new::<SetSystemHostname>(Account::Super, "super", "salt").unwrap();
+    assert!(cmd.to_request(Some(auth)).is_ok());
+}